@@ -0,0 +1,63 @@
+use anyhow::Result;
+use ike_logger::{log, Logger};
+use ike_toml::{Dependency, IkeToml};
+
+use super::cli::Cli;
+use crate::error::IkeError::{CouldNotFindIkeToml, RequiresIkeToml};
+use crate::install::{
+    manifest::{parse_package_spec, ManifestEditor},
+    registry::RegistryClient,
+    Installer,
+};
+
+pub fn add_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
+    let pkg = match cli.pkg {
+        Some(pkg) => pkg,
+        None => return Err(CouldNotFindIkeToml.into()),
+    };
+    if pkg.is_package_json() {
+        return Err(RequiresIkeToml("add").into());
+    }
+    let file_path = pkg.file_path.clone().ok_or(CouldNotFindIkeToml)?;
+
+    let spec = sub_matches.get_one::<String>("package").unwrap();
+    let dev = sub_matches.get_flag("dev");
+    let (name, range) = parse_package_spec(spec);
+
+    let mut dep = Dependency {
+        version: range.map(str::to_string),
+        path: sub_matches.get_one::<String>("path").cloned(),
+        git: sub_matches.get_one::<String>("git").cloned(),
+        branch: sub_matches.get_one::<String>("branch").cloned(),
+        rev: sub_matches.get_one::<String>("rev").cloned(),
+    };
+
+    // Without a range the latest version is added, as a caret range like npm does
+    if dep.version.is_none() && dep.path.is_none() && dep.git.is_none() {
+        let registry = RegistryClient::registry_url(pkg.toml.install.registry.as_deref());
+        let latest = RegistryClient::new(&registry).resolve(name, "latest")?;
+        dep.version = Some(format!("^{}", latest.version));
+    }
+
+    dep.validate(name)?;
+
+    let mut editor = ManifestEditor::open(&file_path)?;
+    editor.add_dependency(name, &dep, dev);
+    editor.save()?;
+
+    log!(
+        info,
+        "added <cyan>{}<r> to <cyan>{}<r>",
+        name,
+        if dev {
+            "dev-dependencies"
+        } else {
+            "dependencies"
+        }
+    );
+
+    let pkg = IkeToml::from_file(file_path)?.with_workspace()?;
+    Installer::new(pkg, false, false)?.install()?;
+
+    Ok(())
+}
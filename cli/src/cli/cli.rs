@@ -1,12 +1,19 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use super::{run_command::run_command, style, test_command::test_command};
-use crate::error::IkeError::FailedToParseRoot;
+use super::{
+    add_command::add_command, install_command::install_command, pack_command::pack_command,
+    publish_command::publish_command, remove_command::remove_command, run_command::run_command,
+    style, test_command::test_command, toml_command::toml_command,
+};
+use crate::dotenv::load_env_files;
+use crate::error::IkeError::{CouldNotFindIkeToml, FailedToParseRoot, NoMatchingWorkspaceMembers};
 use crate::fs::normalize_path;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use clap::{Arg, Command};
-use ike_toml::IkeToml;
+use clap::{Arg, ArgAction, Command};
+use ike_core::permissions::{PermissionKind, Permissions};
+use ike_logger::{elog, Logger};
+use ike_toml::{AllowList, IkeToml};
 
 #[derive(Clone, Debug)]
 pub struct Cli {
@@ -42,23 +49,150 @@ impl Cli {
             .about("Simple JavaScript runtime")
             .subcommand(
                 Command::new("run")
-                    .about("Run a JavaScript file")
-                    .args([Arg::new("entry")
-                        .help("name of the script.")
-                        .required(false)
-                        .num_args(1)])
+                    .about("Run a JavaScript file or a task from ike.toml")
+                    .args([
+                        Arg::new("entry")
+                            .help("name of the script.")
+                            .required(false)
+                            .num_args(1),
+                        Self::filter_arg(),
+                        Self::env_file_arg(),
+                        Arg::new("unhandled-rejections")
+                            .help("What to do with a rejected promise nothing handles")
+                            .long("unhandled-rejections")
+                            .value_parser(["strict", "warn", "none"]),
+                    ])
+                    .args(Self::permission_args())
+                    .args(Self::features_args())
                     .args(Self::global_args()),
             )
             .subcommand(
                 Command::new("test")
                     .about("Run tests")
-                    .args([Arg::new("pattern")
-                        .help("Pattern to match test files")
-                        .required(false)
-                        .short('p')
-                        .long("pattern")])
+                    .args([
+                        Arg::new("pattern")
+                            .help("Pattern to match test files")
+                            .required(false)
+                            .short('p')
+                            .long("pattern"),
+                        Arg::new("include")
+                            .help("Globs of test files, replaces `include` of [test]")
+                            .long("include")
+                            .action(ArgAction::Append),
+                        Arg::new("exclude")
+                            .help("Globs of files that aren't tests")
+                            .long("exclude")
+                            .action(ArgAction::Append),
+                        Arg::new("setup")
+                            .help("File to evaluate before each test file")
+                            .long("setup")
+                            .action(ArgAction::Append),
+                        Arg::new("timeout")
+                            .help("Milliseconds a single test may take")
+                            .long("timeout")
+                            .value_parser(clap::value_parser!(u64)),
+                        Arg::new("reporter")
+                            .help("How results are printed")
+                            .long("reporter")
+                            .value_parser(["pretty", "dot"]),
+                        Self::filter_arg(),
+                        Self::env_file_arg(),
+                    ])
+                    .args(Self::permission_args())
+                    .args(Self::features_args())
+                    .args(Self::global_args()),
+            )
+            .subcommand(
+                Command::new("install")
+                    .about("Install dependencies from ike.toml")
+                    .args([
+                        Arg::new("frozen")
+                            .help(
+                                "Fail if ike.lock is missing or out of date instead of updating it",
+                            )
+                            .long("frozen")
+                            .action(ArgAction::SetTrue),
+                        Arg::new("allow-scripts")
+                            .help(
+                                "Run lifecycle scripts of all dependencies, not only trusted ones",
+                            )
+                            .long("allow-scripts")
+                            .action(ArgAction::SetTrue),
+                    ])
+                    .args(Self::features_args())
+                    .args(Self::global_args()),
+            )
+            .subcommand(
+                Command::new("add")
+                    .about("Add a dependency to ike.toml and install it")
+                    .args([
+                        Arg::new("package")
+                            .help(
+                                "Name of the package, optionally with a version range: name@^1.0.0",
+                            )
+                            .required(true)
+                            .num_args(1),
+                        Arg::new("dev")
+                            .help("Add it to dev-dependencies")
+                            .short('D')
+                            .long("dev")
+                            .action(ArgAction::SetTrue),
+                        Arg::new("path")
+                            .help("Local path of the package")
+                            .long("path")
+                            .conflicts_with("git"),
+                        Arg::new("git")
+                            .help("Git repository of the package")
+                            .long("git"),
+                        Arg::new("branch")
+                            .help("Branch of the git repository")
+                            .long("branch")
+                            .requires("git"),
+                        Arg::new("rev")
+                            .help("Commit of the git repository")
+                            .long("rev")
+                            .requires("git"),
+                    ])
+                    .args(Self::global_args()),
+            )
+            .subcommand(
+                Command::new("remove")
+                    .about("Remove a dependency from ike.toml")
+                    .args([Arg::new("package")
+                        .help("Name of the package")
+                        .required(true)
+                        .num_args(1)])
                     .args(Self::global_args()),
             )
+            .subcommand(
+                Command::new("pack")
+                    .about("Pack the package into a tarball")
+                    .args(Self::pack_args())
+                    .args([Arg::new("out-dir")
+                        .help("Directory to write the tarball to")
+                        .long("out-dir")
+                        .short('o')])
+                    .args(Self::global_args()),
+            )
+            .subcommand(
+                Command::new("publish")
+                    .about("Publish the package to a registry")
+                    .args(Self::pack_args())
+                    .args([Arg::new("registry")
+                        .help("Registry URL or directory to publish to")
+                        .long("registry")])
+                    .args(Self::global_args()),
+            )
+            .subcommand(
+                Command::new("toml")
+                    .about("Work with ike.toml")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("validate")
+                            .about("Check ike.toml for errors, unknown keys and invalid values")
+                            .args(Self::global_args()),
+                    ),
+            )
             .next_display_order(800)
             .allow_external_subcommands(true)
             .styles(styles)
@@ -71,6 +205,222 @@ impl Cli {
             .help("Root directory of the project")]
     }
 
+    pub fn pack_args() -> Vec<Arg> {
+        vec![
+            Arg::new("transpile")
+                .help("Transpile TypeScript to JavaScript")
+                .long("transpile")
+                .action(ArgAction::SetTrue),
+            Arg::new("declarations")
+                .help("Emit .d.ts declarations, implies --transpile")
+                .long("declarations")
+                .action(ArgAction::SetTrue),
+            Arg::new("dry-run")
+                .help("Show what would be packed without writing anything")
+                .long("dry-run")
+                .action(ArgAction::SetTrue),
+        ]
+    }
+
+    pub fn filter_arg() -> Arg {
+        Arg::new("filter")
+            .long("filter")
+            .help("Only run in workspace members whose name matches, e.g. @app/*")
+    }
+
+    pub fn env_file_arg() -> Arg {
+        Arg::new("env-file")
+            .long("env-file")
+            .help(".env file to load, can be passed several times")
+            .action(ArgAction::Append)
+    }
+
+    pub fn permission_args() -> Vec<Arg> {
+        let allow = |name: &'static str, help: &'static str| {
+            Arg::new(name)
+                .long(name)
+                .help(help)
+                .num_args(0..)
+                .value_delimiter(',')
+                .require_equals(true)
+                .action(ArgAction::Append)
+        };
+
+        vec![
+            Arg::new("sandbox")
+                .help("Only allow what --allow-* flags or [permissions] of ike.toml grant")
+                .long("sandbox")
+                .action(ArgAction::SetTrue),
+            allow(
+                "allow-read",
+                "Allow reading files, or only the comma separated paths",
+            ),
+            allow(
+                "allow-write",
+                "Allow writing files, or only the comma separated paths",
+            ),
+            allow(
+                "allow-env",
+                "Allow accessing environment variables, or only the comma separated ones",
+            ),
+            allow(
+                "allow-run",
+                "Allow running subprocesses, or only the comma separated commands",
+            ),
+            allow(
+                "allow-net",
+                "Reserved for network access, it has no effect yet",
+            ),
+        ]
+    }
+
+    pub fn features_args() -> Vec<Arg> {
+        vec![
+            Arg::new("features")
+                .help("Comma separated list of features to activate")
+                .long("features")
+                .value_delimiter(',')
+                .action(ArgAction::Append),
+            Arg::new("no-default-features")
+                .help("Do not activate the default features")
+                .long("no-default-features")
+                .action(ArgAction::SetTrue),
+        ]
+    }
+
+    /// Features activated by `--features` and `--no-default-features` together with the
+    /// defaults from ike.toml.
+    pub fn active_features(&self, matches: &clap::ArgMatches) -> Result<Vec<String>> {
+        let requested: Vec<String> = matches
+            .get_many::<String>("features")
+            .map(|features| features.cloned().collect())
+            .unwrap_or_default();
+        let no_default = matches.get_flag("no-default-features");
+
+        match &self.pkg {
+            Some(pkg) => Ok(pkg.toml.active_features(&requested, no_default)?),
+            None if requested.is_empty() => Ok(Vec::new()),
+            None => Err(CouldNotFindIkeToml.into()),
+        }
+    }
+
+    /// Permissions of `ike run` and `ike test`. Scripts run in a sandbox with `--sandbox`,
+    /// any `--allow-*` flag or a `[permissions]` table in ike.toml, otherwise everything is
+    /// allowed. Paths of ike.toml are relative to the package, the ones of flags to the
+    /// current directory.
+    pub fn permissions(&self, matches: &clap::ArgMatches) -> Result<Permissions> {
+        let config = self.pkg.as_ref().and_then(|pkg| {
+            let dir = pkg.dir().unwrap_or(&self.root).to_path_buf();
+            pkg.toml.permissions.clone().map(|config| (dir, config))
+        });
+        let flags: Vec<(PermissionKind, Vec<String>)> = PermissionKind::ALL
+            .into_iter()
+            .filter_map(|kind| {
+                let values = matches.get_many::<String>(&format!("allow-{}", kind.name()))?;
+                Some((kind, values.cloned().collect()))
+            })
+            .collect();
+
+        if !matches.get_flag("sandbox") && flags.is_empty() && config.is_none() {
+            return Ok(Permissions::allow_all());
+        }
+
+        let net_granted = flags.iter().any(|(kind, _)| *kind == PermissionKind::Net)
+            || config.as_ref().is_some_and(|(_, config)| {
+                matches!(config.net, Some(AllowList::All(true) | AllowList::List(_)))
+            });
+        if net_granted {
+            elog!(
+                warn,
+                "the <cyan>net<r> permission is reserved and has no effect yet, network access is not sandboxed"
+            );
+        }
+
+        let mut permissions = Permissions::sandboxed();
+
+        if let Some((dir, config)) = config {
+            let lists = [
+                (PermissionKind::Read, config.read),
+                (PermissionKind::Write, config.write),
+                (PermissionKind::Env, config.env),
+                (PermissionKind::Run, config.run),
+                (PermissionKind::Net, config.net),
+            ];
+
+            for (kind, list) in lists {
+                match list {
+                    Some(AllowList::All(true)) => permissions.allow(kind, None, &dir),
+                    Some(AllowList::List(targets)) => permissions.allow(kind, Some(&targets), &dir),
+                    Some(AllowList::All(false)) | None => {}
+                }
+            }
+        }
+
+        let cwd = std::env::current_dir()?;
+        for (kind, targets) in flags {
+            permissions.allow(kind, Some(&targets), &cwd);
+        }
+
+        Ok(permissions)
+    }
+
+    /// Loads `env-file` of ike.toml, then the files passed with `--env-file`.
+    pub fn load_env_files(&self, matches: &clap::ArgMatches) -> Result<()> {
+        let mut files: Vec<PathBuf> = match &self.pkg {
+            Some(pkg) => {
+                let dir = pkg.dir().unwrap_or(&self.root);
+                pkg.toml
+                    .env_files
+                    .iter()
+                    .map(|file| dir.join(file))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        let cwd = std::env::current_dir()?;
+        files.extend(
+            matches
+                .get_many::<String>("env-file")
+                .into_iter()
+                .flatten()
+                .map(|file| cwd.join(file)),
+        );
+
+        load_env_files(&files)
+    }
+
+    /// Workspace members a command runs in. `None` means the command runs in the current
+    /// package only, which is the case outside of workspaces or in a member without `--filter`.
+    pub fn workspace_members(&self, filter: Option<&str>) -> Result<Option<Vec<&IkeToml>>> {
+        let pkg = match &self.pkg {
+            Some(pkg) => pkg,
+            None => return Ok(None),
+        };
+        let workspace = match &pkg.workspace {
+            Some(workspace) if pkg.is_workspace_root() || filter.is_some() => workspace,
+            _ => return Ok(None),
+        };
+
+        let members = workspace.filter(filter);
+        if let (Some(filter), true) = (filter, members.is_empty()) {
+            return Err(NoMatchingWorkspaceMembers(filter.to_string()).into());
+        }
+
+        Ok(Some(members))
+    }
+
+    /// Nearest ike.toml from `root`, its warnings are logged once here.
+    pub fn find_pkg(root: &Path) -> Result<Option<IkeToml>> {
+        let pkg = IkeToml::find_nearest_from(root.to_path_buf())?;
+
+        for warning in pkg.iter().flat_map(|pkg| &pkg.warnings) {
+            elog!(warn, "{}", warning);
+        }
+
+        Ok(pkg)
+    }
+
     pub fn set_root(mut self, root: PathBuf) -> Self {
         self.root = root;
         self
@@ -98,16 +448,47 @@ impl Cli {
         match matches.subcommand() {
             Some(("run", sub_matches)) => {
                 let root = self.parse_root(sub_matches)?;
-                let pkg = IkeToml::find_nearest_from(root.clone());
+                let pkg = Self::find_pkg(&root)?;
 
                 run_command(self.set_root(root.clone()).set_pkg(pkg), sub_matches)?
             }
             Some(("test", sub_matches)) => {
                 let root = self.parse_root(sub_matches)?;
-                let pkg = IkeToml::find_nearest_from(root.clone());
+                let pkg = Self::find_pkg(&root)?;
 
                 test_command(self.set_root(root.clone()).set_pkg(pkg), sub_matches)?
             }
+            Some(("install", sub_matches)) => {
+                let root = self.parse_root(sub_matches)?;
+                let pkg = Self::find_pkg(&root)?;
+
+                install_command(self.set_root(root.clone()).set_pkg(pkg), sub_matches)?
+            }
+            Some(("add", sub_matches)) => {
+                let root = self.parse_root(sub_matches)?;
+                let pkg = Self::find_pkg(&root)?;
+
+                add_command(self.set_root(root.clone()).set_pkg(pkg), sub_matches)?
+            }
+            Some(("remove", sub_matches)) => {
+                let root = self.parse_root(sub_matches)?;
+                let pkg = Self::find_pkg(&root)?;
+
+                remove_command(self.set_root(root.clone()).set_pkg(pkg), sub_matches)?
+            }
+            Some(("pack", sub_matches)) => {
+                let root = self.parse_root(sub_matches)?;
+                let pkg = Self::find_pkg(&root)?;
+
+                pack_command(self.set_root(root.clone()).set_pkg(pkg), sub_matches)?
+            }
+            Some(("publish", sub_matches)) => {
+                let root = self.parse_root(sub_matches)?;
+                let pkg = Self::find_pkg(&root)?;
+
+                publish_command(self.set_root(root.clone()).set_pkg(pkg), sub_matches)?
+            }
+            Some(("toml", sub_matches)) => toml_command(self, sub_matches)?,
             _ => {}
         };
 
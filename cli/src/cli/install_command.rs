@@ -0,0 +1,29 @@
+use anyhow::Result;
+use ike_logger::{log, Logger};
+
+use super::cli::Cli;
+use crate::error::IkeError::CouldNotFindIkeToml;
+use crate::{globals::VERSION, install::Installer};
+
+pub fn install_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
+    let features = cli.active_features(sub_matches)?;
+    let pkg = match cli.pkg {
+        Some(pkg) => pkg,
+        None => return Err(CouldNotFindIkeToml.into()),
+    };
+    let frozen = sub_matches.get_flag("frozen");
+    let allow_scripts = sub_matches.get_flag("allow-scripts");
+
+    log!(
+        info,
+        "<cyan>{}<r> <d>{}<r>",
+        VERSION,
+        pkg.dir().unwrap_or(&cli.root).display()
+    );
+
+    Installer::new(pkg, frozen, allow_scripts)?
+        .with_features(features)
+        .install()?;
+
+    Ok(())
+}
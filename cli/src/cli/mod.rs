@@ -1,4 +1,10 @@
+pub mod add_command;
 pub mod cli;
+pub mod install_command;
+pub mod pack_command;
+pub mod publish_command;
+pub mod remove_command;
 pub mod run_command;
 pub mod style;
 pub mod test_command;
+pub mod toml_command;
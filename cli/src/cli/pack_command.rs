@@ -0,0 +1,59 @@
+use anyhow::Result;
+use ike_logger::{log, Logger};
+use std::path::PathBuf;
+
+use super::cli::Cli;
+use crate::error::IkeError::{CouldNotFindIkeToml, RequiresIkeToml};
+use crate::pack::{pack, PackOptions};
+
+pub fn pack_options(sub_matches: &clap::ArgMatches) -> PackOptions {
+    let declarations = sub_matches.get_flag("declarations");
+
+    PackOptions {
+        // Declarations are emitted for the transpiled files
+        transpile: sub_matches.get_flag("transpile") || declarations,
+        declarations,
+    }
+}
+
+pub fn pack_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
+    let pkg = match cli.pkg {
+        Some(pkg) => pkg,
+        None => return Err(CouldNotFindIkeToml.into()),
+    };
+    if pkg.is_package_json() {
+        return Err(RequiresIkeToml("pack").into());
+    }
+    let dry_run = sub_matches.get_flag("dry-run");
+
+    let packed = pack(&pkg, pack_options(sub_matches))?;
+
+    for file in &packed.files {
+        log!("<d>{}<r>", file);
+    }
+
+    let out_dir = sub_matches
+        .get_one::<String>("out-dir")
+        .map(|dir| cli.root.join(dir))
+        .or_else(|| pkg.dir().map(PathBuf::from))
+        .unwrap_or(cli.root);
+    let out = out_dir.join(&packed.file_name);
+
+    if !dry_run {
+        std::fs::create_dir_all(&out_dir)?;
+        std::fs::write(&out, &packed.bytes)?;
+    }
+
+    log!(
+        success,
+        "packed <cyan>{}@{}<r> <d>({} files, {} bytes)<r> {}",
+        packed.name,
+        packed.version,
+        packed.files.len(),
+        packed.bytes.len(),
+        out.display()
+    );
+    log!("<d>integrity: {}<r>", packed.integrity);
+
+    Ok(())
+}
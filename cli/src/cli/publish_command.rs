@@ -0,0 +1,47 @@
+use anyhow::Result;
+use ike_logger::{log, Logger};
+
+use super::{cli::Cli, pack_command::pack_options};
+use crate::error::IkeError::{CouldNotFindIkeToml, RequiresIkeToml};
+use crate::install::registry::RegistryClient;
+use crate::pack::{pack, publish::publish};
+
+pub fn publish_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
+    let pkg = match cli.pkg {
+        Some(pkg) => pkg,
+        None => return Err(CouldNotFindIkeToml.into()),
+    };
+    if pkg.is_package_json() {
+        return Err(RequiresIkeToml("publish").into());
+    }
+
+    let registry = match sub_matches.get_one::<String>("registry") {
+        Some(registry) => registry.clone(),
+        None => RegistryClient::registry_url(pkg.toml.install.registry.as_deref()),
+    };
+
+    let packed = pack(&pkg, pack_options(sub_matches))?;
+
+    if sub_matches.get_flag("dry-run") {
+        log!(
+            info,
+            "would publish <cyan>{}@{}<r> to {}",
+            packed.name,
+            packed.version,
+            registry
+        );
+        return Ok(());
+    }
+
+    let location = publish(&packed, &registry)?;
+
+    log!(
+        success,
+        "published <cyan>{}@{}<r> <d>{}<r>",
+        packed.name,
+        packed.version,
+        location
+    );
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use ike_logger::{log, Logger};
+use ike_toml::IkeToml;
+
+use super::cli::Cli;
+use crate::error::IkeError::{CouldNotFindIkeToml, RequiresIkeToml};
+use crate::install::{
+    manifest::ManifestEditor, registry::validate_package_name, remove_existing,
+    InstallError::DependencyNotFound, Installer, STORE_DIR,
+};
+
+pub fn remove_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
+    let pkg = match cli.pkg {
+        Some(pkg) => pkg,
+        None => return Err(CouldNotFindIkeToml.into()),
+    };
+    if pkg.is_package_json() {
+        return Err(RequiresIkeToml("remove").into());
+    }
+    let file_path = pkg.file_path.clone().ok_or(CouldNotFindIkeToml)?;
+    let name = sub_matches.get_one::<String>("package").unwrap();
+    validate_package_name(name)?;
+
+    let mut editor = ManifestEditor::open(&file_path)?;
+    if !editor.remove_dependency(name) {
+        return Err(DependencyNotFound(name.clone()).into());
+    }
+    editor.save()?;
+
+    log!(info, "removed <cyan>{}<r>", name);
+
+    if let Some(dir) = file_path.parent() {
+        remove_existing(&dir.join(STORE_DIR).join(name))?;
+    }
+
+    let pkg = IkeToml::from_file(file_path)?.with_workspace()?;
+    Installer::new(pkg, false, false)?.install()?;
+
+    Ok(())
+}
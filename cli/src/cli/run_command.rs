@@ -3,14 +3,18 @@ use std::path::PathBuf;
 use anyhow::Result;
 use ike_fs::FsError::FileNotFound;
 use ike_logger::{elog, Logger};
+use ike_toml::IkeToml;
 
 use super::cli::Cli;
 use crate::error::IkeError::CouldNotResolveEntry;
-use crate::{fs::is_file, runtime::runtime::start_runtime};
+use crate::runtime::rejections::UnhandledRejections;
+use crate::{fs::is_file, runtime::runtime::start_runtime, task::run_tasks};
 
 pub fn run_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
+    cli.load_env_files(sub_matches)?;
+
     let cli_entry = resolve_entry(cli.clone(), sub_matches)?;
-    let entry = parse_entry(&cli_entry, cli.root)?;
+    let entry = parse_entry(&cli_entry, cli.root.clone())?;
 
     if entry.is_file {
         if entry.path.is_none() {
@@ -22,20 +26,46 @@ pub fn run_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
             return Err(FileNotFound(entry_path).into());
         }
 
-        match start_runtime(&entry_path, None) {
-            Ok(_) => {}
+        let features = cli.active_features(sub_matches)?;
+        let permissions = cli.permissions(sub_matches)?;
+        let unhandled_rejections = UnhandledRejections::from_arg(
+            sub_matches
+                .get_one::<String>("unhandled-rejections")
+                .map(String::as_str),
+        );
+
+        match start_runtime(&entry_path, &features, permissions, unhandled_rejections) {
+            Ok(0) => {}
+            Ok(code) => std::process::exit(code),
             Err(e) => {
                 elog!(error, "{}", e);
                 std::process::exit(1);
             }
         }
     } else {
-        // TODO: Implement execution of a script and global packages
+        // TODO: Implement execution of global packages
+        let task = entry.executable.unwrap();
+        let filter = sub_matches.get_one::<String>("filter").map(String::as_str);
+
+        let pkgs = match cli.workspace_members(filter)? {
+            // The root's own task wins over the ones of its members
+            Some(_) if filter.is_none() && has_task(cli.pkg.as_ref(), &task) => {
+                vec![cli.pkg.as_ref().unwrap()]
+            }
+            Some(members) => members,
+            None => cli.pkg.as_ref().into_iter().collect(),
+        };
+
+        run_tasks(&pkgs, &task)?;
     }
 
     Ok(())
 }
 
+fn has_task(pkg: Option<&IkeToml>, task: &str) -> bool {
+    pkg.is_some_and(|pkg| pkg.toml.tasks.contains_key(task))
+}
+
 fn resolve_entry(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<String> {
     if let Some(entry) = sub_matches.get_one::<String>("entry") {
         Ok(entry.to_string())
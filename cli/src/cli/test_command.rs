@@ -1,18 +1,20 @@
 use crate::{
     globals::{ALLOWED_EXTENSIONS, VERSION},
-    testing::tests::run_tests,
+    testing::tests::{run_tests, TestOptions},
 };
 use std::path::Path;
+use std::time::Duration;
 use std::{fs::read_dir, path::PathBuf};
 
 use super::cli::Cli;
-use crate::error::IkeError::FailedToConvertPath;
+use crate::error::IkeError::{FailedToConvertPath, InvalidTestGlob};
 use anyhow::Result;
+use glob::{MatchOptions, Pattern};
 use ike_logger::{log, new_line, Logger};
+use ike_toml::{TestConfig, TestReporter};
 
-// TODO: pattern handling
 pub fn test_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
-    let root = cli.root;
+    let root = cli.root.clone();
 
     log!(info, "<cyan>{}<r> <d>{}<r>", VERSION, root.display());
 
@@ -32,28 +34,217 @@ pub fn test_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
             .join(", ")
     );
 
-    let glob_result = Scanner::scan(root.clone(), patterns)?;
+    let features = cli.active_features(sub_matches)?;
+    let permissions = cli.permissions(sub_matches)?;
+    cli.load_env_files(sub_matches)?;
 
-    if glob_result.is_empty() {
+    // In a workspace every member is tested on its own, with its own root and [test]
+    let filter = sub_matches.get_one::<String>("filter").map(String::as_str);
+    let targets: Vec<TestTarget> = match cli.workspace_members(filter)? {
+        Some(members) => {
+            let mut targets: Vec<TestTarget> = members
+                .iter()
+                .filter_map(|member| {
+                    let dir = member.dir()?.to_path_buf();
+                    Some(TestTarget::new(dir.clone(), dir, member.toml.test.clone()))
+                })
+                .collect();
+
+            // The root package is tested too, without the members that live below it
+            if let (Some(pkg), None) = (&cli.pkg, filter) {
+                let dir = pkg.dir().unwrap_or(&root).to_path_buf();
+                let mut target = TestTarget::new(dir.clone(), dir, pkg.toml.test.clone());
+                target.skip = targets
+                    .iter()
+                    .filter_map(|member| member.dir.canonicalize().ok())
+                    .collect();
+                target.is_workspace_root = true;
+                targets.insert(0, target);
+            }
+
+            targets
+        }
+        None => {
+            let (base, config) = match &cli.pkg {
+                Some(pkg) => (
+                    pkg.dir().unwrap_or(&root).to_path_buf(),
+                    pkg.toml.test.clone(),
+                ),
+                None => (root.clone(), TestConfig::default()),
+            };
+            vec![TestTarget::new(root, base, config)]
+        }
+    };
+    let in_workspace = targets.len() > 1 || filter.is_some();
+
+    for target in targets {
+        let TestTarget { dir, base, .. } = &target;
+        let config = with_overrides(target.config.clone(), sub_matches);
+        let scanner = Scanner::new(base, &config, patterns.clone(), &target.skip)?;
+        let glob_result = scanner.scan(dir)?;
+
+        // A workspace root without tests of its own is only there to hold the members
+        if target.is_workspace_root && glob_result.is_empty() {
+            continue;
+        }
+
+        if in_workspace {
+            new_line!();
+            log!(info, "<cyan>{}<r>", dir.display());
+        }
+
+        if glob_result.is_empty() {
+            new_line!();
+            log!(
+                warn,
+                "no test files found. files should have _spec, .spec, _test, .test in thier filename"
+            );
+        }
         new_line!();
-        log!(
-            warn,
-            "no test files found. files should have _spec, .spec, _test, .test in thier filename"
-        );
-    }
-    new_line!();
 
-    run_tests(glob_result, root).unwrap();
+        let options = TestOptions {
+            features: features.clone(),
+            timeout: config
+                .timeout
+                .map(Duration::from_millis)
+                .unwrap_or(TestOptions::DEFAULT_TIMEOUT),
+            setup: config.setup.iter().map(|file| base.join(file)).collect(),
+            reporter: config.reporter.unwrap_or_default(),
+            permissions: permissions.clone(),
+        };
+
+        // Variables of one member don't leak into the tests of the next one
+        let previous: Vec<(String, Option<String>)> = config
+            .env
+            .iter()
+            .map(|(key, value)| {
+                let old = std::env::var(key).ok();
+                std::env::set_var(key, value);
+                (key.clone(), old)
+            })
+            .collect();
+
+        run_tests(glob_result, dir.clone(), &options).unwrap();
+
+        for (key, old) in previous {
+            match old {
+                Some(value) => std::env::set_var(key, value),
+                None => std::env::remove_var(key),
+            }
+        }
+    }
 
     Ok(())
 }
 
-struct Scanner;
+/// A package whose tests run together, with the `[test]` config of its ike.toml.
+struct TestTarget {
+    dir: PathBuf,
+    /// What `include` and `exclude` globs are relative to.
+    base: PathBuf,
+    config: TestConfig,
+    /// Canonical directories of workspace members, which are tested on their own.
+    skip: Vec<PathBuf>,
+    is_workspace_root: bool,
+}
+
+impl TestTarget {
+    fn new(dir: PathBuf, base: PathBuf, config: TestConfig) -> Self {
+        Self {
+            dir,
+            base,
+            config,
+            skip: Vec::new(),
+            is_workspace_root: false,
+        }
+    }
+}
+
+/// Flags of `ike test` take precedence over `[test]` of ike.toml. `--include` replaces the
+/// configured globs, `--exclude` and `--setup` add to them.
+fn with_overrides(mut config: TestConfig, sub_matches: &clap::ArgMatches) -> TestConfig {
+    let values = |name: &str| -> Vec<String> {
+        sub_matches
+            .get_many::<String>(name)
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default()
+    };
+
+    let include = values("include");
+    if !include.is_empty() {
+        config.include = include;
+    }
+    config.exclude.extend(values("exclude"));
+
+    // Setup files from the command line are relative to where ike runs
+    let cwd = std::env::current_dir().unwrap_or_default();
+    config.setup.extend(
+        values("setup")
+            .into_iter()
+            .map(|file| cwd.join(file).to_string_lossy().to_string()),
+    );
+
+    if let Some(timeout) = sub_matches.get_one::<u64>("timeout") {
+        config.timeout = Some(*timeout);
+    }
+    match sub_matches
+        .get_one::<String>("reporter")
+        .map(String::as_str)
+    {
+        Some("dot") => config.reporter = Some(TestReporter::Dot),
+        Some("pretty") => config.reporter = Some(TestReporter::Pretty),
+        _ => {}
+    }
+
+    config
+}
+
+/// Finds test files. `include` and `exclude` globs match paths relative to `base`.
+struct Scanner<'a> {
+    base: PathBuf,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    patterns: Vec<&'a str>,
+    skip: &'a [PathBuf],
+}
 
-impl Scanner {
+impl<'a> Scanner<'a> {
     pub const NAME_SUFFIXES: [&'static str; 4] = ["_test", ".test", "_spec", ".spec"];
+    /// Directories that are never entered.
+    pub const IGNORED_DIRS: [&'static str; 3] = [".git", "node_modules", "target"];
+
+    const MATCH_OPTIONS: MatchOptions = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
 
-    pub fn is_test_file(path: &Path) -> bool {
+    pub fn new(
+        base: &Path,
+        config: &TestConfig,
+        patterns: Vec<&'a str>,
+        skip: &'a [PathBuf],
+    ) -> Result<Self> {
+        let compile = |globs: &[String]| -> Result<Vec<Pattern>> {
+            globs
+                .iter()
+                .map(|glob| {
+                    Pattern::new(glob).map_err(|e| InvalidTestGlob(glob.clone(), e.to_string()))
+                })
+                .collect::<Result<_, _>>()
+                .map_err(Into::into)
+        };
+
+        Ok(Self {
+            base: base.to_path_buf(),
+            include: compile(&config.include)?,
+            exclude: compile(&config.exclude)?,
+            patterns,
+            skip,
+        })
+    }
+
+    fn has_test_name(path: &Path) -> bool {
         let ext = if let Some(ext) = path.extension() {
             ext.to_str().unwrap()
         } else {
@@ -71,37 +262,53 @@ impl Scanner {
             .any(|suffix| stem.ends_with(suffix))
     }
 
-    pub fn scan(dir: PathBuf, patterns: Vec<&str>) -> Result<Vec<PathBuf>> {
+    pub fn is_test_file(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.base).unwrap_or(path);
+        let matches = |pattern: &Pattern| pattern.matches_path_with(relative, Self::MATCH_OPTIONS);
+
+        let included = match self.include.is_empty() {
+            true => Self::has_test_name(path),
+            false => self.include.iter().any(matches),
+        };
+        if !included || self.exclude.iter().any(matches) {
+            return false;
+        }
+
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        self.patterns.is_empty() || self.patterns.iter().any(|p| stem.contains(p))
+    }
+
+    fn is_skipped(&self, dir: &Path) -> bool {
+        !self.skip.is_empty() && dir.canonicalize().is_ok_and(|dir| self.skip.contains(&dir))
+    }
+
+    pub fn scan(&self, dir: &Path) -> Result<Vec<PathBuf>> {
         let mut paths = Vec::new();
+        self.scan_dir(dir, &mut paths)?;
+        paths.sort();
+
+        Ok(paths)
+    }
 
+    fn scan_dir(&self, dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
         for file in read_dir(dir)? {
             let file = file?;
             let path = file.path();
 
-            if let Some(path_str) = path.to_str() {
-                if path_str.contains(".git")
-                    || path_str.contains("node_modules")
-                    || path_str.contains("target")
-                {
-                    continue;
-                }
-            } else {
-                return Err(FailedToConvertPath.into());
-            }
-
             if path.is_dir() {
-                paths.extend(Self::scan(path, patterns.clone())?);
-            } else if patterns.is_empty() && Self::is_test_file(&path) {
-                paths.push(path);
-            } else if Self::is_test_file(&path)
-                && patterns
-                    .iter()
-                    .any(|p| path.file_stem().unwrap().to_str().unwrap().contains(p))
-            {
+                let name = match path.file_name().and_then(|name| name.to_str()) {
+                    Some(name) => name,
+                    None => return Err(FailedToConvertPath.into()),
+                };
+
+                if !Self::IGNORED_DIRS.contains(&name) && !self.is_skipped(&path) {
+                    self.scan_dir(&path, paths)?;
+                }
+            } else if self.is_test_file(&path) {
                 paths.push(path);
             }
         }
 
-        Ok(paths)
+        Ok(())
     }
 }
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use ike_fs::find_nearest_file;
+use ike_logger::{elog, log, Logger};
+use ike_toml::IkeToml;
+
+use super::cli::Cli;
+use crate::error::IkeError::CouldNotFindIkeToml;
+
+pub fn toml_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
+    match sub_matches.subcommand() {
+        Some(("validate", validate_matches)) => validate_command(cli, validate_matches),
+        _ => Ok(()),
+    }
+}
+
+/// Checks the nearest ike.toml and, for a workspace root, the ike.toml of every member.
+/// Errors stop the command, warnings are listed.
+fn validate_command(cli: Cli, sub_matches: &clap::ArgMatches) -> Result<()> {
+    let root = cli.parse_root(sub_matches)?;
+    let file_path = find_nearest_file(root, "ike.toml").ok_or(CouldNotFindIkeToml)?;
+    let pkg = IkeToml::from_file(file_path.clone())?.with_workspace()?;
+
+    let mut pkgs = vec![&pkg];
+    if let (true, Some(workspace)) = (pkg.is_workspace_root(), &pkg.workspace) {
+        pkgs.extend(workspace.members.iter());
+    }
+
+    let mut warnings = 0;
+    for warning in pkgs.iter().flat_map(|pkg| &pkg.warnings) {
+        elog!(warn, "{}", warning);
+        warnings += 1;
+    }
+
+    match (pkgs.len(), warnings) {
+        (1, 0) => log!(success, "<cyan>{}<r> is valid", file_path.display()),
+        (1, _) => log!(
+            success,
+            "<cyan>{}<r> is valid <d>[{} warnings]<r>",
+            file_path.display(),
+            warnings
+        ),
+        (count, _) => log!(
+            success,
+            "<cyan>{}<r> and {} workspace members are valid <d>[{} warnings]<r>",
+            file_path.display(),
+            count - 1,
+            warnings
+        ),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,306 @@
+use std::{
+    collections::HashMap,
+    iter::Peekable,
+    path::{Path, PathBuf},
+    str::Chars,
+};
+
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::error::IkeError::{FailedToReadEnvFile, InvalidEnvFile};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DotenvError {
+    #[error("line {0}: expected `KEY=value`")]
+    MissingEquals(usize),
+    #[error("line {0}: `{1}` is not a valid variable name")]
+    InvalidKey(usize, String),
+    #[error("line {0}: unterminated {1} quote")]
+    UnterminatedQuote(usize, char),
+}
+
+/// Parses a .env file into its variables, in the order they are defined.
+///
+/// Values can be unquoted, where ` #` starts a comment, or quoted. Double quoted values
+/// understand escapes like `\n`, single quoted and backtick quoted ones are taken as they
+/// are. Quoted values can span several lines. `${VAR}` and `${VAR:-default}` expand to
+/// variables defined earlier in the file, then to what `lookup` returns.
+pub fn parse(
+    source: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<(String, String)>, DotenvError> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+
+    loop {
+        while let Some(&c) = chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            if c == '\n' {
+                line += 1;
+            }
+            chars.next();
+        }
+
+        let start = line;
+        match chars.peek() {
+            None => break,
+            Some('#') => {
+                skip_line(&mut chars);
+                continue;
+            }
+            _ => {}
+        }
+
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '=' || c == '\n' {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+        if chars.next() != Some('=') {
+            return Err(DotenvError::MissingEquals(start));
+        }
+
+        let key = key.trim();
+        let key = key.strip_prefix("export ").map_or(key, str::trim_start);
+        if !is_valid_key(key) {
+            return Err(DotenvError::InvalidKey(start, key.to_string()));
+        }
+
+        while matches!(chars.peek(), Some(' ' | '\t')) {
+            chars.next();
+        }
+
+        let resolve = |name: &str| {
+            vars.iter()
+                .rev()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+                .or_else(|| lookup(name))
+        };
+
+        let value = match chars.peek().copied() {
+            Some(quote @ ('"' | '\'' | '`')) => {
+                chars.next();
+
+                let mut raw = String::new();
+                let mut closed = false;
+                while let Some(c) = chars.next() {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    if c == quote {
+                        closed = true;
+                        break;
+                    }
+
+                    raw.push(c);
+                    // An escaped quote doesn't end the value
+                    if c == '\\' && quote == '"' {
+                        if let Some(next) = chars.next() {
+                            if next == '\n' {
+                                line += 1;
+                            }
+                            raw.push(next);
+                        }
+                    }
+                }
+                if !closed {
+                    return Err(DotenvError::UnterminatedQuote(start, quote));
+                }
+                skip_line(&mut chars);
+
+                match quote {
+                    '"' => interpolate(&raw, true, resolve),
+                    _ => raw,
+                }
+            }
+            _ => {
+                let mut raw = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == '\n' {
+                        break;
+                    }
+                    if c == '#' && raw.ends_with([' ', '\t']) {
+                        skip_line(&mut chars);
+                        break;
+                    }
+                    raw.push(c);
+                    chars.next();
+                }
+
+                interpolate(raw.trim(), false, resolve)
+            }
+        };
+
+        vars.push((key.to_string(), value));
+    }
+
+    Ok(vars)
+}
+
+fn skip_line(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|&c| c != '\n') {
+        chars.next();
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Expands `${VAR}` in `raw`, `\$` is a literal `$`. With `escapes`, `\n`, `\r` and `\t`
+/// are turned into their characters and any other escaped character is kept as it is.
+fn interpolate(raw: &str, escapes: bool, resolve: impl Fn(&str) -> Option<String>) -> String {
+    let mut value = String::new();
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if escapes => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some(other) => value.push(other),
+                None => value.push('\\'),
+            },
+            '\\' if chars.peek() == Some(&'$') => {
+                chars.next();
+                value.push('$');
+            }
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+
+                let mut name = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c);
+                }
+
+                if !closed {
+                    value.push_str("${");
+                    value.push_str(&name);
+                    continue;
+                }
+
+                let (name, default) = match name.split_once(":-") {
+                    Some((name, default)) => (name, Some(default)),
+                    None => (name.as_str(), None),
+                };
+                match resolve(name).filter(|value| !value.is_empty()) {
+                    Some(resolved) => value.push_str(&resolved),
+                    None => value.push_str(default.unwrap_or_default()),
+                }
+            }
+            c => value.push(c),
+        }
+    }
+
+    value
+}
+
+/// Loads .env files into the environment of the process. Later files take precedence over
+/// earlier ones, variables that are already set take precedence over all of them.
+pub fn load_env_files(files: &[PathBuf]) -> Result<()> {
+    let mut values: HashMap<String, String> = HashMap::new();
+
+    for file in files {
+        for (key, value) in read_env_file(file, &values)? {
+            values.insert(key, value);
+        }
+    }
+
+    for (key, value) in values {
+        if std::env::var_os(&key).is_none() {
+            std::env::set_var(key, value);
+        }
+    }
+
+    Ok(())
+}
+
+fn read_env_file(file: &Path, values: &HashMap<String, String>) -> Result<Vec<(String, String)>> {
+    let source = std::fs::read_to_string(file)
+        .map_err(|e| FailedToReadEnvFile(file.display().to_string(), e.to_string()))?;
+
+    let lookup = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .or_else(|| values.get(name).cloned())
+    };
+
+    parse(&source, lookup)
+        .map_err(|e| InvalidEnvFile(file.display().to_string(), e.to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_map(source: &str) -> HashMap<String, String> {
+        let lookup = |name: &str| (name == "HOME").then(|| "/home/ike".to_string());
+        parse(source, lookup).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let vars = parse_map(
+            r#"
+# comment
+export NAME=ike # inline comment
+EMPTY=
+SINGLE='no ${NAME} \n here'
+DOUBLE="hello\n\"${NAME}\""
+MULTILINE="first
+second"
+HASH=value#not-a-comment
+PATH_VAR=${HOME}/bin
+FALLBACK=${MISSING:-default}
+ESCAPED=\${NAME}
+"#,
+        );
+
+        assert_eq!(vars["NAME"], "ike");
+        assert_eq!(vars["EMPTY"], "");
+        assert_eq!(vars["SINGLE"], "no ${NAME} \\n here");
+        assert_eq!(vars["DOUBLE"], "hello\n\"ike\"");
+        assert_eq!(vars["MULTILINE"], "first\nsecond");
+        assert_eq!(vars["HASH"], "value#not-a-comment");
+        assert_eq!(vars["PATH_VAR"], "/home/ike/bin");
+        assert_eq!(vars["FALLBACK"], "default");
+        assert_eq!(vars["ESCAPED"], "${NAME}");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let lookup = |_: &str| None;
+
+        assert_eq!(
+            parse("A=1\nnot a variable\n", lookup),
+            Err(DotenvError::MissingEquals(2))
+        );
+        assert_eq!(
+            parse("1A=1", lookup),
+            Err(DotenvError::InvalidKey(1, "1A".to_string()))
+        );
+        assert_eq!(
+            parse("A=\"open\nB=2", lookup),
+            Err(DotenvError::UnterminatedQuote(1, '"'))
+        );
+    }
+}
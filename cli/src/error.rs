@@ -8,4 +8,24 @@ pub enum IkeError {
     FailedToConvertPath,
     #[error("Failed to parse project root")]
     FailedToParseRoot,
+    #[error("Could not find <cyan>ike.toml<r> in the project root or any of its parents")]
+    CouldNotFindIkeToml,
+    #[error("Task <cyan>{0}<r> is not defined in <cyan>[tasks]<r> of ike.toml")]
+    TaskNotFound(String),
+    #[error("Task <cyan>{0}<r> of <cyan>{1}<r> exited with code {2}")]
+    TaskFailed(String, String, i32),
+    #[error("No workspace member matches <cyan>--filter {0}<r>")]
+    NoMatchingWorkspaceMembers(String),
+    #[error("<cyan>ike {0}<r> needs an <cyan>ike.toml<r>, this project only has a <cyan>package.json<r>")]
+    RequiresIkeToml(&'static str),
+    #[error("Invalid test glob <cyan>{0}<r>: {1}")]
+    InvalidTestGlob(String, String),
+    #[error("Could not read env file <cyan>{0}<r>: {1}")]
+    FailedToReadEnvFile(String, String),
+    #[error("Invalid env file <cyan>{0}<r>, {1}")]
+    InvalidEnvFile(String, String),
+    #[error("Unsupported file extension of <cyan>{0}<r>")]
+    UnsupportedSourceType(String),
+    #[error("Could not emit declarations for <cyan>{0}<r>:\n{1}")]
+    FailedToEmitDeclarations(String, String),
 }
@@ -23,6 +23,80 @@ where
     }
 }
 
+/// Same as [`read_json`], but allows comments and trailing commas like tsconfig.json does.
+pub fn read_jsonc<Json, FilePath>(file_path: FilePath) -> Result<Json>
+where
+    Json: serde::de::DeserializeOwned,
+    FilePath: AsRef<Path>,
+{
+    let mut file = File::open(file_path)?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    let serialized_json = serde_json::from_str(&strip_json_comments(&contents));
+
+    match serialized_json {
+        Ok(json) => Ok(json),
+        Err(error) => Err(FailedToReadFileWithError(error.to_string()).into()),
+    }
+}
+
+pub fn strip_json_comments(json: &str) -> String {
+    let mut result = String::with_capacity(json.len());
+    let mut chars = json.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            result.push(c);
+            match c {
+                '\\' => {
+                    if let Some(next) = chars.next() {
+                        result.push(next);
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                result.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        result.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            ',' => {
+                // Drop trailing commas before a closing bracket
+                let rest = chars.clone().find(|c| !c.is_whitespace());
+                if !matches!(rest, Some('}') | Some(']')) {
+                    result.push(c);
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
 // Determine if a path is a file using regex
 pub fn is_file(path: &str) -> bool {
     let re = Regex::new(r"\.[a-zA-Z0-9]+$").unwrap();
@@ -54,3 +128,35 @@ pub fn normalize_path(mut path: PathBuf, root: PathBuf) -> Result<PathBuf> {
 
     Ok(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_json_comments() {
+        let json = r#"{
+  // line comment
+  "url": "https://example.com", /* block
+  comment */ "glob": "src/**/*.ts",
+  "quote": "a \" // b",
+  "list": [1, 2, ],
+}"#;
+        let value: serde_json::Value = serde_json::from_str(&strip_json_comments(json)).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "url": "https://example.com",
+                "glob": "src/**/*.ts",
+                "quote": "a \" // b",
+                "list": [1, 2],
+            })
+        );
+    }
+
+    #[test]
+    fn test_trailing_commas_in_strings_are_kept() {
+        assert_eq!(strip_json_comments(r#"[",]", ",}",]"#), r#"[",]", ",}"]"#);
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::{
+    remove_existing,
+    InstallError::{GitCommandFailed, GitNotFound},
+};
+use ike_fs::which;
+
+/// Thin wrapper around the git binary. Remotes are cloned with the local git, so `file://`
+/// remotes and local paths work without network access.
+pub struct Git {
+    binary: PathBuf,
+}
+
+impl Git {
+    pub fn new() -> Result<Self> {
+        match which("git", None, None) {
+            Some(binary) => Ok(Self { binary }),
+            None => Err(GitNotFound.into()),
+        }
+    }
+
+    fn run(&self, cwd: Option<&Path>, args: &[&str]) -> Result<String> {
+        let mut command = Command::new(&self.binary);
+        command.args(args);
+        if let Some(cwd) = cwd {
+            command.current_dir(cwd);
+        }
+
+        let output = command.output()?;
+        if !output.status.success() {
+            return Err(GitCommandFailed(
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )
+            .into());
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Clones `url` into `dest` and checks out `branch` or `rev`, returns the commit HEAD points to.
+    pub fn checkout(
+        &self,
+        url: &str,
+        dest: &Path,
+        branch: Option<&str>,
+        rev: Option<&str>,
+    ) -> Result<String> {
+        remove_existing(dest)?;
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let dest_str = dest.to_string_lossy();
+        let mut args = vec!["clone", "--quiet"];
+        if let Some(branch) = branch {
+            args.extend(["--branch", branch]);
+        }
+        // A URL starting with `-` must not be taken for an option
+        args.extend(["--", url, dest_str.as_ref()]);
+        self.run(None, &args)?;
+
+        if let Some(rev) = rev {
+            self.run(Some(dest), &["checkout", "--quiet", rev])?;
+        }
+
+        self.head(dest)
+    }
+
+    /// Commit that HEAD of the repository at `dir` points to.
+    pub fn head(&self, dir: &Path) -> Result<String> {
+        self.run(Some(dir), &["rev-parse", "HEAD"])
+    }
+}
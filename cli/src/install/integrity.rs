@@ -0,0 +1,101 @@
+use anyhow::Result;
+use base64::prelude::*;
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Directories that are never part of a package's contents.
+const IGNORED_DIRS: [&str; 2] = [".git", "node_modules"];
+
+/// Subresource integrity string (`sha512-<base64>`) of `bytes`, the same format npm uses.
+pub fn integrity_of_bytes(bytes: &[u8]) -> String {
+    let digest = Sha512::digest(bytes);
+    format!("sha512-{}", BASE64_STANDARD.encode(digest))
+}
+
+/// Hex encoded sha1 of `bytes`, older registries only publish this `shasum`.
+pub fn shasum_of_bytes(bytes: &[u8]) -> String {
+    format!("{:x}", Sha1::digest(bytes))
+}
+
+/// Checks `bytes` against the integrity and/or the sha1 shasum published by the registry.
+/// Returns the sha512 integrity of `bytes`, or the mismatching hash on failure.
+pub fn verify_integrity(
+    bytes: &[u8],
+    integrity: Option<&str>,
+    shasum: Option<&str>,
+) -> Result<String, String> {
+    let computed = integrity_of_bytes(bytes);
+
+    if let Some(integrity) = integrity {
+        // An integrity string can contain several hashes, one of them has to match
+        for expected in integrity.split_whitespace() {
+            match expected.split_once('-') {
+                Some(("sha512", _)) if expected == computed => return Ok(computed),
+                Some(("sha1", hash)) if BASE64_STANDARD.encode(Sha1::digest(bytes)) == hash => {
+                    return Ok(computed)
+                }
+                _ => {}
+            }
+        }
+
+        return Err(computed);
+    }
+
+    if let Some(shasum) = shasum {
+        let actual = shasum_of_bytes(bytes);
+        if !actual.eq_ignore_ascii_case(shasum) {
+            return Err(actual);
+        }
+    }
+
+    Ok(computed)
+}
+
+/// Subresource integrity string (`sha512-<base64>`) of a whole directory. Files are hashed
+/// in sorted order together with their relative paths, so the result only depends on the
+/// contents of the directory.
+pub fn integrity_of_dir(dir: &Path) -> Result<String> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+    files.sort();
+
+    let mut hasher = Sha512::new();
+    for file in files {
+        let relative = file.strip_prefix(dir)?.to_string_lossy().replace('\\', "/");
+        let contents = fs::read(&file)?;
+
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+
+    Ok(format!(
+        "sha512-{}",
+        BASE64_STANDARD.encode(hasher.finalize())
+    ))
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            let name = entry.file_name();
+            if IGNORED_DIRS.iter().any(|ignored| name == *ignored) {
+                continue;
+            }
+            collect_files(&path, files)?;
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use anyhow::Result;
+use ike_fs::read_to_string;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::InstallError::{FailedToParseLockfile, FailedToWriteLockfile};
+
+pub const LOCKFILE_NAME: &str = "ike.lock";
+pub const LOCKFILE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Lockfile {
+    pub version: u32,
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    pub name: String,
+    /// Where the package comes from, e.g. `path+../lib`, `git+https://host/repo.git`
+    /// or `registry+https://registry.npmjs.org`.
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Commit the git dependency was resolved to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integrity: Option<String>,
+    /// Tarball the registry package was downloaded from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+    /// Where the registry package lives in the tree, e.g. `node_modules/a/node_modules/b`.
+    /// The same package can be installed at several locations in different versions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lockfile {
+    pub fn new() -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            packages: Vec::new(),
+        }
+    }
+
+    pub fn path_for(dir: &Path) -> PathBuf {
+        dir.join(LOCKFILE_NAME)
+    }
+
+    /// Reads the lockfile from `dir`, returns `None` if there is no lockfile yet.
+    pub fn read(dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path_for(dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = read_to_string(&path)?;
+        let lockfile: Self =
+            toml::from_str(&content).map_err(|e| FailedToParseLockfile(path, e.to_string()))?;
+
+        Ok(Some(lockfile))
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        let path = Self::path_for(dir);
+
+        std::fs::write(&path, self.to_toml()?)
+            .map_err(|e| FailedToWriteLockfile(path, e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Serializes the lockfile, packages are always sorted so the output is deterministic.
+    pub fn to_toml(&self) -> Result<String> {
+        let mut sorted = self.clone();
+        sorted.packages.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then(a.source.cmp(&b.source))
+                .then(a.location.cmp(&b.location))
+        });
+
+        let body = toml::to_string(&sorted)?;
+        Ok(format!(
+            "# This file is generated by `ike install`. Do not edit it manually.\n{}",
+            body
+        ))
+    }
+
+    /// Finds a directly installed package, nested registry packages are ignored.
+    pub fn find(&self, name: &str) -> Option<&LockedPackage> {
+        self.find_at(name, None)
+    }
+
+    /// Finds the package installed at `location`, e.g. a dependency of a workspace member.
+    pub fn find_at(&self, name: &str, location: Option<&str>) -> Option<&LockedPackage> {
+        self.packages
+            .iter()
+            .find(|pkg| pkg.name == name && pkg.location.as_deref() == location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str) -> LockedPackage {
+        LockedPackage {
+            name: name.to_string(),
+            source: format!("path+../{}", name),
+            version: None,
+            rev: None,
+            integrity: None,
+            resolved: None,
+            location: None,
+        }
+    }
+
+    #[test]
+    fn test_lockfile_is_deterministic() {
+        let mut a = Lockfile::new();
+        a.packages = vec![package("b"), package("a")];
+        let mut b = Lockfile::new();
+        b.packages = vec![package("a"), package("b")];
+
+        assert_eq!(a.to_toml().unwrap(), b.to_toml().unwrap());
+    }
+
+    #[test]
+    fn test_lockfile_roundtrip() {
+        let mut lockfile = Lockfile::new();
+        lockfile.packages = vec![package("a")];
+
+        let content = lockfile.to_toml().unwrap();
+        let parsed: Lockfile = toml::from_str(&content).unwrap();
+
+        assert_eq!(parsed, lockfile);
+    }
+}
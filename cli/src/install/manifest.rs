@@ -0,0 +1,208 @@
+use anyhow::Result;
+use ike_toml::Dependency;
+use std::path::{Path, PathBuf};
+use toml_edit::{table, DocumentMut, InlineTable, Item, Value};
+
+/// Edits ike.toml in place. Only the touched entries change, comments, ordering and
+/// formatting of everything else are kept as they are.
+pub struct ManifestEditor {
+    path: PathBuf,
+    doc: DocumentMut,
+}
+
+impl ManifestEditor {
+    pub fn open(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(path, &content)
+    }
+
+    pub fn parse(path: &Path, content: &str) -> Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            doc: content.parse()?,
+        })
+    }
+
+    /// Adds or replaces `name` in `[dependencies]` or `[dev-dependencies]`. A dependency
+    /// only lives in one of them, so it's removed from the other one.
+    pub fn add_dependency(&mut self, name: &str, dep: &Dependency, dev: bool) {
+        let (key, other) = match dev {
+            true => ("dev-dependencies", "dependencies"),
+            false => ("dependencies", "dev-dependencies"),
+        };
+
+        if let Some(deps) = self.doc.get_mut(other).and_then(Item::as_table_like_mut) {
+            deps.remove(name);
+        }
+
+        let deps = self
+            .doc
+            .entry(key)
+            .or_insert_with(table)
+            .as_table_like_mut()
+            .expect("dependencies must be a table");
+
+        let mut new = dependency_value(dep);
+        match deps.get_mut(name) {
+            // Replaced in place, so the key keeps its position and comments
+            Some(old) => {
+                if let Some(old) = old.as_value() {
+                    *new.decor_mut() = old.decor().clone();
+                }
+                *old = Item::Value(new);
+            }
+            None => {
+                deps.insert(name, Item::Value(new));
+            }
+        }
+    }
+
+    /// Removes `name` from both dependency tables, returns false if it wasn't there.
+    pub fn remove_dependency(&mut self, name: &str) -> bool {
+        let mut removed = false;
+
+        for key in ["dependencies", "dev-dependencies"] {
+            if let Some(deps) = self.doc.get_mut(key).and_then(Item::as_table_like_mut) {
+                removed |= deps.remove(name).is_some();
+            }
+        }
+
+        removed
+    }
+
+    pub fn save(&self) -> Result<()> {
+        std::fs::write(&self.path, self.doc.to_string())?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ManifestEditor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.doc)
+    }
+}
+
+/// `name = "^1.0.0"` for plain registry dependencies, an inline table otherwise.
+fn dependency_value(dep: &Dependency) -> Value {
+    if let (Some(version), None, None) = (&dep.version, &dep.path, &dep.git) {
+        return Value::from(version.as_str());
+    }
+
+    let mut inline = InlineTable::new();
+    let fields = [
+        ("version", &dep.version),
+        ("path", &dep.path),
+        ("git", &dep.git),
+        ("branch", &dep.branch),
+        ("rev", &dep.rev),
+    ];
+    for (key, field) in fields {
+        if let Some(field) = field {
+            inline.insert(key, field.as_str().into());
+        }
+    }
+
+    Value::InlineTable(inline)
+}
+
+/// Splits `name@range` into its parts, scoped names like `@scope/name@^1` are supported.
+pub fn parse_package_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.rfind('@') {
+        Some(index) if index > 0 => (&spec[..index], Some(&spec[index + 1..])),
+        _ => (spec, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"# My package
+[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+# Used for parsing
+zod = "^3.0.0" # pinned major
+lib = { path = "../lib" }
+
+[tasks]
+dev = "ike run"
+"#;
+
+    fn editor() -> ManifestEditor {
+        ManifestEditor::parse(Path::new("ike.toml"), MANIFEST).unwrap()
+    }
+
+    #[test]
+    fn test_add_dependency_preserves_formatting() {
+        let mut editor = editor();
+        editor.add_dependency(
+            "zod",
+            &Dependency {
+                version: Some("^3.1.0".to_string()),
+                ..Default::default()
+            },
+            false,
+        );
+        editor.add_dependency(
+            "utils",
+            &Dependency {
+                git: Some("https://example.com/utils.git".to_string()),
+                rev: Some("abc123".to_string()),
+                ..Default::default()
+            },
+            false,
+        );
+
+        assert_eq!(
+            editor.to_string(),
+            MANIFEST
+                .replace("\"^3.0.0\"", "\"^3.1.0\"")
+                .replace(
+                    "lib = { path = \"../lib\" }\n",
+                    "lib = { path = \"../lib\" }\nutils = { git = \"https://example.com/utils.git\", rev = \"abc123\" }\n"
+                )
+        );
+    }
+
+    #[test]
+    fn test_add_dev_dependency_moves_it() {
+        let mut editor = editor();
+        editor.add_dependency(
+            "zod",
+            &Dependency {
+                version: Some("^3.0.0".to_string()),
+                ..Default::default()
+            },
+            true,
+        );
+
+        let content = editor.to_string();
+        assert!(content.contains("[dev-dependencies]\nzod = \"^3.0.0\"\n"));
+        assert!(!content.contains("# pinned major"));
+        assert_eq!(content.matches("zod").count(), 1);
+    }
+
+    #[test]
+    fn test_remove_dependency() {
+        let mut editor = editor();
+
+        assert!(editor.remove_dependency("lib"));
+        assert!(!editor.remove_dependency("missing"));
+        assert!(!editor.to_string().contains("lib ="));
+        assert!(editor.to_string().contains("[tasks]\ndev = \"ike run\"\n"));
+    }
+
+    #[test]
+    fn test_parse_package_spec() {
+        assert_eq!(parse_package_spec("zod"), ("zod", None));
+        assert_eq!(parse_package_spec("zod@^3"), ("zod", Some("^3")));
+        assert_eq!(parse_package_spec("@std/path"), ("@std/path", None));
+        assert_eq!(
+            parse_package_spec("@std/path@1.0.0"),
+            ("@std/path", Some("1.0.0"))
+        );
+    }
+}
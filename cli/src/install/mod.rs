@@ -0,0 +1,616 @@
+use anyhow::Result;
+use ike_logger::{log, Logger};
+use ike_toml::{Dependency, IkeToml};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+use thiserror::Error;
+
+use crate::format::format_time;
+use git::Git;
+use integrity::integrity_of_dir;
+use lockfile::{LockedPackage, Lockfile};
+use npm::{DirectDependency, NpmInstaller};
+use registry::{validate_package_name, RegistryClient};
+
+pub mod git;
+pub mod integrity;
+pub mod lockfile;
+pub mod manifest;
+pub mod npm;
+pub mod range;
+pub mod registry;
+
+/// Dependencies are installed into `node_modules` so npm packages and Ike packages live
+/// side by side and bare specifiers can be resolved the same way for both.
+pub const STORE_DIR: &str = "node_modules";
+
+#[derive(Error, Debug)]
+pub enum InstallError {
+    #[error("<cyan>git<r> is required to install git dependencies, but it was not found in PATH")]
+    GitNotFound,
+    #[error("<cyan>git {0}<r> failed: {1}")]
+    GitCommandFailed(String, String),
+    #[error("Failed to parse lockfile {0}: {1}")]
+    FailedToParseLockfile(PathBuf, String),
+    #[error("Failed to write lockfile {0}: {1}")]
+    FailedToWriteLockfile(PathBuf, String),
+    #[error(
+        "<cyan>ike.lock<r> is missing, run <cyan>ike install<r> without <cyan>--frozen<r> first"
+    )]
+    MissingLockfile,
+    #[error("<cyan>ike.lock<r> is out of date: {0}")]
+    OutdatedLockfile(String),
+    #[error("Integrity check failed for {0}: expected {1}, got {2}")]
+    IntegrityMismatch(String, String, String),
+    #[error("Dependency {0} points to {1} which does not exist")]
+    MissingPathDependency(String, PathBuf),
+    #[error("Failed to fetch {0}: {1}")]
+    FailedToFetch(String, String),
+    #[error("No version of <cyan>{0}<r> matches <cyan>{1}<r>")]
+    NoMatchingVersion(String, String),
+    #[error("<cyan>{0}<r> {1} script failed")]
+    ScriptFailed(String, String),
+    #[error("<cyan>{0}<r> is not a dependency of this package")]
+    DependencyNotFound(String),
+    #[error("<cyan>{0}<r> is not a valid package name")]
+    InvalidPackageName(String),
+}
+
+/// Where a dependency comes from, decided by the fields set in ike.toml.
+pub enum DependencySource<'a> {
+    Path(&'a str),
+    Git {
+        url: &'a str,
+        branch: Option<&'a str>,
+        rev: Option<&'a str>,
+    },
+    Registry(&'a str),
+}
+
+impl<'a> DependencySource<'a> {
+    pub fn of(dep: &'a Dependency) -> Self {
+        if let Some(path) = &dep.path {
+            Self::Path(path)
+        } else if let Some(url) = &dep.git {
+            Self::Git {
+                url,
+                branch: dep.branch.as_deref(),
+                rev: dep.rev.as_deref(),
+            }
+        } else {
+            // ike.toml validation guarantees that a dependency has at least one source
+            Self::Registry(dep.version.as_deref().unwrap_or("*"))
+        }
+    }
+
+    /// Source as it's written to the lockfile. When it changes, the locked package is stale.
+    pub fn to_locked(&self) -> String {
+        match self {
+            Self::Path(path) => format!("path+{}", path),
+            Self::Git { url, branch, rev } => {
+                let mut source = format!("git+{}", url);
+                if let Some(branch) = branch {
+                    source.push_str(&format!("?branch={}", branch));
+                } else if let Some(rev) = rev {
+                    source.push_str(&format!("?rev={}", rev));
+                }
+                source
+            }
+            Self::Registry(version) => format!("registry+{}", version),
+        }
+    }
+}
+
+/// A package whose dependencies get installed: the package itself or, in a workspace, the
+/// root and each of its members.
+struct Dependent<'a> {
+    pkg: &'a IkeToml,
+    dir: PathBuf,
+    /// Directory relative to the installer's, empty for the installer's own package.
+    location: String,
+}
+
+impl Dependent<'_> {
+    fn store_dir(&self) -> PathBuf {
+        self.dir.join(STORE_DIR)
+    }
+
+    /// Lockfile location of `name` installed for this package, packages of the root aren't
+    /// given one so lockfiles without a workspace stay the same.
+    fn lock_location(&self, name: &str) -> Option<String> {
+        (!self.location.is_empty()).then(|| format!("{}/{}/{}", self.location, STORE_DIR, name))
+    }
+}
+
+pub struct Installer {
+    dir: PathBuf,
+    pkg: IkeToml,
+    frozen: bool,
+    allow_scripts: bool,
+    /// Package the install was started in, which is a member when installing from one.
+    package: String,
+    /// Active features of `package`, their dependencies are installed along with it.
+    features: Vec<String>,
+}
+
+impl Installer {
+    /// Creates an installer for `pkg`. Workspace members are installed from the workspace
+    /// root, since the whole workspace shares one lockfile.
+    pub fn new(pkg: IkeToml, frozen: bool, allow_scripts: bool) -> Result<Self> {
+        let package = pkg.toml.package.name.clone();
+        let pkg = match &pkg.workspace {
+            Some(workspace) if !pkg.is_workspace_root() => {
+                IkeToml::from_file(workspace.root.join("ike.toml"))?.with_workspace()?
+            }
+            _ => pkg,
+        };
+        let dir = match pkg.dir() {
+            Some(dir) => dir.to_path_buf(),
+            None => std::env::current_dir()?,
+        };
+
+        Ok(Self {
+            dir,
+            pkg,
+            frozen,
+            allow_scripts,
+            package,
+            features: Vec::new(),
+        })
+    }
+
+    pub fn with_features(mut self, features: Vec<String>) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn store_dir(&self) -> PathBuf {
+        self.dir.join(STORE_DIR)
+    }
+
+    fn dependents(&self) -> Vec<Dependent<'_>> {
+        let mut dependents = vec![Dependent {
+            pkg: &self.pkg,
+            dir: self.dir.clone(),
+            location: String::new(),
+        }];
+
+        if let Some(workspace) = &self.pkg.workspace {
+            for member in &workspace.members {
+                let dir = member.dir().unwrap_or(&self.dir).to_path_buf();
+                let location = relative_location(&self.dir, &dir);
+                dependents.push(Dependent {
+                    pkg: member,
+                    dir,
+                    location,
+                });
+            }
+        }
+
+        dependents
+    }
+
+    pub fn install(&self) -> Result<Lockfile> {
+        let start = Instant::now();
+        let existing = Lockfile::read(&self.dir)?;
+        if self.frozen && existing.is_none() {
+            return Err(InstallError::MissingLockfile.into());
+        }
+
+        let mut lockfile = Lockfile::new();
+        let mut git = None;
+        let mut registry_deps = Vec::new();
+        let mut linked_members = BTreeSet::new();
+
+        for dependent in self.dependents() {
+            // BTreeMap keeps the install order stable
+            let features = match dependent.pkg.toml.package.name == self.package {
+                true => self.features.as_slice(),
+                false => &[],
+            };
+            let deps: BTreeMap<&String, &Dependency> = dependent
+                .pkg
+                .toml
+                .dependencies
+                .iter()
+                .chain(dependent.pkg.toml.dev_dependencies.iter())
+                .chain(dependent.pkg.toml.feature_dependencies(features))
+                .collect();
+
+            for (name, dep) in deps {
+                validate_package_name(name)?;
+                let source = DependencySource::of(dep);
+
+                // Members of the workspace are used from source, whatever the version says
+                let member = self
+                    .pkg
+                    .workspace
+                    .as_ref()
+                    .and_then(|workspace| workspace.member(name));
+                if let (Some(member), DependencySource::Registry(_)) = (member, &source) {
+                    if linked_members.insert(name.as_str()) {
+                        lockfile.packages.push(self.install_member(name, member)?);
+                    }
+                    continue;
+                }
+
+                // Registry dependencies are installed together, they share one node_modules tree
+                if let DependencySource::Registry(range) = source {
+                    registry_deps.push(DirectDependency {
+                        parent: dependent.location.clone(),
+                        name,
+                        range,
+                    });
+                    continue;
+                }
+
+                let location = dependent.lock_location(name);
+                let locked = existing
+                    .as_ref()
+                    .and_then(|lockfile| lockfile.find_at(name, location.as_deref()))
+                    .filter(|locked| locked.source == source.to_locked());
+
+                if self.frozen && locked.is_none() {
+                    return Err(InstallError::OutdatedLockfile(format!(
+                        "<cyan>{}<r> is not locked or its source changed",
+                        name
+                    ))
+                    .into());
+                }
+
+                let package = match source {
+                    DependencySource::Path(path) => self.install_path(&dependent, name, path)?,
+                    DependencySource::Git { .. } => {
+                        if git.is_none() {
+                            git = Some(Git::new()?);
+                        }
+                        let git = git.as_ref().unwrap();
+                        self.install_git(git, &dependent, name, &source, locked)?
+                    }
+                    DependencySource::Registry(_) => unreachable!(),
+                };
+
+                log!(
+                    "<green>+<r> {} <d>{}<r>",
+                    package.name,
+                    package.rev.as_deref().unwrap_or(&package.source)
+                );
+                lockfile.packages.push(package);
+            }
+        }
+
+        if !registry_deps.is_empty() {
+            let packages = self.install_registry(&registry_deps, existing.as_ref())?;
+            for package in &packages {
+                let top_level = format!("{}/{}", STORE_DIR, package.name);
+                if package.location.as_deref() == Some(top_level.as_str()) {
+                    log!(
+                        "<green>+<r> {} <d>{}<r>",
+                        package.name,
+                        package.version.as_deref().unwrap_or_default()
+                    );
+                }
+            }
+            lockfile.packages.extend(packages);
+        }
+
+        if self.frozen {
+            let existing = existing.unwrap();
+            if existing.to_toml()? != lockfile.to_toml()? {
+                return Err(InstallError::OutdatedLockfile(
+                    "dependencies don't match the locked ones".to_string(),
+                )
+                .into());
+            }
+        } else {
+            lockfile.write(&self.dir)?;
+        }
+
+        log!(
+            success,
+            "installed {} dependencies <d>[{}]<r>",
+            lockfile.packages.len(),
+            format_time(start.elapsed(), false)
+        );
+
+        Ok(lockfile)
+    }
+
+    fn install_registry(
+        &self,
+        deps: &[DirectDependency],
+        existing: Option<&Lockfile>,
+    ) -> Result<Vec<LockedPackage>> {
+        let config = &self.pkg.toml.install;
+        let client = RegistryClient::new(&RegistryClient::registry_url(config.registry.as_deref()));
+        let trusted = config.trusted_dependencies.as_deref().unwrap_or_default();
+        let locked = existing
+            .map(|lockfile| lockfile.packages.as_slice())
+            .unwrap_or_default();
+
+        NpmInstaller::new(
+            client,
+            &self.dir,
+            self.frozen,
+            self.allow_scripts,
+            trusted,
+            locked,
+        )
+        .install(deps)
+    }
+
+    /// Workspace members are linked into the store of the root, where every member sees them.
+    fn install_member(&self, name: &str, member: &IkeToml) -> Result<LockedPackage> {
+        let member_dir = member.dir().unwrap_or(&self.dir);
+        link_dir(member_dir, &self.store_dir().join(name))?;
+
+        Ok(LockedPackage {
+            name: name.to_string(),
+            source: format!("workspace+{}", relative_location(&self.dir, member_dir)),
+            version: Some(member.toml.package.version.clone()),
+            rev: None,
+            integrity: None,
+            resolved: None,
+            location: None,
+        })
+    }
+
+    /// Path dependencies are linked, so changes to them are visible without reinstalling.
+    fn install_path(&self, dependent: &Dependent, name: &str, path: &str) -> Result<LockedPackage> {
+        let source_dir = fs::normalize_p(dependent.dir.join(path));
+        if !source_dir.exists() {
+            return Err(InstallError::MissingPathDependency(name.to_string(), source_dir).into());
+        }
+
+        link_dir(&source_dir, &dependent.store_dir().join(name))?;
+
+        Ok(LockedPackage {
+            name: name.to_string(),
+            source: DependencySource::Path(path).to_locked(),
+            version: None,
+            rev: None,
+            integrity: None,
+            resolved: None,
+            location: dependent.lock_location(name),
+        })
+    }
+
+    fn install_git(
+        &self,
+        git: &Git,
+        dependent: &Dependent,
+        name: &str,
+        source: &DependencySource,
+        locked: Option<&LockedPackage>,
+    ) -> Result<LockedPackage> {
+        let DependencySource::Git { url, branch, rev } = *source else {
+            unreachable!()
+        };
+        let dest = dependent.store_dir().join(name);
+        let url = git_url(&dependent.dir, url);
+        let locked_rev = locked.and_then(|locked| locked.rev.as_deref());
+
+        let resolved_rev = match locked_rev {
+            // Already checked out at the locked commit
+            Some(locked_rev)
+                if dest.exists() && git.head(&dest).ok().as_deref() == Some(locked_rev) =>
+            {
+                locked_rev.to_string()
+            }
+            Some(locked_rev) => git.checkout(&url, &dest, branch, Some(locked_rev))?,
+            None => git.checkout(&url, &dest, branch, rev)?,
+        };
+
+        let integrity = integrity_of_dir(&dest)?;
+        if let Some(expected) = locked
+            .filter(|locked| locked.rev.as_deref() == Some(resolved_rev.as_str()))
+            .and_then(|locked| locked.integrity.as_ref())
+        {
+            if *expected != integrity {
+                return Err(InstallError::IntegrityMismatch(
+                    name.to_string(),
+                    expected.clone(),
+                    integrity,
+                )
+                .into());
+            }
+        }
+
+        Ok(LockedPackage {
+            name: name.to_string(),
+            source: source.to_locked(),
+            version: None,
+            rev: Some(resolved_rev),
+            integrity: Some(integrity),
+            resolved: None,
+            location: dependent.lock_location(name),
+        })
+    }
+}
+
+/// Relative git remotes are relative to the package, not to the current directory.
+fn git_url(dir: &Path, url: &str) -> String {
+    if url.starts_with("./") || url.starts_with("../") {
+        dir.join(url).to_string_lossy().to_string()
+    } else {
+        url.to_string()
+    }
+}
+
+/// `dir` relative to `base` with forward slashes, as it's written to the lockfile.
+fn relative_location(base: &Path, dir: &Path) -> String {
+    dir.strip_prefix(base)
+        .unwrap_or(dir)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn link_dir(source: &Path, dest: &Path) -> Result<()> {
+    remove_existing(dest)?;
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(source, dest)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_dir(source, dest)?;
+
+    Ok(())
+}
+
+/// Removes a previously installed package, it can be a directory or a link to one.
+pub fn remove_existing(path: &Path) -> Result<()> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return Ok(()),
+    };
+
+    if metadata.is_dir() {
+        std::fs::remove_dir_all(path)?;
+    } else {
+        fs::FileSystem::remove(path, false)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Empty directory for a single test, packages are written into it.
+    fn workspace(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ike-install-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_package(dir: &Path, name: &str, dependencies: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(
+            dir.join("ike.toml"),
+            format!(
+                "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n\n[dependencies]\n{}",
+                name, dependencies
+            ),
+        )
+        .unwrap();
+    }
+
+    fn install(dir: &Path, frozen: bool) -> Result<Lockfile> {
+        let pkg = IkeToml::from_file(dir.join("ike.toml"))?;
+        Installer::new(pkg, frozen, false)?.install()
+    }
+
+    fn install_error(dir: &Path, frozen: bool) -> InstallError {
+        install(dir, frozen)
+            .unwrap_err()
+            .downcast::<InstallError>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_path_dependency() {
+        let root = workspace("path");
+        let app = root.join("app");
+        write_package(&root.join("lib"), "lib", "");
+        write_package(&app, "app", "lib = { path = \"../lib\" }\n");
+
+        let lockfile = install(&app, false).unwrap();
+
+        assert!(std::fs::read_link(app.join("node_modules/lib")).is_ok());
+        assert!(app.join("node_modules/lib/ike.toml").exists());
+        assert_eq!(lockfile.packages.len(), 1);
+        assert_eq!(lockfile.packages[0].source, "path+../lib");
+
+        // What was installed is what's read back
+        assert_eq!(Lockfile::read(&app).unwrap(), Some(lockfile));
+
+        write_package(&app, "app", "lib = { path = \"../missing\" }\n");
+        assert!(matches!(
+            install_error(&app, false),
+            InstallError::MissingPathDependency(..)
+        ));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_frozen_lockfile() {
+        let root = workspace("frozen");
+        let app = root.join("app");
+        write_package(&root.join("lib"), "lib", "");
+        write_package(&root.join("other"), "other", "");
+        write_package(&app, "app", "lib = { path = \"../lib\" }\n");
+
+        assert!(matches!(
+            install_error(&app, true),
+            InstallError::MissingLockfile
+        ));
+
+        let lockfile = install(&app, false).unwrap();
+        assert_eq!(install(&app, true).unwrap(), lockfile);
+
+        // A new dependency and a changed source both make the lockfile outdated
+        write_package(
+            &app,
+            "app",
+            "lib = { path = \"../lib\" }\nother = { path = \"../other\" }\n",
+        );
+        assert!(matches!(
+            install_error(&app, true),
+            InstallError::OutdatedLockfile(..)
+        ));
+        write_package(&app, "app", "lib = { path = \"../other\" }\n");
+        assert!(matches!(
+            install_error(&app, true),
+            InstallError::OutdatedLockfile(..)
+        ));
+        assert_eq!(Lockfile::read(&app).unwrap(), Some(lockfile));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_git_dependency() {
+        if ike_fs::which("git", None, None).is_none() {
+            return;
+        }
+
+        let root = workspace("git");
+        let repo = root.join("repo");
+        let app = root.join("app");
+        write_package(&repo, "repo", "");
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=ike", "-c", "user.email=ike@localhost"])
+                .args(args)
+                .current_dir(&repo)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {} failed", args.join(" "));
+        };
+        git(&["init", "--quiet"]);
+        git(&["add", "ike.toml"]);
+        git(&["commit", "--quiet", "-m", "init"]);
+        write_package(&app, "app", "repo = { git = \"../repo\" }\n");
+
+        let lockfile = install(&app, false).unwrap();
+        let locked = lockfile.find("repo").unwrap();
+
+        assert_eq!(locked.source, "git+../repo");
+        assert_eq!(locked.rev.as_deref().map(str::len), Some(40));
+        assert!(locked.integrity.is_some());
+        assert!(app.join("node_modules/repo/ike.toml").exists());
+        assert_eq!(install(&app, true).unwrap(), lockfile);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
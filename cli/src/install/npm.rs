@@ -0,0 +1,451 @@
+use anyhow::Result;
+use ike_logger::{log, Logger};
+use semver::Version;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    path::Path,
+};
+
+use super::{
+    lockfile::LockedPackage,
+    range::NpmRange,
+    registry::{validate_package_name, PackageVersion, RegistryClient},
+    remove_existing, InstallError, STORE_DIR,
+};
+use crate::task::shell_command;
+
+/// Lifecycle scripts that run after a package is installed, in this order.
+const LIFECYCLE_SCRIPTS: [&str; 3] = ["preinstall", "install", "postinstall"];
+
+/// A registry dependency declared in an ike.toml.
+pub struct DirectDependency<'a> {
+    /// Location of the package that declares it, empty for the root package.
+    pub parent: String,
+    pub name: &'a str,
+    pub range: &'a str,
+}
+
+/// A registry package that has a place in the `node_modules` tree.
+struct Placed {
+    name: String,
+    version: PackageVersion,
+    integrity: Option<String>,
+}
+
+/// Installs registry dependencies into a hoisted `node_modules` tree, like npm does: a
+/// package goes to the top level unless another version of it is already there, in which
+/// case it's nested into the `node_modules` of the package that depends on it.
+pub struct NpmInstaller<'a> {
+    client: RegistryClient,
+    dir: &'a Path,
+    frozen: bool,
+    allow_scripts: bool,
+    trusted: &'a [String],
+    /// Locked packages by location, e.g. `node_modules/a/node_modules/b`.
+    locked: HashMap<String, &'a LockedPackage>,
+    placed: BTreeMap<String, Placed>,
+}
+
+impl<'a> NpmInstaller<'a> {
+    pub fn new(
+        client: RegistryClient,
+        dir: &'a Path,
+        frozen: bool,
+        allow_scripts: bool,
+        trusted: &'a [String],
+        locked: &'a [LockedPackage],
+    ) -> Self {
+        let locked = locked
+            .iter()
+            .filter_map(|pkg| Some((pkg.location.clone()?, pkg)))
+            .collect();
+
+        Self {
+            client,
+            dir,
+            frozen,
+            allow_scripts,
+            trusted,
+            locked,
+            placed: BTreeMap::new(),
+        }
+    }
+
+    /// Installs `deps` with all of their dependencies and returns the packages to lock.
+    pub fn install(mut self, deps: &[DirectDependency]) -> Result<Vec<LockedPackage>> {
+        // Breadth first, so direct dependencies always end up on the top level
+        let mut queue: VecDeque<(String, String, String, bool)> = deps
+            .iter()
+            .map(|dep| {
+                let (name, range) = (dep.name.to_string(), dep.range.to_string());
+                (name, range, dep.parent.clone(), false)
+            })
+            .collect();
+
+        while let Some((name, range, parent, optional)) = queue.pop_front() {
+            // Names come from manifests and packuments, they must not escape `node_modules`
+            validate_package_name(&name)?;
+
+            let location = match self.place(&name, &range, &parent)? {
+                Some(location) => location,
+                None => continue,
+            };
+
+            let version = match self.resolve(&name, &range, &location) {
+                Ok(version) => version,
+                Err(e) if optional => {
+                    log!(warn, "optional dependency <cyan>{}<r> skipped: {}", name, e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            let dependencies = version
+                .dependencies
+                .iter()
+                .map(|dep| (dep, false))
+                .chain(version.optional_dependencies.iter().map(|dep| (dep, true)));
+            for ((dep_name, dep_range), optional) in dependencies {
+                queue.push_back((
+                    dep_name.clone(),
+                    dep_range.clone(),
+                    location.clone(),
+                    optional,
+                ));
+            }
+
+            self.placed.insert(
+                location,
+                Placed {
+                    name,
+                    version,
+                    integrity: None,
+                },
+            );
+        }
+
+        self.download()?;
+        self.run_scripts()?;
+
+        let source = format!("registry+{}", self.client.url());
+        Ok(self
+            .placed
+            .into_iter()
+            .map(|(location, placed)| LockedPackage {
+                name: placed.name,
+                source: source.clone(),
+                version: Some(placed.version.version),
+                rev: None,
+                integrity: placed.integrity,
+                resolved: Some(placed.version.dist.tarball),
+                location: Some(location),
+            })
+            .collect())
+    }
+
+    /// Finds where `name` goes when `parent` depends on it. Returns `None` when a version
+    /// that is visible from `parent` already satisfies `range`.
+    fn place(&self, name: &str, range: &str, parent: &str) -> Result<Option<String>> {
+        let npm_range = NpmRange::parse(range).map_err(anyhow::Error::msg)?;
+
+        // Walk up like node does when resolving `name` from `parent`
+        let mut ancestor = Some(parent);
+        while let Some(dir) = ancestor {
+            let location = join_location(dir, name);
+            if let Some(placed) = self.placed.get(&location) {
+                let satisfied = Version::parse(&placed.version.version)
+                    .map(|version| npm_range.matches(&version))
+                    .unwrap_or(false);
+
+                return Ok(match satisfied {
+                    true => None,
+                    false => Some(join_location(parent, name)),
+                });
+            }
+            ancestor = parent_location(dir);
+        }
+
+        Ok(Some(join_location("", name)))
+    }
+
+    /// Picks the version to install at `location`, the locked one wins while it still
+    /// satisfies `range`.
+    fn resolve(&mut self, name: &str, range: &str, location: &str) -> Result<PackageVersion> {
+        let locked = self
+            .locked
+            .get(location)
+            .filter(|locked| locked.name == name)
+            .and_then(|locked| locked.version.clone());
+
+        if let Some(version) = locked {
+            let satisfied = NpmRange::parse(range)
+                .map_err(anyhow::Error::msg)?
+                .matches(&Version::parse(&version)?);
+
+            if satisfied {
+                if let Some(found) = self.client.packument(name)?.versions.get(&version) {
+                    return Ok(found.clone());
+                }
+            }
+        }
+
+        if self.frozen {
+            return Err(InstallError::OutdatedLockfile(format!(
+                "<cyan>{}@{}<r> is not locked",
+                name, range
+            ))
+            .into());
+        }
+
+        self.client.resolve(name, range)
+    }
+
+    fn download(&mut self) -> Result<()> {
+        for (location, placed) in self.placed.iter_mut() {
+            let dest = self.dir.join(location);
+            let locked = self
+                .locked
+                .get(location)
+                .filter(|locked| locked.version.as_ref() == Some(&placed.version.version));
+
+            // Already extracted by a previous install
+            if let Some(locked) = locked {
+                if installed_version(&dest).as_ref() == Some(&placed.version.version) {
+                    placed.integrity = locked.integrity.clone();
+                    continue;
+                }
+            }
+
+            remove_existing(&dest)?;
+            let integrity = self.client.download(&placed.name, &placed.version, &dest)?;
+
+            if let Some(expected) = locked.and_then(|locked| locked.integrity.as_ref()) {
+                if *expected != integrity {
+                    return Err(InstallError::IntegrityMismatch(
+                        placed.name.clone(),
+                        expected.clone(),
+                        integrity,
+                    )
+                    .into());
+                }
+            }
+
+            placed.integrity = Some(integrity);
+        }
+
+        Ok(())
+    }
+
+    /// Runs lifecycle scripts of trusted packages, deepest packages first since packages
+    /// may need their dependencies to be built.
+    fn run_scripts(&self) -> Result<()> {
+        let mut locations: Vec<&String> = self.placed.keys().collect();
+        locations.sort_by_key(|location| std::cmp::Reverse(location.matches(STORE_DIR).count()));
+
+        for location in locations {
+            let placed = &self.placed[location];
+            let scripts: Vec<(&str, &String)> = LIFECYCLE_SCRIPTS
+                .iter()
+                .filter_map(|event| Some((*event, placed.version.scripts.get(*event)?)))
+                .collect();
+
+            if scripts.is_empty() {
+                continue;
+            }
+
+            if !self.allow_scripts && !self.trusted.contains(&placed.name) {
+                log!(
+                    warn,
+                    "lifecycle scripts of <cyan>{}<r> were not run, add it to <cyan>trusted-dependencies<r> in ike.toml to allow them",
+                    placed.name
+                );
+                continue;
+            }
+
+            let dir = self.dir.join(location);
+            for (event, script) in scripts {
+                log!("<d>{} {}:<r> {}", placed.name, event, script);
+                run_script(&dir, &placed.name, event, script)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn run_script(dir: &Path, name: &str, event: &str, script: &str) -> Result<()> {
+    let status = shell_command(script)
+        .current_dir(dir)
+        .env("npm_lifecycle_event", event)
+        .env("npm_package_name", name)
+        .status()?;
+
+    if !status.success() {
+        return Err(InstallError::ScriptFailed(name.to_string(), event.to_string()).into());
+    }
+
+    Ok(())
+}
+
+/// Version from the package.json of an installed package.
+fn installed_version(dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(dir.join("package.json")).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+
+    json.get("version")?.as_str().map(str::to_string)
+}
+
+fn join_location(parent: &str, name: &str) -> String {
+    match parent.is_empty() {
+        true => format!("{}/{}", STORE_DIR, name),
+        false => format!("{}/{}/{}", parent, STORE_DIR, name),
+    }
+}
+
+/// Location of the package that contains `location`, `Some("")` is the project itself.
+fn parent_location(location: &str) -> Option<&str> {
+    if location.is_empty() {
+        return None;
+    }
+
+    let marker = format!("/{}/", STORE_DIR);
+    Some(match location.rfind(&marker) {
+        Some(index) => &location[..index],
+        None => "",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Local registry with packuments and tarballs for the tests, see tests/registry.
+    fn registry() -> RegistryClient {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/registry");
+        RegistryClient::new(&dir.to_string_lossy())
+    }
+
+    /// Empty project directory for a single test.
+    fn project(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ike-npm-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn install(
+        dir: &Path,
+        deps: &[(&str, &str)],
+        locked: &[LockedPackage],
+    ) -> Result<Vec<LockedPackage>> {
+        let deps: Vec<DirectDependency> = deps
+            .iter()
+            .map(|(name, range)| DirectDependency {
+                parent: String::new(),
+                name,
+                range,
+            })
+            .collect();
+
+        NpmInstaller::new(registry(), dir, false, false, &[], locked).install(&deps)
+    }
+
+    fn install_error(dir: &Path, deps: &[(&str, &str)], locked: &[LockedPackage]) -> InstallError {
+        install(dir, deps, locked)
+            .unwrap_err()
+            .downcast::<InstallError>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_hoisted_layout() {
+        let dir = project("hoisted");
+        let packages = install(
+            &dir,
+            &[("a", "^1.0.0"), ("b", "^1.0.0"), ("c", "^1.0.0")],
+            &[],
+        )
+        .unwrap();
+
+        let locations: Vec<(&str, &str)> = packages
+            .iter()
+            .map(|pkg| {
+                (
+                    pkg.location.as_deref().unwrap(),
+                    pkg.version.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        // `b` needs another `a` than the hoisted one, `c` shares it
+        assert_eq!(
+            locations,
+            [
+                ("node_modules/a", "1.0.0"),
+                ("node_modules/b", "1.0.0"),
+                ("node_modules/b/node_modules/a", "2.0.0"),
+                ("node_modules/c", "1.0.0"),
+            ]
+        );
+        assert!(packages.iter().all(|pkg| pkg.integrity.is_some()));
+        assert_eq!(
+            installed_version(&dir.join("node_modules/b/node_modules/a")).as_deref(),
+            Some("2.0.0")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_integrity_mismatch() {
+        let dir = project("integrity");
+
+        let err = install_error(&dir, &[("tampered", "1.0.0")], &[]);
+        assert!(matches!(err, InstallError::IntegrityMismatch(..)));
+        assert!(!dir.join("node_modules/tampered/package.json").exists());
+
+        // The locked integrity has to match as well
+        let locked = LockedPackage {
+            name: "a".to_string(),
+            source: format!("registry+{}", registry().url()),
+            version: Some("1.0.0".to_string()),
+            rev: None,
+            integrity: Some("sha512-changed".to_string()),
+            resolved: Some("a-1.0.0.tgz".to_string()),
+            location: Some("node_modules/a".to_string()),
+        };
+        let err = install_error(&dir, &[("a", "^1.0.0")], &[locked]);
+        assert!(matches!(err, InstallError::IntegrityMismatch(..)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_no_matching_version() {
+        let dir = project("no-match");
+
+        match install_error(&dir, &[("a", "^3.0.0")], &[]) {
+            InstallError::NoMatchingVersion(name, range) => {
+                assert_eq!((name, range), ("a".to_string(), "^3.0.0".to_string()))
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_escaping_names() {
+        let dir = project("escape");
+
+        // Directly and as a dependency from a packument
+        let err = install_error(&dir, &[("../escape", "1.0.0")], &[]);
+        assert!(matches!(err, InstallError::InvalidPackageName(..)));
+        let err = install_error(&dir, &[("evil", "1.0.0")], &[]);
+        assert!(matches!(err, InstallError::InvalidPackageName(name) if name == "../escape"));
+        assert!(!dir.join("escape").exists());
+        assert!(!dir.join("node_modules/evil").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+use semver::{Version, VersionReq};
+
+/// A npm version range, e.g. `^1.2.0 || >=2 <3`. npm separates comparators with spaces and
+/// allows hyphen ranges, so it's translated into one [`VersionReq`] per `||` alternative.
+#[derive(Debug, Clone)]
+pub struct NpmRange {
+    alternatives: Vec<VersionReq>,
+}
+
+impl NpmRange {
+    pub fn parse(range: &str) -> Result<Self, String> {
+        let mut alternatives = Vec::new();
+
+        for alternative in range.split("||") {
+            let req = translate(alternative.trim());
+            let req = VersionReq::parse(&req)
+                .map_err(|e| format!("Invalid version range `{}`: {}", range, e))?;
+            alternatives.push(req);
+        }
+
+        Ok(Self { alternatives })
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.alternatives.iter().any(|req| req.matches(version))
+    }
+
+    /// Highest version from `versions` that satisfies the range.
+    pub fn max_satisfying<'a, I>(&self, versions: I) -> Option<&'a Version>
+    where
+        I: IntoIterator<Item = &'a Version>,
+    {
+        versions
+            .into_iter()
+            .filter(|version| self.matches(version))
+            .max()
+    }
+}
+
+fn translate(range: &str) -> String {
+    if range.is_empty() || range == "*" || range == "latest" || range == "x" {
+        return "*".to_string();
+    }
+
+    // `1.2.3 - 2.3.4` is `>=1.2.3, <=2.3.4`
+    if let Some((from, to)) = range.split_once(" - ") {
+        return format!(">={}, <={}", from.trim(), to.trim());
+    }
+
+    let mut comparators = Vec::new();
+    let mut pending_operator = String::new();
+    for part in range.split_whitespace() {
+        // `>= 1.2.3` is the same as `>=1.2.3`
+        if part
+            .chars()
+            .all(|c| matches!(c, '<' | '>' | '=' | '~' | '^'))
+        {
+            pending_operator.push_str(part);
+            continue;
+        }
+
+        let part = part.strip_prefix('v').unwrap_or(part);
+        comparators.push(format!("{}{}", std::mem::take(&mut pending_operator), part));
+    }
+
+    comparators
+        .iter()
+        .map(|comparator| {
+            // A bare version means an exact match in npm, but a caret range in cargo.
+            if comparator.starts_with(|c: char| c.is_ascii_digit())
+                && !comparator.contains(['x', 'X', '*'])
+            {
+                format!("={}", comparator)
+            } else {
+                comparator.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(range: &str, version: &str) -> bool {
+        NpmRange::parse(range)
+            .unwrap()
+            .matches(&Version::parse(version).unwrap())
+    }
+
+    #[test]
+    fn test_npm_ranges() {
+        assert!(matches("^1.2.0", "1.9.0"));
+        assert!(!matches("^1.2.0", "2.0.0"));
+        assert!(matches("1.2.3", "1.2.3"));
+        assert!(!matches("1.2.3", "1.2.4"));
+        assert!(matches(">=1.0.0 <2.0.0", "1.5.0"));
+        assert!(matches(">= 1.0.0", "3.0.0"));
+        assert!(matches("1.0.0 - 1.5.0", "1.5.0"));
+        assert!(matches("^1.0.0 || ^2.0.0", "2.1.0"));
+        assert!(matches("1.x", "1.4.0"));
+        assert!(matches("*", "0.0.1"));
+    }
+
+    #[test]
+    fn test_max_satisfying() {
+        let versions: Vec<Version> = ["1.0.0", "1.2.0", "2.0.0"]
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect();
+        let range = NpmRange::parse("^1.0.0").unwrap();
+
+        assert_eq!(
+            range.max_satisfying(&versions),
+            Some(&Version::parse("1.2.0").unwrap())
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::Result;
+use flate2::read::GzDecoder;
+use isahc::{config::Configurable, ReadResponseExt, Request, RequestExt};
+use semver::Version;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+use url::Url;
+
+use super::{
+    integrity::verify_integrity,
+    range::NpmRange,
+    InstallError::{self, FailedToFetch, IntegrityMismatch, InvalidPackageName, NoMatchingVersion},
+};
+
+pub const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org";
+
+/// Full or abbreviated packument, only the fields needed for installing are parsed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Packument {
+    pub name: String,
+    #[serde(default, rename = "dist-tags")]
+    pub dist_tags: HashMap<String, String>,
+    #[serde(default)]
+    pub versions: HashMap<String, PackageVersion>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageVersion {
+    pub version: String,
+    #[serde(default)]
+    pub dependencies: HashMap<String, String>,
+    #[serde(default)]
+    pub optional_dependencies: HashMap<String, String>,
+    #[serde(default)]
+    pub scripts: HashMap<String, String>,
+    pub dist: Dist,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Dist {
+    pub tarball: String,
+    pub integrity: Option<String>,
+    pub shasum: Option<String>,
+}
+
+/// Client for npm compatible registries. Besides http(s) registries, a directory (or a
+/// `file://` URL) laid out like a registry is supported: `<dir>/<name>/index.json` holds
+/// the packument and tarballs live next to it, which is what `ike publish` writes.
+#[derive(Debug)]
+pub struct RegistryClient {
+    url: String,
+    packuments: HashMap<String, Packument>,
+}
+
+impl RegistryClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            packuments: HashMap::new(),
+        }
+    }
+
+    /// Registry from `IKE_REGISTRY`, then from ike.toml, then the public npm registry.
+    pub fn registry_url(configured: Option<&str>) -> String {
+        std::env::var("IKE_REGISTRY")
+            .ok()
+            .filter(|url| !url.is_empty())
+            .or_else(|| configured.map(str::to_string))
+            .unwrap_or_else(|| DEFAULT_REGISTRY.to_string())
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Directory of a local registry, `None` for http(s) registries.
+    pub fn local_dir(&self) -> Option<PathBuf> {
+        if self.url.starts_with("http://") || self.url.starts_with("https://") {
+            return None;
+        }
+
+        match Url::parse(&self.url) {
+            Ok(url) if url.scheme() == "file" => url.to_file_path().ok(),
+            _ => Some(PathBuf::from(&self.url)),
+        }
+    }
+
+    fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            let mut response = Request::get(url)
+                .header(
+                    "Accept",
+                    "application/vnd.npm.install-v1+json; q=1.0, application/json; q=0.8",
+                )
+                .redirect_policy(isahc::config::RedirectPolicy::Follow)
+                .body(())?
+                .send()?;
+
+            if !response.status().is_success() {
+                return Err(FailedToFetch(url.to_string(), response.status().to_string()).into());
+            }
+
+            return Ok(response.bytes()?);
+        }
+
+        let path = match Url::parse(url) {
+            Ok(parsed) if parsed.scheme() == "file" => parsed
+                .to_file_path()
+                .map_err(|_| FailedToFetch(url.to_string(), "invalid file URL".to_string()))?,
+            _ => PathBuf::from(url),
+        };
+
+        std::fs::read(&path).map_err(|e| FailedToFetch(url.to_string(), e.to_string()).into())
+    }
+
+    pub fn packument(&mut self, name: &str) -> Result<&Packument> {
+        validate_package_name(name)?;
+
+        if !self.packuments.contains_key(name) {
+            let url = match self.local_dir() {
+                Some(dir) => dir
+                    .join(name)
+                    .join("index.json")
+                    .to_string_lossy()
+                    .to_string(),
+                // Scoped packages are requested as `@scope%2fname`
+                None => format!("{}/{}", self.url, name.replace('/', "%2f")),
+            };
+            let bytes = self.fetch(&url)?;
+            let packument: Packument = serde_json::from_slice(&bytes)
+                .map_err(|e| FailedToFetch(url.clone(), e.to_string()))?;
+
+            self.packuments.insert(name.to_string(), packument);
+        }
+
+        Ok(self.packuments.get(name).unwrap())
+    }
+
+    /// Picks the highest version of `name` that satisfies `range`, dist-tags like `latest`
+    /// can be used as a range too.
+    pub fn resolve(&mut self, name: &str, range: &str) -> Result<PackageVersion> {
+        let packument = self.packument(name)?;
+
+        if let Some(version) = packument.dist_tags.get(range) {
+            if let Some(found) = packument.versions.get(version) {
+                return Ok(found.clone());
+            }
+        }
+
+        let npm_range = NpmRange::parse(range).map_err(anyhow::Error::msg)?;
+        let versions: Vec<Version> = packument
+            .versions
+            .keys()
+            .filter_map(|version| Version::parse(version).ok())
+            .collect();
+
+        // Prefer `latest` if it satisfies the range, that's what npm does.
+        let latest = packument
+            .dist_tags
+            .get("latest")
+            .and_then(|latest| Version::parse(latest).ok())
+            .filter(|latest| npm_range.matches(latest));
+
+        // Versions are looked up by their normalized form, which a packument may not use
+        let found = latest
+            .or_else(|| npm_range.max_satisfying(&versions).cloned())
+            .and_then(|version| packument.versions.get(&version.to_string()));
+
+        match found {
+            Some(found) => Ok(found.clone()),
+            None => Err(NoMatchingVersion(name.to_string(), range.to_string()).into()),
+        }
+    }
+
+    /// Absolute URL of a tarball, tarballs of local registries can be relative to the package.
+    pub fn tarball_url(&self, name: &str, tarball: &str) -> String {
+        if tarball.contains("://") || Path::new(tarball).is_absolute() {
+            return tarball.to_string();
+        }
+
+        match self.local_dir() {
+            Some(dir) => dir.join(name).join(tarball).to_string_lossy().to_string(),
+            None => format!("{}/{}", self.url, tarball.trim_start_matches('/')),
+        }
+    }
+
+    /// Downloads the tarball of `version`, checks its integrity and extracts it into `dest`.
+    /// Returns the integrity of the tarball.
+    pub fn download(&self, name: &str, version: &PackageVersion, dest: &Path) -> Result<String> {
+        let url = self.tarball_url(name, &version.dist.tarball);
+        let bytes = self.fetch(&url)?;
+
+        let integrity = match verify_integrity(
+            &bytes,
+            version.dist.integrity.as_deref(),
+            version.dist.shasum.as_deref(),
+        ) {
+            Ok(integrity) => integrity,
+            Err(actual) => {
+                return Err(IntegrityMismatch(
+                    format!("{}@{}", name, version.version),
+                    version
+                        .dist
+                        .integrity
+                        .clone()
+                        .or_else(|| version.dist.shasum.clone())
+                        .unwrap_or_default(),
+                    actual,
+                )
+                .into())
+            }
+        };
+
+        extract_tarball(&bytes, dest)?;
+
+        Ok(integrity)
+    }
+}
+
+/// Package names end up in paths, like `node_modules/<name>` or the directory of a local
+/// registry, so they can only be a single directory name or `@scope/name`.
+pub fn validate_package_name(name: &str) -> Result<(), InstallError> {
+    let segments = match name.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('/') {
+            Some((scope, unscoped)) => vec![scope, unscoped],
+            None => return Err(InvalidPackageName(name.to_string())),
+        },
+        None => vec![name],
+    };
+
+    let valid = segments.iter().all(|segment| {
+        !segment.is_empty() && !segment.starts_with('.') && !segment.contains(['/', '\\', ':'])
+    });
+
+    match valid {
+        true => Ok(()),
+        false => Err(InvalidPackageName(name.to_string())),
+    }
+}
+
+/// Extracts a gzipped npm tarball into `dest`. npm tarballs keep everything in a single top
+/// level directory (usually `package/`), which is stripped.
+pub fn extract_tarball(bytes: &[u8], dest: &Path) -> Result<()> {
+    std::fs::create_dir_all(dest)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(bytes));
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_path_buf();
+
+        // Skip the top level directory and anything that would escape `dest`
+        let relative: PathBuf = path.components().skip(1).collect();
+        if relative.as_os_str().is_empty()
+            || relative
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            continue;
+        }
+
+        let target = dest.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if entry.header().entry_type().is_file() {
+            entry.unpack(&target)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_package_name() {
+        assert!(validate_package_name("left-pad").is_ok());
+        assert!(validate_package_name("@types/node").is_ok());
+        assert!(validate_package_name("..").is_err());
+        assert!(validate_package_name("../evil").is_err());
+        assert!(validate_package_name("/etc").is_err());
+        assert!(validate_package_name("a/b").is_err());
+        assert!(validate_package_name("a\\b").is_err());
+        assert!(validate_package_name("@scope/../evil").is_err());
+        assert!(validate_package_name("@scope/a/b").is_err());
+        assert!(validate_package_name("@/name").is_err());
+        assert!(validate_package_name("").is_err());
+    }
+}
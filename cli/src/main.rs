@@ -1,15 +1,18 @@
 pub mod cli;
+pub mod dotenv;
 mod error;
 pub mod format;
 pub mod fs;
 pub mod globals;
+pub mod install;
 pub mod macros;
+pub mod pack;
 pub mod panic_handler;
 pub mod runtime;
+pub mod task;
 pub mod testing;
 pub mod transpiler;
 mod utils;
-mod which;
 
 use anyhow::Result;
 use cli::cli::Cli;
@@ -20,9 +23,6 @@ use panic_handler::setup_panic_handler;
 async fn main() -> Result<()> {
     setup_panic_handler();
 
-    // TODO: fix this
-    // dotenvy::dotenv()?;
-
     let cli = Cli::new();
     match cli.run().await {
         Ok(_) => {}
@@ -0,0 +1,491 @@
+use anyhow::Result;
+use flate2::{Compression, GzBuilder};
+use glob::Pattern;
+use ike_toml::IkeToml;
+use regex::{Captures, Regex};
+use serde_json::{json, Map};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+use crate::error::IkeError::CouldNotFindIkeToml;
+use crate::install::{
+    integrity::{integrity_of_bytes, shasum_of_bytes},
+    DependencySource, STORE_DIR,
+};
+use crate::transpiler::{declarations, transpile};
+
+pub mod publish;
+
+/// Modification time of every file in a tarball, the same one npm uses. A fixed time keeps
+/// tarballs of the same sources byte for byte identical.
+const FIXED_MTIME: u64 = 499162500;
+
+/// Never packed, even if `files` matches them.
+const IGNORED: [&str; 4] = [".git", STORE_DIR, "ike.lock", ".DS_Store"];
+
+#[derive(Error, Debug)]
+pub enum PackError {
+    #[error("<cyan>package.{0}<r> is required to pack a package")]
+    MissingPackageField(&'static str),
+    #[error("Invalid pattern {0} in <cyan>package.files<r>: {1}")]
+    InvalidFilesPattern(String, String),
+    #[error("Dependency <cyan>{0}<r> can't be published, only registry dependencies can")]
+    UnpublishableDependency(String),
+    #[error("<cyan>{0}@{1}<r> is already published")]
+    VersionAlreadyPublished(String, String),
+    #[error("Failed to publish to {0}: {1}")]
+    PublishFailed(String, String),
+    #[error("Invalid <cyan>package.version<r> {0}: {1}")]
+    InvalidVersion(String, String),
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PackOptions {
+    /// Ship TypeScript sources as JavaScript.
+    pub transpile: bool,
+    /// Emit `.d.ts` declarations next to the transpiled files.
+    pub declarations: bool,
+}
+
+pub struct Packed {
+    pub name: String,
+    pub version: String,
+    /// npm style tarball name, `@scope/name` becomes `scope-name-1.0.0.tgz`.
+    pub file_name: String,
+    /// Paths inside the package, sorted.
+    pub files: Vec<String>,
+    pub bytes: Vec<u8>,
+    pub integrity: String,
+    pub shasum: String,
+    /// package.json written into the tarball, registries use it as the version's metadata.
+    pub manifest: serde_json::Value,
+}
+
+/// Packs the package into a gzipped tarball with the same layout npm uses, everything lives
+/// in a top level `package/` directory.
+pub fn pack(pkg: &IkeToml, options: PackOptions) -> Result<Packed> {
+    let dir = pkg.dir().ok_or(CouldNotFindIkeToml)?;
+    let package = &pkg.toml.package;
+    if package.name.is_empty() {
+        return Err(PackError::MissingPackageField("name").into());
+    }
+    if package.version.is_empty() {
+        return Err(PackError::MissingPackageField("version").into());
+    }
+
+    // Files of every feature are shipped, features are picked by whoever installs the package
+    let patterns = package.files.as_ref().map(|files| {
+        let mut features: Vec<_> = pkg.toml.features.iter().collect();
+        features.sort_by_key(|(name, _)| name.as_str());
+
+        let feature_files = features.into_iter().flat_map(|(_, feature)| &feature.files);
+        files
+            .iter()
+            .chain(feature_files)
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+
+    let mut contents: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    for file in collect_files(dir, patterns.as_deref())? {
+        let path = dir.join(&file);
+
+        if options.transpile && is_typescript(&file) {
+            let code = rewrite_import_extensions(&transpile(&path)?);
+            contents.insert(js_path(&file), code.into_bytes());
+            if options.declarations {
+                let types = rewrite_import_extensions(&declarations(&path)?);
+                contents.insert(declaration_path(&file), types.into_bytes());
+            }
+        } else {
+            contents.insert(file, fs::read(&path)?);
+        }
+    }
+
+    if options.transpile {
+        let ike_toml = String::from_utf8_lossy(&contents["ike.toml"]).to_string();
+        let rewritten = rewrite_manifest(&ike_toml, options.declarations)?;
+        contents.insert("ike.toml".to_string(), rewritten.into_bytes());
+    }
+
+    let manifest = package_json(pkg, &contents, options)?;
+    if !contents.contains_key("package.json") {
+        contents.insert(
+            "package.json".to_string(),
+            serde_json::to_vec_pretty(&manifest)?,
+        );
+    }
+
+    let bytes = write_tarball(&contents)?;
+
+    Ok(Packed {
+        name: package.name.clone(),
+        version: package.version.clone(),
+        file_name: tarball_name(&package.name, &package.version),
+        files: contents.keys().cloned().collect(),
+        integrity: integrity_of_bytes(&bytes),
+        shasum: shasum_of_bytes(&bytes),
+        bytes,
+        manifest,
+    })
+}
+
+pub fn tarball_name(name: &str, version: &str) -> String {
+    format!(
+        "{}-{}.tgz",
+        name.trim_start_matches('@').replace('/', "-"),
+        version
+    )
+}
+
+/// Files selected by `package.files`, relative to `dir`. Without `files` everything is
+/// packed. ike.toml, README and LICENSE files are always included.
+fn collect_files(dir: &Path, patterns: Option<&[String]>) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+
+    match patterns {
+        Some(patterns) => {
+            for pattern in patterns {
+                let full = dir.join(pattern);
+                // A plain directory means everything in it
+                if full.is_dir() {
+                    walk(dir, &full, &mut files)?;
+                    continue;
+                }
+
+                let matches = glob::glob(&full.to_string_lossy())
+                    .map_err(|e| PackError::InvalidFilesPattern(pattern.clone(), e.to_string()))?;
+                for path in matches.flatten() {
+                    match path.is_dir() {
+                        true => walk(dir, &path, &mut files)?,
+                        false => files.push(path),
+                    }
+                }
+            }
+        }
+        None => walk(dir, dir, &mut files)?,
+    }
+
+    let always = [
+        Pattern::new("ike.toml")?,
+        Pattern::new("readme*")?,
+        Pattern::new("license*")?,
+        Pattern::new("licence*")?,
+    ];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let name = name.to_lowercase();
+
+        if path.is_file() && always.iter().any(|pattern| pattern.matches(&name)) {
+            files.push(path);
+        }
+    }
+
+    let mut relative: Vec<String> = files
+        .iter()
+        .filter_map(|file| file.strip_prefix(dir).ok())
+        .filter(|file| !is_ignored(file))
+        .map(|file| file.to_string_lossy().replace('\\', "/"))
+        .collect();
+    relative.sort();
+    relative.dedup();
+
+    Ok(relative)
+}
+
+fn walk(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if is_ignored(path.strip_prefix(root).unwrap_or(&path)) {
+            continue;
+        }
+
+        match path.is_dir() {
+            true => walk(root, &path, files)?,
+            false => files.push(path),
+        }
+    }
+
+    Ok(())
+}
+
+fn is_ignored(path: &Path) -> bool {
+    let is_tarball = path.extension().is_some_and(|ext| ext == "tgz");
+
+    is_tarball
+        || path
+            .components()
+            .any(|c| IGNORED.iter().any(|ignored| c.as_os_str() == *ignored))
+}
+
+fn is_typescript(file: &str) -> bool {
+    let is_declaration = [".d.ts", ".d.mts", ".d.cts"]
+        .iter()
+        .any(|ext| file.ends_with(ext));
+
+    !is_declaration
+        && [".ts", ".mts", ".cts", ".tsx"]
+            .iter()
+            .any(|ext| file.ends_with(ext))
+}
+
+/// `src/index.ts` becomes `src/index.js`, `.mts` and `.cts` become `.mjs` and `.cjs`.
+fn js_path(file: &str) -> String {
+    for (ts, js) in [
+        (".mts", ".mjs"),
+        (".cts", ".cjs"),
+        (".tsx", ".js"),
+        (".ts", ".js"),
+    ] {
+        if let Some(stem) = file.strip_suffix(ts) {
+            return format!("{}{}", stem, js);
+        }
+    }
+
+    file.to_string()
+}
+
+/// Relative imports of `.ts` files have to point to the transpiled `.js` files.
+fn rewrite_import_extensions(code: &str) -> String {
+    let re = Regex::new(r#"((?:from|import)\s*\(?\s*)(['"])(\.{1,2}/[^'"]+?\.(?:m|c)?tsx?)(['"])"#)
+        .unwrap();
+
+    re.replace_all(code, |caps: &Captures| {
+        format!("{}{}{}{}", &caps[1], &caps[2], js_path(&caps[3]), &caps[4])
+    })
+    .to_string()
+}
+
+fn declaration_path(file: &str) -> String {
+    for (ts, dts) in [
+        (".mts", ".d.mts"),
+        (".cts", ".d.cts"),
+        (".tsx", ".d.ts"),
+        (".ts", ".d.ts"),
+    ] {
+        if let Some(stem) = file.strip_suffix(ts) {
+            return format!("{}{}", stem, dts);
+        }
+    }
+
+    file.to_string()
+}
+
+/// Points `main` and `exports` of a packed ike.toml to the transpiled files. With
+/// declarations, `types` is filled in where it's missing.
+fn rewrite_manifest(content: &str, declarations: bool) -> Result<String> {
+    let mut doc: DocumentMut = content.parse()?;
+
+    if let Some(package) = doc.get_mut("package").and_then(Item::as_table_like_mut) {
+        rewrite_entry(package, "main", declarations);
+        if let Some(exports) = package.get_mut("exports") {
+            rewrite_exports(exports, declarations);
+        }
+    }
+    if let Some(exports) = doc.get_mut("exports") {
+        rewrite_exports(exports, declarations);
+    }
+
+    Ok(doc.to_string())
+}
+
+fn rewrite_exports(exports: &mut Item, declarations: bool) {
+    if let Some(exports) = exports.as_table_like_mut() {
+        for (_, export) in exports.iter_mut() {
+            if let Some(export) = export.as_table_like_mut() {
+                rewrite_entry(export, "import", declarations);
+            }
+        }
+    }
+}
+
+fn rewrite_entry(table: &mut dyn TableLike, key: &str, declarations: bool) {
+    let Some(value) = table.get_mut(key).and_then(Item::as_value_mut) else {
+        return;
+    };
+    let file = match value.as_str() {
+        Some(file) if is_typescript(file) => file.to_string(),
+        _ => return,
+    };
+
+    // Keep comments around the value
+    let decor = value.decor().clone();
+    *value = Value::from(js_path(&file));
+    *value.decor_mut() = decor;
+
+    if declarations && !table.contains_key("types") {
+        table.insert("types", toml_edit::value(declaration_path(&file)));
+    }
+}
+
+/// package.json of the packed package. An existing one is kept as it is, otherwise it's
+/// generated from ike.toml so npm and other tools understand the package too.
+fn package_json(
+    pkg: &IkeToml,
+    contents: &BTreeMap<String, Vec<u8>>,
+    options: PackOptions,
+) -> Result<serde_json::Value> {
+    if let Some(existing) = contents.get("package.json") {
+        return Ok(serde_json::from_slice(existing)?);
+    }
+
+    let package = &pkg.toml.package;
+    let mut manifest = Map::new();
+    manifest.insert("name".into(), json!(package.name));
+    manifest.insert("version".into(), json!(package.version));
+    manifest.insert("type".into(), json!("module"));
+
+    if let Some(description) = &package.description {
+        manifest.insert("description".into(), json!(description));
+    }
+
+    let entry = |file: &String| match options.transpile && is_typescript(file) {
+        true => js_path(file),
+        false => file.clone(),
+    };
+    if let Some(main) = &package.main {
+        manifest.insert("main".into(), json!(entry(main)));
+    }
+    if let Some(types) = &package.types {
+        manifest.insert("types".into(), json!(types));
+    }
+
+    if let Some(exports) = &pkg.toml.exports {
+        let exports: Map<String, serde_json::Value> = exports
+            .iter()
+            .map(|(key, export)| {
+                let key = match key.as_str() {
+                    "." | "" => ".".to_string(),
+                    key if key.starts_with("./") => key.to_string(),
+                    key => format!("./{}", key),
+                };
+                let mut conditions = Map::new();
+                if let Some(types) = &export.types {
+                    conditions.insert("types".into(), json!(format!("./{}", types)));
+                }
+                conditions.insert(
+                    "import".into(),
+                    json!(format!("./{}", entry(&export.import))),
+                );
+                (key, serde_json::Value::Object(conditions))
+            })
+            .collect();
+        manifest.insert("exports".into(), serde_json::Value::Object(exports));
+    }
+
+    let mut dependencies = Map::new();
+    for (name, dep) in &pkg.toml.dependencies {
+        match DependencySource::of(dep) {
+            DependencySource::Registry(range) => {
+                dependencies.insert(name.clone(), json!(range));
+            }
+            _ => return Err(PackError::UnpublishableDependency(name.clone()).into()),
+        }
+    }
+    if !dependencies.is_empty() {
+        manifest.insert(
+            "dependencies".into(),
+            serde_json::Value::Object(dependencies),
+        );
+    }
+
+    Ok(serde_json::Value::Object(manifest))
+}
+
+fn write_tarball(contents: &BTreeMap<String, Vec<u8>>) -> Result<Vec<u8>> {
+    // No file name and a zero mtime in the gzip header, so only the contents matter
+    let encoder = GzBuilder::new()
+        .mtime(0)
+        .write(Vec::new(), Compression::best());
+    let mut builder = tar::Builder::new(encoder);
+    builder.mode(tar::HeaderMode::Deterministic);
+
+    for (path, data) in contents {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(FIXED_MTIME);
+        header.set_uid(0);
+        header.set_gid(0);
+
+        builder.append_data(&mut header, format!("package/{}", path), data.as_slice())?;
+    }
+
+    Ok(builder.into_inner()?.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tarball_name() {
+        assert_eq!(tarball_name("lib", "1.0.0"), "lib-1.0.0.tgz");
+        assert_eq!(tarball_name("@ike/lib", "1.0.0"), "ike-lib-1.0.0.tgz");
+    }
+
+    #[test]
+    fn test_transpiled_paths() {
+        assert_eq!(js_path("src/index.ts"), "src/index.js");
+        assert_eq!(js_path("src/index.mts"), "src/index.mjs");
+        assert_eq!(declaration_path("src/index.ts"), "src/index.d.ts");
+        assert!(!is_typescript("src/index.d.ts"));
+        assert!(is_typescript("src/app.tsx"));
+    }
+
+    #[test]
+    fn test_rewrite_import_extensions() {
+        assert_eq!(
+            rewrite_import_extensions(
+                "import { a } from './a.ts';\nexport * from \"../b.mts\";\nimport('./c.tsx');\nimport 'lib/d.ts';"
+            ),
+            "import { a } from './a.js';\nexport * from \"../b.mjs\";\nimport('./c.js');\nimport 'lib/d.ts';"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_manifest() {
+        let manifest = r#"[package]
+name = "lib"
+version = "1.0.0"
+main = "src/index.ts" # entry
+
+[exports.utils]
+import = "src/utils.ts"
+types = "src/utils.d.ts"
+"#;
+
+        assert_eq!(
+            rewrite_manifest(manifest, true).unwrap(),
+            r#"[package]
+name = "lib"
+version = "1.0.0"
+main = "src/index.js" # entry
+types = "src/index.d.ts"
+
+[exports.utils]
+import = "src/utils.js"
+types = "src/utils.d.ts"
+"#
+        );
+    }
+
+    #[test]
+    fn test_tarball_is_reproducible() {
+        let mut contents = BTreeMap::new();
+        contents.insert("ike.toml".to_string(), b"[package]".to_vec());
+        contents.insert("src/index.js".to_string(), b"export {}".to_vec());
+
+        assert_eq!(
+            write_tarball(&contents).unwrap(),
+            write_tarball(&contents).unwrap()
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use base64::prelude::*;
+use isahc::{ReadResponseExt, Request, RequestExt};
+use semver::Version;
+use serde_json::{json, Value};
+use std::path::Path;
+
+use super::{
+    PackError::{InvalidVersion, PublishFailed, VersionAlreadyPublished},
+    Packed,
+};
+use crate::install::registry::{validate_package_name, RegistryClient};
+
+/// Publishes a packed package. Local registries get the layout `ike install` reads from
+/// them, http(s) registries are published to with the npm publish protocol.
+/// Returns where the tarball was published to.
+pub fn publish(packed: &Packed, registry: &str) -> Result<String> {
+    // Both end up in paths and URLs of the registry, so they are checked before anything
+    // is written
+    validate_package_name(&packed.name)?;
+    Version::parse(&packed.version)
+        .map_err(|e| InvalidVersion(packed.version.clone(), e.to_string()))?;
+
+    let client = RegistryClient::new(registry);
+
+    match client.local_dir() {
+        Some(dir) => publish_to_dir(packed, &dir),
+        None => publish_to_url(packed, client.url()),
+    }
+}
+
+fn version_metadata(packed: &Packed, tarball: &str) -> Value {
+    let mut metadata = packed.manifest.clone();
+    metadata["_id"] = json!(format!("{}@{}", packed.name, packed.version));
+    metadata["dist"] = json!({
+        "tarball": tarball,
+        "integrity": packed.integrity,
+        "shasum": packed.shasum,
+    });
+
+    metadata
+}
+
+/// `<dir>/<name>/index.json` holds the packument, tarballs are stored next to it.
+fn publish_to_dir(packed: &Packed, dir: &Path) -> Result<String> {
+    let package_dir = dir.join(&packed.name);
+    let index_path = package_dir.join("index.json");
+    std::fs::create_dir_all(&package_dir)?;
+
+    let mut packument: Value = match index_path.exists() {
+        true => serde_json::from_slice(&std::fs::read(&index_path)?)?,
+        false => json!({ "name": packed.name, "dist-tags": {}, "versions": {} }),
+    };
+
+    if packument["versions"].get(&packed.version).is_some() {
+        return Err(VersionAlreadyPublished(packed.name.clone(), packed.version.clone()).into());
+    }
+
+    let tarball_path = package_dir.join(&packed.file_name);
+    std::fs::write(&tarball_path, &packed.bytes)?;
+
+    // Tarballs are relative to the packument, so the registry directory can be moved
+    packument["versions"][&packed.version] = version_metadata(packed, &packed.file_name);
+
+    let latest = packument["dist-tags"]["latest"]
+        .as_str()
+        .and_then(|latest| Version::parse(latest).ok());
+    let version = Version::parse(&packed.version)?;
+    let is_latest = match latest {
+        Some(latest) => version > latest,
+        None => true,
+    };
+    if is_latest {
+        packument["dist-tags"]["latest"] = json!(packed.version);
+    }
+
+    std::fs::write(&index_path, serde_json::to_string_pretty(&packument)?)?;
+
+    Ok(tarball_path.display().to_string())
+}
+
+fn publish_to_url(packed: &Packed, url: &str) -> Result<String> {
+    let package_url = format!("{}/{}", url, packed.name.replace('/', "%2f"));
+    let tarball = format!("{}/{}/-/{}", url, packed.name, packed.file_name);
+
+    let body = json!({
+        "_id": packed.name,
+        "name": packed.name,
+        "dist-tags": { "latest": packed.version },
+        "versions": { &packed.version: version_metadata(packed, &tarball) },
+        "_attachments": {
+            &packed.file_name: {
+                "content_type": "application/octet-stream",
+                "data": BASE64_STANDARD.encode(&packed.bytes),
+                "length": packed.bytes.len(),
+            }
+        },
+    });
+
+    let mut request = Request::put(&package_url).header("Content-Type", "application/json");
+    if let Ok(token) = std::env::var("IKE_REGISTRY_TOKEN") {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let mut response = request.body(serde_json::to_vec(&body)?)?.send()?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().unwrap_or_default();
+
+        return Err(PublishFailed(package_url, format!("{} {}", status, text.trim())).into());
+    }
+
+    Ok(tarball)
+}
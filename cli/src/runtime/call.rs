@@ -4,7 +4,13 @@ use crate::{
     assert_arg_type,
     runtime::{
         buffer::is_utf8,
+        dotenv::dotenv_parse,
+        shell::{shell_glob, shell_read_dir, shell_stat, shell_write_file},
         uuid::{uuid_parse, uuid_stringify, uuidv4, uuidv5},
+        worker::{
+            worker_close, worker_create, worker_on_message, worker_post_message,
+            worker_post_to_parent, worker_terminate,
+        },
     },
 };
 
@@ -45,6 +51,39 @@ pub fn rust_function(
         });
         m.insert("uuidv4", unsafe { NativeFunction::from_closure(uuidv4) });
         m.insert("uuidv5", unsafe { NativeFunction::from_closure(uuidv5) });
+        m.insert("dotenvParse", unsafe {
+            NativeFunction::from_closure(dotenv_parse)
+        });
+        m.insert("shellGlob", unsafe {
+            NativeFunction::from_closure(shell_glob)
+        });
+        m.insert("shellReadDir", unsafe {
+            NativeFunction::from_closure(shell_read_dir)
+        });
+        m.insert("shellStat", unsafe {
+            NativeFunction::from_closure(shell_stat)
+        });
+        m.insert("shellWriteFile", unsafe {
+            NativeFunction::from_closure(shell_write_file)
+        });
+        m.insert("workerCreate", unsafe {
+            NativeFunction::from_closure(worker_create)
+        });
+        m.insert("workerPostMessage", unsafe {
+            NativeFunction::from_closure(worker_post_message)
+        });
+        m.insert("workerTerminate", unsafe {
+            NativeFunction::from_closure(worker_terminate)
+        });
+        m.insert("workerOnMessage", unsafe {
+            NativeFunction::from_closure(worker_on_message)
+        });
+        m.insert("workerPostToParent", unsafe {
+            NativeFunction::from_closure(worker_post_to_parent)
+        });
+        m.insert("workerClose", unsafe {
+            NativeFunction::from_closure(worker_close)
+        });
         m
     };
     if args.is_empty() {
@@ -0,0 +1,226 @@
+use boa_engine::{
+    js_str, js_string,
+    module::SyntheticModuleInitializer,
+    object::{
+        builtins::{JsArray, JsFunction},
+        FunctionObjectBuilder, JsObject, ObjectInitializer,
+    },
+    property::{Attribute, PropertyKey},
+    Context, JsError, JsNativeError, JsResult, JsValue, Module, NativeFunction, Source,
+};
+use boa_gc::{Finalize, Gc, GcRefCell, Trace};
+use ike_core::{js_str_to_string, throw};
+use ike_toml::ModuleType;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use super::{modules::is_builtin_module, resolver::IkeResolver};
+use crate::transpiler::transpile;
+
+/// Runs CommonJS modules. `require` is synchronous, so a module runs as soon as it's
+/// required instead of going through the module graph like ES modules do.
+#[derive(Debug, Clone, Trace, Finalize)]
+pub struct CommonJsLoader {
+    #[unsafe_ignore_trace]
+    resolver: Rc<IkeResolver>,
+    /// `module` objects by path. They are cached before the module runs, so circular
+    /// requires get the exports as far as they are filled, like in node. The cache is
+    /// reachable from every `require`, so it lives on the GC heap to let cycles be
+    /// collected.
+    modules: Gc<GcRefCell<HashMap<PathBuf, JsObject>>>,
+}
+
+impl Default for CommonJsLoader {
+    fn default() -> Self {
+        Self::new(Rc::default())
+    }
+}
+
+impl CommonJsLoader {
+    pub fn new(resolver: Rc<IkeResolver>) -> Self {
+        Self {
+            resolver,
+            modules: Gc::new(GcRefCell::default()),
+        }
+    }
+
+    /// Wraps the CommonJS module at `file` into an ES module. `module.exports` is the
+    /// default export and its own properties are the named ones.
+    pub fn load_as_module(&self, file: &Path, ctx: &mut Context) -> JsResult<Module> {
+        let exports = self.require(file, ctx)?;
+
+        let mut names = vec![js_string!("default")];
+        if let Some(object) = exports.as_object() {
+            for key in object.own_property_keys(ctx)? {
+                if let PropertyKey::String(name) = key {
+                    if name.to_std_string_escaped() != "default" {
+                        names.push(name);
+                    }
+                }
+            }
+        }
+
+        let captures = (
+            exports,
+            JsArray::from_iter(names.iter().cloned().map(JsValue::from), ctx),
+        );
+        let initializer = SyntheticModuleInitializer::from_copy_closure_with_captures(
+            |module, (exports, names), ctx| {
+                // Modules compiled from ES modules mark themselves with `__esModule`
+                let default = match exports.as_object() {
+                    Some(object) if object.get(js_str!("__esModule"), ctx)?.to_boolean() => {
+                        object.get(js_str!("default"), ctx)?
+                    }
+                    _ => exports.clone(),
+                };
+                module.set_export(&js_string!("default"), default)?;
+
+                for index in 1..names.length(ctx)? {
+                    let name = names.get(index, ctx)?.to_string(ctx)?;
+                    let value = match exports.as_object() {
+                        Some(object) => object.get(name.clone(), ctx)?,
+                        None => JsValue::undefined(),
+                    };
+                    module.set_export(&name, value)?;
+                }
+
+                Ok(())
+            },
+            captures,
+        );
+
+        Ok(Module::synthetic(
+            &names,
+            initializer,
+            Some(file.to_path_buf()),
+            None,
+            ctx,
+        ))
+    }
+
+    /// Runs the module at `file` once and returns its `module.exports`.
+    pub fn require(&self, file: &Path, ctx: &mut Context) -> JsResult<JsValue> {
+        let cached = self.modules.borrow().get(file).cloned();
+        if let Some(module) = cached {
+            return module.get(js_str!("exports"), ctx);
+        }
+
+        if file.extension().is_some_and(|ext| ext == "json") {
+            return self.require_json(file, ctx);
+        }
+
+        let code = transpile(&file.to_path_buf()).map_err(|err| {
+            JsNativeError::error()
+                .with_message(format!("could not transpile module `{}`", file.display()))
+                .with_cause(JsError::from_opaque(js_string!(err.to_string()).into()))
+        })?;
+        let wrapper = format!(
+            "(function (exports, require, module, __filename, __dirname) {{{}\n}})",
+            code
+        );
+        let function = ctx.eval(Source::from_bytes(wrapper.as_bytes()).with_path(file))?;
+        let function = match function.as_callable() {
+            Some(function) => function.clone(),
+            None => throw!(typ, "could not wrap CommonJS module `{}`", file.display()),
+        };
+
+        let filename = js_string!(file.to_string_lossy().to_string());
+        let dir = file.parent().unwrap_or(file);
+        let dirname = js_string!(dir.to_string_lossy().to_string());
+        let exports = JsObject::with_object_proto(ctx.intrinsics());
+        let module = ObjectInitializer::new(ctx)
+            .property(js_string!("exports"), exports.clone(), Attribute::all())
+            .property(js_string!("id"), filename.clone(), Attribute::all())
+            .property(js_string!("filename"), filename.clone(), Attribute::all())
+            .build();
+        let require = self.require_function(dir.to_path_buf(), ctx);
+
+        self.modules
+            .borrow_mut()
+            .insert(file.to_path_buf(), module.clone());
+
+        let args = [
+            exports.clone().into(),
+            require.into(),
+            module.clone().into(),
+            filename.into(),
+            dirname.into(),
+        ];
+        if let Err(err) = function.call(&exports.into(), &args, ctx) {
+            // A module that threw can be required again
+            self.modules.borrow_mut().remove(file);
+            return Err(err);
+        }
+
+        module.get(js_str!("exports"), ctx)
+    }
+
+    fn require_json(&self, file: &Path, ctx: &mut Context) -> JsResult<JsValue> {
+        let content = match std::fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(err) => throw!(err, "could not read `{}`: {}", file.display(), err),
+        };
+        let json: serde_json::Value = match serde_json::from_str(&content) {
+            Ok(json) => json,
+            Err(err) => {
+                return Err(JsNativeError::syntax()
+                    .with_message(format!("could not parse `{}`: {}", file.display(), err))
+                    .into())
+            }
+        };
+        let value = JsValue::from_json(&json, ctx)?;
+
+        let module = ObjectInitializer::new(ctx)
+            .property(js_string!("exports"), value.clone(), Attribute::all())
+            .build();
+        self.modules.borrow_mut().insert(file.to_path_buf(), module);
+
+        Ok(value)
+    }
+
+    /// `require` for modules in `dir`.
+    fn require_function(&self, dir: PathBuf, ctx: &mut Context) -> JsFunction {
+        let require = NativeFunction::from_copy_closure_with_captures(
+            |_, args, (loader, dir), ctx| {
+                let specifier = match args.first() {
+                    Some(specifier) if !specifier.is_undefined() => specifier.to_string(ctx)?,
+                    _ => throw!(typ, "require: Expected a specifier"),
+                };
+                let specifier = js_str_to_string!(specifier);
+
+                if is_builtin_module(&specifier) {
+                    throw!(
+                        typ,
+                        "`{}` is an ES module, use `import` instead of `require`",
+                        specifier
+                    );
+                }
+
+                let file = match loader.resolver.resolve(dir, &specifier) {
+                    Ok(file) => file,
+                    Err(err) => throw!(typ, "{}", err),
+                };
+                let is_json = file.extension().is_some_and(|ext| ext == "json");
+                if !is_json && loader.resolver.module_type(&file) == ModuleType::Module {
+                    throw!(
+                        typ,
+                        "`{}` is an ES module, use `import` instead of `require`",
+                        file.display()
+                    );
+                }
+
+                loader.require(&file, ctx)
+            },
+            (self.clone(), dir),
+        );
+
+        FunctionObjectBuilder::new(ctx.realm(), require)
+            .name(js_string!("require"))
+            .length(1)
+            .constructor(false)
+            .build()
+    }
+}
@@ -0,0 +1,39 @@
+use boa_engine::{js_string, Context, JsNativeError, JsObject, JsResult, JsValue};
+use ike_core::{
+    permissions::{self, PermissionKind},
+    str_from_jsvalue, throw,
+};
+
+use crate::{assert_arg_type, dotenv::parse};
+
+/// `parse` of `@std/dotenv`, variables that aren't in the source expand to the ones of
+/// the process. In a sandbox only the variables env access is granted to are expanded.
+pub fn dotenv_parse(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let source = match args.first() {
+        Some(source) => source,
+        None => throw!(typ, "Expected a source to parse"),
+    };
+    assert_arg_type!(string, source);
+    let source = str_from_jsvalue!(source, ctx);
+
+    let lookup = |name: &str| match permissions::is_granted(PermissionKind::Env, Some(name), ctx) {
+        true => std::env::var(name).ok(),
+        false => None,
+    };
+
+    let vars = match parse(&source, lookup) {
+        Ok(vars) => vars,
+        Err(err) => {
+            return Err(JsNativeError::syntax()
+                .with_message(format!("Invalid .env source, {}", err))
+                .into())
+        }
+    };
+
+    let obj = JsObject::with_object_proto(ctx.intrinsics());
+    for (key, value) in vars {
+        obj.set(js_string!(key), js_string!(value), false, ctx)?;
+    }
+
+    Ok(obj.into())
+}
@@ -0,0 +1,160 @@
+use boa_engine::{
+    js_string,
+    object::{
+        builtins::{JsArray, JsProxyBuilder},
+        ObjectInitializer,
+    },
+    property::Attribute,
+    Context, JsNativeError, JsObject, JsResult, JsValue,
+};
+use ike_core::{
+    permissions::{self, PermissionKind},
+    throw,
+};
+
+/// `Ike.env`, a proxy that reads and writes the environment of the process, so changes
+/// are seen by native code and inherited by subprocesses.
+pub struct IkeEnv;
+
+impl IkeEnv {
+    pub fn init(ctx: &mut Context) -> JsObject {
+        let proxy = JsProxyBuilder::new(JsObject::with_null_proto())
+            .get(Self::get)
+            .set(Self::set)
+            .has(Self::has)
+            .delete_property(Self::delete_property)
+            .own_keys(Self::own_keys)
+            .get_own_property_descriptor(Self::get_own_property_descriptor)
+            .define_property(Self::define_property)
+            .build(ctx);
+
+        proxy.into()
+    }
+
+    /// Name of the variable a trap is called for, `None` for symbols.
+    fn key(args: &[JsValue], ctx: &mut Context) -> JsResult<Option<String>> {
+        match args.get(1) {
+            Some(key) if !key.is_symbol() => Ok(Some(key.to_string(ctx)?.to_std_string_escaped())),
+            _ => Ok(None),
+        }
+    }
+
+    /// Like `key`, if the sandbox allows access to the variable.
+    fn checked_key(args: &[JsValue], ctx: &mut Context) -> JsResult<Option<String>> {
+        let key = Self::key(args, ctx)?;
+        if let Some(key) = &key {
+            permissions::check(PermissionKind::Env, Some(key), ctx)?;
+        }
+
+        Ok(key)
+    }
+
+    /// Sets a variable, names and values the OS would reject throw instead of panicking.
+    fn set_var(key: &str, value: &JsValue, ctx: &mut Context) -> JsResult<()> {
+        if key.is_empty() || key.contains(['=', '\0']) {
+            throw!(typ, "Invalid environment variable name `{}`", key);
+        }
+
+        let value = value.to_string(ctx)?.to_std_string_escaped();
+        if value.contains('\0') {
+            throw!(
+                typ,
+                "Value of environment variable `{}` contains a NUL character",
+                key
+            );
+        }
+
+        std::env::set_var(key, value);
+        Ok(())
+    }
+
+    fn get(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+        let value = Self::checked_key(args, ctx)?.and_then(|key| std::env::var(key).ok());
+
+        Ok(match value {
+            Some(value) => js_string!(value).into(),
+            None => JsValue::undefined(),
+        })
+    }
+
+    fn set(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+        let key = match Self::checked_key(args, ctx)? {
+            Some(key) => key,
+            None => throw!(typ, "Environment variable names must be strings"),
+        };
+        Self::set_var(&key, &args.get(2).cloned().unwrap_or_default(), ctx)?;
+
+        Ok(true.into())
+    }
+
+    fn has(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+        let exists =
+            Self::checked_key(args, ctx)?.is_some_and(|key| std::env::var_os(key).is_some());
+
+        Ok(exists.into())
+    }
+
+    fn delete_property(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+        if let Some(key) = Self::checked_key(args, ctx)? {
+            if !key.is_empty() && !key.contains(['=', '\0']) {
+                std::env::remove_var(key);
+            }
+        }
+
+        Ok(true.into())
+    }
+
+    /// Variables the sandbox doesn't allow access to are left out.
+    fn own_keys(_: &JsValue, _: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+        let keys = std::env::vars_os()
+            .filter_map(|(key, _)| key.into_string().ok())
+            .filter(|key| permissions::is_granted(PermissionKind::Env, Some(key), ctx))
+            .map(|key| js_string!(key).into())
+            .collect::<Vec<JsValue>>();
+
+        Ok(JsArray::from_iter(keys, ctx).into())
+    }
+
+    fn get_own_property_descriptor(
+        _: &JsValue,
+        args: &[JsValue],
+        ctx: &mut Context,
+    ) -> JsResult<JsValue> {
+        let value = match Self::checked_key(args, ctx)?.and_then(|key| std::env::var(key).ok()) {
+            Some(value) => value,
+            None => return Ok(JsValue::undefined()),
+        };
+
+        let descriptor = ObjectInitializer::new(ctx)
+            .property(js_string!("value"), js_string!(value), Attribute::all())
+            .property(js_string!("writable"), true, Attribute::all())
+            .property(js_string!("enumerable"), true, Attribute::all())
+            .property(js_string!("configurable"), true, Attribute::all())
+            .build();
+
+        Ok(descriptor.into())
+    }
+
+    /// `Object.defineProperty` works for plain values only, there are no accessors in the
+    /// environment.
+    fn define_property(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+        let key = match Self::checked_key(args, ctx)? {
+            Some(key) => key,
+            None => throw!(typ, "Environment variable names must be strings"),
+        };
+        let descriptor = match args.get(2).and_then(JsValue::as_object) {
+            Some(descriptor) => descriptor.clone(),
+            None => throw!(typ, "Property description must be an object"),
+        };
+        if descriptor.has_property(js_string!("get"), ctx)?
+            || descriptor.has_property(js_string!("set"), ctx)?
+        {
+            throw!(typ, "Environment variables can't have getters or setters");
+        }
+
+        let value = descriptor.get(js_string!("value"), ctx)?;
+        Self::set_var(&key, &value, ctx)?;
+
+        Ok(true.into())
+    }
+}
@@ -1,15 +1,24 @@
+use super::lifecycle::exit;
 use super::meta::Meta;
+use super::worker::is_worker;
 use crate::globals::ALLOWED_EXTENSIONS;
 use crate::runtime::toml::parse_toml;
 use crate::transpiler::{transpile, transpile_with_text};
-use crate::which::which;
 use crate::{create_method, globals::VERSION};
 use boa_engine::{
-    js_str, js_string, object::ObjectInitializer, property::Attribute, value::Type, Context,
-    JsData, JsNativeError, JsObject, JsResult, JsValue, NativeFunction,
+    js_str, js_string,
+    object::{builtins::JsArray, IntegrityLevel, ObjectInitializer},
+    property::{Attribute, PropertyDescriptor},
+    value::Type,
+    Context, JsData, JsNativeError, JsObject, JsResult, JsValue, NativeFunction,
 };
 use boa_gc::{Finalize, Trace};
-use ike_core::{js_str_to_string, throw};
+use ike_core::{
+    js_str_to_string,
+    permissions::{self, PermissionKind},
+    throw,
+};
+use ike_fs::which;
 use std::path::PathBuf;
 
 #[derive(Debug, Default, Trace, Finalize, JsData)]
@@ -27,6 +36,14 @@ impl IkeGlobalObject {
             Attribute::all(),
         );
         obj.property(js_string!("version"), js_string!(VERSION), Attribute::all());
+        let exec_path = std::env::current_exe()
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_default();
+        obj.property(
+            js_string!("execPath"),
+            js_string!(exec_path),
+            Attribute::all(),
+        );
         obj.function(create_method!(Self::exit), js_string!("exit"), 1);
         obj.function(
             create_method!(Self::set_exit_code),
@@ -74,17 +91,72 @@ impl IkeGlobalObject {
         ike.as_object().unwrap().clone()
     }
 
+    /// Exposes the active features as a frozen `Ike.features` array.
+    pub fn set_features(ctx: &mut Context, features: &[String]) -> JsResult<()> {
+        let this = Self::get_ike_global_object(ctx);
+        let features = JsArray::from_iter(
+            features
+                .iter()
+                .map(|feature| JsValue::from(js_string!(feature.as_str()))),
+            ctx,
+        );
+        features.set_integrity_level(IntegrityLevel::Frozen, ctx)?;
+
+        this.define_property_or_throw(
+            js_str!("features"),
+            PropertyDescriptor::builder()
+                .value(features)
+                .writable(false)
+                .enumerable(true)
+                .configurable(false),
+            ctx,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn get_features(ctx: &mut Context) -> JsResult<Vec<String>> {
+        let this = Self::get_ike_global_object(ctx);
+        let features = this.get(js_str!("features"), ctx)?;
+        let features = match features.as_object() {
+            Some(features) => JsArray::from_object(features.clone())?,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut list = Vec::new();
+        for index in 0..features.length(ctx)? {
+            let feature = features.get(index, ctx)?;
+            list.push(feature.to_string(ctx)?.to_std_string_escaped());
+        }
+
+        Ok(list)
+    }
+
     pub fn get_exit_code(ctx: &mut Context) -> i32 {
         let this = Self::get_ike_global_object(ctx);
         let exit_code = this.get(js_str!("exitCode"), ctx).unwrap();
         exit_code.to_i32(ctx).unwrap()
     }
 
+    pub fn update_exit_code(ctx: &mut Context, code: i32) {
+        let this = Self::get_ike_global_object(ctx);
+        this.set(js_str!("exitCode"), JsValue::from(code), false, ctx)
+            .expect("Failed to set exit code");
+    }
+
+    /// Exits with the given code, or `Ike.exitCode` without one, after dispatching `exit`.
     pub fn exit(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
-        Self::set_exit_code(&JsValue::undefined(), args, ctx)?;
+        if is_worker() {
+            throw!(
+                typ,
+                "Ike.exit can't be called in a worker, use close() instead"
+            );
+        }
+        if !args.is_empty() {
+            Self::set_exit_code(&JsValue::undefined(), args, ctx)?;
+        }
 
-        let code = Self::get_exit_code(ctx);
-        std::process::exit(code);
+        exit(ctx);
     }
 
     pub fn set_exit_code(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
@@ -99,8 +171,7 @@ impl IkeGlobalObject {
         }
         let code = code.unwrap().to_i32(ctx).unwrap();
 
-        this.set(js_str!("exitCode"), JsValue::from(code), false, ctx)
-            .expect("Failed to set exit code");
+        Self::update_exit_code(ctx, code);
 
         Ok(JsValue::undefined())
     }
@@ -183,6 +254,11 @@ impl IkeGlobalObject {
 
         let cwd = cwd.map(PathBuf::from);
 
+        // Without a `path` option the command is looked up in PATH
+        if path.is_none() {
+            permissions::check(PermissionKind::Env, Some("PATH"), ctx)?;
+        }
+
         if let Some(result) = which(&command, path, cwd) {
             let return_value = JsValue::from(js_string!(result.to_string_lossy().to_string()));
             Ok(return_value)
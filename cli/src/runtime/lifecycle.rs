@@ -0,0 +1,107 @@
+use std::{
+    cell::Cell,
+    io::{stderr, stdout, Write},
+};
+
+use boa_engine::{js_str, js_string, Context, JsError, JsObject, JsResult, JsValue};
+use ike_core::throw;
+
+use super::{ike::IkeGlobalObject, queue::Queue, worker};
+
+thread_local! {
+    static EXITING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Creates an event of one of the global event classes, like `Event`.
+pub fn new_event(ctx: &mut Context, class: &str, args: &[JsValue]) -> JsResult<JsObject> {
+    let global = ctx.global_object();
+
+    match global.get(js_string!(class), ctx)?.as_constructor() {
+        Some(constructor) => constructor.construct(args, None, ctx),
+        None => throw!(typ, "{} is not a constructor", class),
+    }
+}
+
+/// Dispatches an event on `globalThis`, `false` if a listener called `preventDefault`.
+pub fn dispatch(ctx: &mut Context, event: JsObject) -> JsResult<bool> {
+    let global = ctx.global_object();
+
+    let dispatched = match global.get(js_str!("dispatchEvent"), ctx)?.as_callable() {
+        Some(dispatch) => dispatch.call(&global.clone().into(), &[event.into()], ctx)?,
+        None => throw!(typ, "dispatchEvent is not a function"),
+    };
+
+    Ok(dispatched.to_boolean())
+}
+
+/// Dispatches `new Event(name)` on `globalThis`.
+pub fn dispatch_event(ctx: &mut Context, name: &str) -> JsResult<bool> {
+    let event = new_event(ctx, "Event", &[js_string!(name).into()])?;
+
+    dispatch(ctx, event)
+}
+
+/// Prints an error nothing caught and makes the process exit with 1.
+pub fn report_uncaught(err: &JsError, ctx: &mut Context) {
+    let error = err.to_opaque(ctx);
+
+    report_uncaught_error(err.to_string(), error, ctx);
+}
+
+/// Like `report_uncaught`, for errors that only have a message, like the ones of workers.
+/// In a worker the error is passed on to its `Worker` object instead.
+pub fn report_uncaught_error(message: String, error: JsValue, ctx: &mut Context) {
+    if worker::is_worker() {
+        worker::forward_error(message, error, ctx);
+        return;
+    }
+
+    eprintln!("Uncaught {message}");
+
+    IkeGlobalObject::update_exit_code(ctx, 1);
+}
+
+pub fn flush_stdio() {
+    let _ = stdout().flush();
+    let _ = stderr().flush();
+}
+
+/// Dispatches `exit` once, calling `Ike.exit` from a listener doesn't dispatch it again.
+pub fn dispatch_exit(ctx: &mut Context) {
+    if EXITING.replace(true) {
+        return;
+    }
+
+    if let Err(e) = dispatch_event(ctx, "exit") {
+        report_uncaught(&e, ctx);
+    }
+}
+
+/// Dispatches `exit`, flushes stdio and exits with `Ike.exitCode` right away.
+pub fn exit(ctx: &mut Context) -> ! {
+    dispatch_exit(ctx);
+    flush_stdio();
+
+    std::process::exit(IkeGlobalObject::get_exit_code(ctx));
+}
+
+/// Runs the event loop until nothing is left, dispatching `beforeExit` each time it
+/// drains. Listeners of it can schedule more work, which keeps the process alive.
+/// Returns the code the process exits with, `Ike.exitCode` after `exit` was dispatched.
+pub fn run_until_exit(queue: &Queue, ctx: &mut Context) -> i32 {
+    loop {
+        ctx.run_jobs();
+
+        if let Err(e) = dispatch_event(ctx, "beforeExit") {
+            report_uncaught(&e, ctx);
+        }
+        if queue.is_empty() {
+            break;
+        }
+    }
+
+    dispatch_exit(ctx);
+    flush_stdio();
+
+    IkeGlobalObject::get_exit_code(ctx)
+}
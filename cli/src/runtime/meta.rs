@@ -1,12 +1,14 @@
 use boa_engine::{
     js_string,
-    object::{JsObject, ObjectInitializer},
+    object::{FunctionObjectBuilder, JsObject, ObjectInitializer},
     property::Attribute,
-    Context, JsData,
+    Context, JsData, JsResult, JsValue, NativeFunction,
 };
 use boa_gc::{Finalize, Trace};
 use std::path::Path;
 
+use super::resolver::path_to_url;
+
 #[derive(Debug, Default, Trace, Finalize, JsData)]
 pub struct Meta;
 
@@ -37,4 +39,47 @@ impl Meta {
             )
             .build()
     }
+
+    /// Fills the `import.meta` object of a module located at `path`.
+    pub fn init_import_meta(
+        import_meta: &JsObject,
+        path: &Path,
+        main: bool,
+        resolve: NativeFunction,
+        ctx: &mut Context,
+    ) -> JsResult<()> {
+        let file = path
+            .file_name()
+            .map(|file| file.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dir = path
+            .parent()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let path_str = path.to_string_lossy().to_string();
+        let url = path_to_url(path).unwrap_or_else(|_| path_str.clone());
+
+        let resolve = FunctionObjectBuilder::new(ctx.realm(), resolve)
+            .name(js_string!("resolve"))
+            .length(1)
+            .constructor(false)
+            .build();
+
+        let properties = [
+            ("url", JsValue::from(js_string!(url))),
+            ("path", JsValue::from(js_string!(path_str.clone()))),
+            ("filename", JsValue::from(js_string!(path_str))),
+            ("file", JsValue::from(js_string!(file))),
+            ("dir", JsValue::from(js_string!(dir.clone()))),
+            ("dirname", JsValue::from(js_string!(dir))),
+            ("main", JsValue::from(main)),
+            ("resolve", JsValue::from(resolve)),
+        ];
+
+        for (key, value) in properties {
+            import_meta.set(js_string!(key), value, false, ctx)?;
+        }
+
+        Ok(())
+    }
 }
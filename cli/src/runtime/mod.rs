@@ -1,11 +1,20 @@
 pub mod buffer;
 pub mod call;
+pub mod commonjs;
 pub mod console;
+pub mod dotenv;
+pub mod env;
 pub mod ike;
+pub mod lifecycle;
 pub mod meta;
 pub mod modules;
+pub mod permissions;
 pub mod queue;
+pub mod rejections;
+pub mod resolver;
 pub mod runtime;
+pub mod shell;
 pub mod terminal;
 pub mod toml;
 pub mod uuid;
+pub mod worker;
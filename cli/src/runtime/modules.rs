@@ -1,21 +1,30 @@
+use super::{
+    commonjs::CommonJsLoader, lifecycle::report_uncaught, meta::Meta, resolver::IkeResolver,
+};
 use crate::transpiler::transpile;
 use boa_engine::{
-    js_string, module::ModuleLoader, Context, JsError, JsNativeError, JsResult, JsString, Module,
-    Source,
+    js_string, module::ModuleLoader, Context, JsError, JsNativeError, JsObject, JsResult, JsString,
+    JsValue, Module, NativeFunction, Source,
 };
 use boa_gc::GcRefCell;
-use oxc_resolver::{EnforceExtension, ResolveOptions, Resolver};
+use ike_core::{js_str_to_string, runtime::RuntimeModuleLoader, throw};
+use ike_toml::ModuleType;
 use rustc_hash::FxHashMap;
 use std::{
+    cell::RefCell,
     collections::HashMap,
     path::{Path, PathBuf},
-    vec,
+    rc::Rc,
 };
+use url::Url;
 
 #[derive(Debug, Default)]
 pub struct IkeModuleLoader {
     module_map: GcRefCell<FxHashMap<PathBuf, Module>>,
-    // root: PathBuf,
+    resolver: Rc<IkeResolver>,
+    commonjs: CommonJsLoader,
+    main: RefCell<Option<PathBuf>>,
+    root: PathBuf,
 }
 
 // ! js folder is generated by the unbuild package.
@@ -31,6 +40,9 @@ lazy_static::lazy_static! {
         m.insert("@std/format", include_str!("js/format/index.js").to_string());
         m.insert("@std/streams", include_str!("js/streams/index.js").to_string());
         m.insert("@std/uuid", include_str!("js/uuid/index.js").to_string());
+        m.insert("@std/dotenv", include_str!("js/dotenv/index.js").to_string());
+        m.insert("@std/shell", include_str!("js/shell/index.js").to_string());
+        m.insert("@std/_worker_", include_str!("js/_worker_/index.js").to_string());
         m
     };
 }
@@ -38,26 +50,119 @@ lazy_static::lazy_static! {
 impl IkeModuleLoader {
     pub fn new<P: AsRef<Path>>(root: P) -> JsResult<Self> {
         let root = root.as_ref();
-        let _ = root.canonicalize().map_err(|e| {
+        let absolute = root.canonicalize().map_err(|e| {
             JsNativeError::typ()
                 .with_message(format!("could not set module root `{}`", root.display()))
                 .with_cause(JsError::from_opaque(js_string!(e.to_string()).into()))
         })?;
+        let resolver = Rc::new(IkeResolver::from_root(&absolute));
+
         Ok(Self {
-            // root: absolute,
+            root: absolute,
             module_map: GcRefCell::default(),
+            commonjs: CommonJsLoader::new(Rc::clone(&resolver)),
+            resolver,
+            main: RefCell::default(),
         })
     }
 
-    #[inline]
-    pub fn insert(&self, path: PathBuf, module: Module) {
-        self.module_map.borrow_mut().insert(path, module);
+    /// Sets the entry module, only this module gets `import.meta.main` set to `true`.
+    pub fn set_main(&self, path: &Path) {
+        *self.main.borrow_mut() = Some(path.to_path_buf());
+    }
+
+    pub fn is_main(&self, path: &Path) -> bool {
+        self.main.borrow().as_deref() == Some(path)
+    }
+
+    pub fn is_commonjs(&self, path: &Path) -> bool {
+        self.resolver.module_type(path) == ModuleType::CommonJs
     }
 
     #[inline]
     pub fn get(&self, path: &Path) -> Option<Module> {
         self.module_map.borrow().get(path).cloned()
     }
+
+    /// Builtin modules are parsed only once and then reused for every import.
+    fn load_builtin_module(&self, spec: &str, ctx: &mut Context) -> JsResult<Module> {
+        let key = Path::new(spec);
+        if let Some(module) = self.get(key) {
+            return Ok(module);
+        }
+
+        let bytes = BUILTIN_MODULES.get(spec).unwrap().as_bytes();
+        let module = Module::parse(Source::from_bytes(bytes), None, ctx)?;
+        self.insert(key.to_path_buf(), module.clone());
+
+        Ok(module)
+    }
+
+    fn load_file_module(&self, ref_dir: &Path, spec: &str, ctx: &mut Context) -> JsResult<Module> {
+        // `import.meta.resolve` returns file URLs, so they should be importable as well.
+        let spec = match Url::parse(spec) {
+            Ok(url) if url.scheme() == "file" => match url.to_file_path() {
+                Ok(path) => path.to_string_lossy().to_string(),
+                Err(_) => throw!(typ, "Invalid file URL `{}`", spec),
+            },
+            _ => spec.to_string(),
+        };
+
+        let file = match self.resolver.resolve(ref_dir, &spec) {
+            Ok(file) => file,
+            Err(err) => throw!(typ, "{}", err),
+        };
+
+        // The same module is returned for every import, so it's evaluated only once
+        // even if it's imported concurrently from different places.
+        if let Some(module) = self.get(&file) {
+            return Ok(module);
+        }
+
+        if self.resolver.module_type(&file) == ModuleType::CommonJs {
+            let module = self.commonjs.load_as_module(&file, ctx)?;
+            self.insert(file, module.clone());
+
+            return Ok(module);
+        }
+
+        let transpiled = transpile(&file).map_err(|err| {
+            JsNativeError::error()
+                .with_message(format!("could not transpile module `{spec}`"))
+                .with_cause(JsError::from_opaque(js_string!(err.to_string()).into()))
+        })?;
+
+        let reader = Source::from_bytes(transpiled.as_bytes()).with_path(&file);
+        let module = Module::parse(reader, None, ctx).map_err(|err| {
+            JsNativeError::syntax()
+                .with_message(format!("could not parse module `{spec}`"))
+                .with_cause(err)
+        })?;
+        self.insert(file, module.clone());
+
+        Ok(module)
+    }
+}
+
+impl RuntimeModuleLoader for IkeModuleLoader {
+    fn insert(&self, specifier: PathBuf, module: Module) {
+        self.module_map.borrow_mut().insert(specifier, module);
+    }
+
+    /// TypeScript entries are transpiled, CommonJS ones run right away.
+    fn load_entry(&self, path: &Path, ctx: &mut Context) -> JsResult<Module> {
+        if self.is_commonjs(path) {
+            return self.commonjs.load_as_module(path, ctx);
+        }
+
+        let transpiled = match transpile(path) {
+            Ok(transpiled) => transpiled,
+            Err(e) => throw!(typ, "Failed to transpile: {:?}", e),
+        };
+        let source = Source::from_bytes(transpiled.as_bytes()).with_path(path);
+
+        Module::parse(source, None, ctx)
+    }
 }
 
 impl ModuleLoader for IkeModuleLoader {
@@ -71,78 +176,24 @@ impl ModuleLoader for IkeModuleLoader {
         let spec = specifier.to_std_string_escaped();
 
         if is_builtin_module(&spec) {
-            let bytes = BUILTIN_MODULES.get(spec.as_str()).unwrap().as_bytes();
-            let source = Source::from_bytes(bytes);
-            let module = Module::parse(source, None, context);
-
-            finish_load(module, context);
-        } else {
-            if let Some(module) = self.get(Path::new(&spec)) {
-                let result = (|| Ok(module))();
-
-                return finish_load(result, context);
-            }
-
-            let ref_path = match referrer.path().unwrap().parent() {
-                Some(parent) => parent,
-                None => {
-                    finish_load(
-                        Err(JsNativeError::typ()
-                            .with_message("Failed to get parent directory")
-                            .into()),
-                        context,
-                    );
-                    return;
-                }
-            };
-
-            let options = ResolveOptions {
-                enforce_extension: EnforceExtension::Disabled,
-                condition_names: vec!["node".into(), "import".into()],
-                extensions: vec![
-                    ".js".into(),
-                    ".mjs".into(),
-                    ".ts".into(),
-                    ".mts".into(),
-                    ".cjs".into(),
-                    ".cts".into(),
-                ],
-                ..ResolveOptions::default()
-            };
-
-            // TODO: implement our own resolver because of the difference in package.json and ike.toml
-            match Resolver::new(options).resolve(ref_path, &spec) {
-                Err(error) => println!("Error: {error}"),
-                Ok(resolution) => {
-                    let result = (|| -> JsResult<Module> {
-                        let file = resolution.full_path();
-                        let transpiled = transpile(&file)
-                            .map_err(|err| {
-                                JsNativeError::error()
-                                    .with_message(err.to_string())
-                                    .with_cause(JsError::from_opaque(
-                                        js_string!(err.to_string()).into(),
-                                    ))
-                            })
-                            .unwrap();
-
-                        let reader =
-                            Source::from_bytes(transpiled.as_bytes()).with_path(&Path::new(&file));
-                        let module = Module::parse(reader, None, context)
-                            .map_err(|err| {
-                                JsNativeError::syntax()
-                                    .with_message(format!("could not parse module `{spec}`"))
-                                    .with_cause(err)
-                            })
-                            .unwrap();
-                        self.insert(file, module.clone());
-                        Ok(module)
-                    })();
-
-                    finish_load(result, context);
-                }
-            }
+            let result = self.load_builtin_module(&spec, context);
+
+            return finish_load(result, context);
         }
+
+        if let Some(module) = self.get(Path::new(&spec)) {
+            return finish_load(Ok(module), context);
+        }
+
+        // Scripts, the REPL and eval'd code might not have a path, so we resolve from the root.
+        let ref_dir = referrer
+            .path()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.root.clone());
+
+        let result = self.load_file_module(&ref_dir, &spec, context);
+        finish_load(result, context);
     }
 
     fn register_module(&self, specifier: JsString, module: Module) {
@@ -156,6 +207,39 @@ impl ModuleLoader for IkeModuleLoader {
 
         self.get(Path::new(&path))
     }
+
+    fn init_import_meta(&self, import_meta: &JsObject, module: &Module, context: &mut Context) {
+        // Modules without a path, which are builtins and sources an embedder builds from
+        // a string, get no `import.meta` properties.
+        let path = match module.path() {
+            Some(path) => path.to_path_buf(),
+            None => return,
+        };
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let resolver = Rc::clone(&self.resolver);
+
+        let resolve = unsafe {
+            NativeFunction::from_closure(move |_, args, ctx| {
+                let specifier = match args.first() {
+                    Some(specifier) if !specifier.is_undefined() => specifier.to_string(ctx)?,
+                    _ => throw!(typ, "import.meta.resolve: Expected a specifier"),
+                };
+                let specifier = js_str_to_string!(specifier);
+
+                match resolver.resolve_to_url(&dir, &specifier) {
+                    Ok(url) => Ok(JsValue::from(js_string!(url))),
+                    Err(err) => throw!(typ, "{}", err),
+                }
+            })
+        };
+
+        // The module still evaluates, with whatever properties were set
+        if let Err(err) =
+            Meta::init_import_meta(import_meta, &path, self.is_main(&path), resolve, context)
+        {
+            report_uncaught(&err, context);
+        }
+    }
 }
 
 pub fn is_builtin_module(specifier: &str) -> bool {
@@ -0,0 +1,101 @@
+use boa_engine::{
+    js_string,
+    object::{builtins::JsPromise, ObjectInitializer},
+    property::Attribute,
+    Context, JsNativeError, JsObject, JsResult, JsValue, NativeFunction,
+};
+use ike_core::{
+    permissions::{self, PermissionKind, PermissionState},
+    throw,
+};
+
+/// `Ike.permissions`, tells scripts what the sandbox allows them to access.
+pub struct IkePermissions;
+
+impl IkePermissions {
+    pub fn init(ctx: &mut Context) -> JsObject {
+        let mut obj = ObjectInitializer::new(ctx);
+
+        obj.function(
+            NativeFunction::from_fn_ptr(Self::query),
+            js_string!("query"),
+            1,
+        );
+        obj.function(
+            NativeFunction::from_fn_ptr(Self::request),
+            js_string!("request"),
+            1,
+        );
+
+        obj.build()
+    }
+
+    /// Kind and target of a descriptor like `{ name: "read", path: "./data" }`. The target is
+    /// `path` for read and write, `variable` for env, `command` for run and `host` for net.
+    fn descriptor(
+        args: &[JsValue],
+        ctx: &mut Context,
+    ) -> JsResult<(PermissionKind, Option<String>)> {
+        let descriptor = match args.first().and_then(JsValue::as_object) {
+            Some(descriptor) => descriptor.clone(),
+            None => throw!(typ, "Expected a permission descriptor"),
+        };
+
+        let name = descriptor
+            .get(js_string!("name"), ctx)?
+            .to_string(ctx)?
+            .to_std_string_escaped();
+        let kind = match PermissionKind::parse(&name) {
+            Some(kind) => kind,
+            None => throw!(typ, "Unknown permission name \"{}\"", name),
+        };
+
+        let key = match kind {
+            PermissionKind::Read | PermissionKind::Write => "path",
+            PermissionKind::Env => "variable",
+            PermissionKind::Run => "command",
+            PermissionKind::Net => "host",
+        };
+        let target = descriptor.get(js_string!(key), ctx)?;
+        let target = match target.is_undefined() {
+            true => None,
+            false => Some(target.to_string(ctx)?.to_std_string_escaped()),
+        };
+
+        Ok((kind, target))
+    }
+
+    fn status(state: PermissionState, ctx: &mut Context) -> JsValue {
+        let status = ObjectInitializer::new(ctx)
+            .property(
+                js_string!("state"),
+                js_string!(state.name()),
+                Attribute::READONLY | Attribute::ENUMERABLE,
+            )
+            .build();
+
+        JsPromise::resolve(status, ctx).into()
+    }
+
+    /// Resolves to `{ state }`, which is `granted`, `prompt` or `denied`.
+    fn query(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+        let (kind, target) = match Self::descriptor(args, ctx) {
+            Ok(descriptor) => descriptor,
+            Err(e) => return Ok(JsPromise::reject(e, ctx).into()),
+        };
+        let state = permissions::query(kind, target.as_deref(), ctx);
+
+        Ok(Self::status(state, ctx))
+    }
+
+    /// Like `query`, but asks on the terminal for permissions in the `prompt` state.
+    fn request(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+        let (kind, target) = match Self::descriptor(args, ctx) {
+            Ok(descriptor) => descriptor,
+            Err(e) => return Ok(JsPromise::reject(e, ctx).into()),
+        };
+        let state = permissions::request(kind, target.as_deref(), ctx);
+
+        Ok(Self::status(state, ctx))
+    }
+}
@@ -1,8 +1,11 @@
 use futures_util::{stream::FuturesUnordered, StreamExt};
-use smol::{future, LocalExecutor};
+use ike_core::event_loop::unref_count;
+use smol::{future, Timer};
 use std::{
     cell::{Cell, RefCell},
     collections::VecDeque,
+    rc::Rc,
+    time::Instant,
 };
 
 use boa_engine::{
@@ -10,83 +13,133 @@ use boa_engine::{
     Context,
 };
 
-pub struct Queue<'a> {
-    executor: LocalExecutor<'a>,
+use super::{lifecycle::report_uncaught, rejections::RejectionTracker};
+
+/// The event loop. Promise jobs run until none are left, then the thread parks on the
+/// reactor until one of the pending futures, like a timer or I/O, completes.
+#[derive(Default)]
+pub struct Queue {
     futures: RefCell<FuturesUnordered<FutureJob>>,
     jobs: RefCell<VecDeque<NativeJob>>,
+    rejections: Rc<RejectionTracker>,
+    stopped: Cell<bool>,
 }
 
-impl<'a> Queue<'a> {
-    pub fn new(executor: LocalExecutor<'a>) -> Self {
-        Self {
-            executor,
-            futures: RefCell::default(),
-            jobs: RefCell::default(),
-        }
+impl Queue {
+    pub fn new() -> Self {
+        Self::default()
     }
-}
 
-impl JobQueue for Queue<'_> {
-    fn enqueue_promise_job(&self, job: NativeJob, _context: &mut Context) {
-        self.jobs.borrow_mut().push_back(job);
+    /// Host hooks for the context, they track the promises rejected without a handler.
+    pub fn rejection_tracker(&self) -> Rc<RejectionTracker> {
+        Rc::clone(&self.rejections)
     }
 
-    fn run_jobs(&self, context: &mut Context) {
-        if self.jobs.borrow().is_empty() && self.futures.borrow().is_empty() {
-            return;
-        }
+    /// Whether no jobs, unhandled rejections or futures that keep the loop alive are left.
+    pub fn is_empty(&self) -> bool {
+        self.jobs.borrow().is_empty() && self.rejections.is_empty() && !self.has_refs()
+    }
 
-        let context = RefCell::new(context);
+    /// Drops every job and future, nothing queued afterwards runs either. A worker that
+    /// closes or gets terminated stops its event loop this way.
+    pub fn stop(&self) {
+        self.stopped.set(true);
+        self.jobs.borrow_mut().clear();
+        self.futures.borrow_mut().clear();
+    }
 
-        future::block_on(self.executor.run(async move {
-            let finished = Cell::new(0b00u8);
+    /// Runs jobs like `run_jobs` until `done` returns true, but gives up once `deadline`
+    /// passed while waiting for a future. Returns false if it gave up.
+    pub fn run_jobs_until(
+        &self,
+        context: &mut Context,
+        deadline: Instant,
+        done: impl Fn() -> bool,
+    ) -> bool {
+        smol::block_on(async {
+            let mut timer = Timer::at(deadline);
 
-            let fqueue = async {
-                loop {
-                    if self.futures.borrow().is_empty() {
-                        finished.set(finished.get() | 0b01);
-                        if finished.get() >= 0b11 {
-                            return;
-                        }
-                        future::yield_now().await;
-                        continue;
-                    }
-                    finished.set(finished.get() & 0b10);
+            loop {
+                self.run_microtasks(context);
 
-                    let futures = &mut std::mem::take(&mut *self.futures.borrow_mut());
-                    while let Some(job) = futures.next().await {
-                        self.enqueue_promise_job(job, &mut context.borrow_mut());
-                    }
+                if done() || !self.has_refs() {
+                    return true;
+                }
+
+                let next = future::poll_fn(|cx| self.futures.borrow_mut().poll_next_unpin(cx));
+                let job = future::or(async { Some(next.await) }, async {
+                    (&mut timer).await;
+                    None
+                })
+                .await;
+
+                match job {
+                    Some(Some(job)) => self.jobs.borrow_mut().push_back(job),
+                    Some(None) => {}
+                    None => return false,
                 }
-            };
-
-            let jqueue = async {
-                loop {
-                    if self.jobs.borrow().is_empty() {
-                        finished.set(finished.get() | 0b10);
-                        if finished.get() >= 0b11 {
-                            return;
-                        }
-                        future::yield_now().await;
-                        continue;
-                    };
-                    finished.set(finished.get() & 0b01);
-
-                    let jobs = std::mem::take(&mut *self.jobs.borrow_mut());
-                    for job in jobs {
-                        if let Err(e) = job.call(&mut context.borrow_mut()) {
-                            eprintln!("Uncaught {e}");
-                        }
-                        future::yield_now().await;
+            }
+        })
+    }
+
+    /// Whether a pending future keeps the event loop alive, unref'd ones don't.
+    fn has_refs(&self) -> bool {
+        self.futures.borrow().len() > unref_count()
+    }
+
+    /// Runs promise jobs until none are left. Promises that are still rejected without a
+    /// handler afterwards won't get one anymore.
+    fn run_microtasks(&self, context: &mut Context) {
+        loop {
+            let job = self.jobs.borrow_mut().pop_front();
+
+            match job {
+                Some(job) => {
+                    if let Err(e) = job.call(context) {
+                        report_uncaught(&e, context);
                     }
                 }
-            };
+                None if !self.rejections.is_empty() => self.rejections.process(context),
+                None => return,
+            }
+        }
+    }
+}
+
+impl JobQueue for Queue {
+    fn enqueue_promise_job(&self, job: NativeJob, _context: &mut Context) {
+        if self.stopped.get() {
+            return;
+        }
 
-            future::zip(fqueue, jqueue).await;
-        }))
+        self.jobs.borrow_mut().push_back(job);
+    }
+
+    fn run_jobs(&self, context: &mut Context) {
+        // `smol::block_on` drives the reactor on this thread while it waits
+        smol::block_on(async {
+            loop {
+                self.run_microtasks(context);
+
+                if !self.has_refs() {
+                    return;
+                }
+
+                // Futures only get added by jobs, so all of them are polled here. Ones that
+                // are unref'd still complete while the others keep the loop alive.
+                let job = future::poll_fn(|cx| self.futures.borrow_mut().poll_next_unpin(cx)).await;
+                if let Some(job) = job {
+                    self.jobs.borrow_mut().push_back(job);
+                }
+            }
+        })
     }
 
     fn enqueue_future_job(&self, future: FutureJob, _context: &mut Context) {
+        if self.stopped.get() {
+            return;
+        }
+
         self.futures.borrow().push(future)
     }
 }
@@ -0,0 +1,161 @@
+use std::{
+    cell::{Cell, RefCell},
+    path::PathBuf,
+};
+
+use boa_engine::{
+    builtins::promise::{OperationType, PromiseState},
+    context::HostHooks,
+    js_str, js_string,
+    object::builtins::JsPromise,
+    vm::ActiveRunnable,
+    Context, JsObject, JsResult, JsValue,
+};
+
+use super::{
+    ike::IkeGlobalObject,
+    lifecycle::{dispatch, exit, new_event, report_uncaught, report_uncaught_error},
+    worker::is_worker,
+};
+
+/// What happens with a rejected promise that has no handler once the microtasks ran,
+/// set with `--unhandled-rejections`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnhandledRejections {
+    /// Print the error and exit with 1.
+    #[default]
+    Strict,
+    /// Print the error and keep running.
+    Warn,
+    /// Ignore it.
+    None,
+}
+
+impl UnhandledRejections {
+    pub fn from_arg(arg: Option<&str>) -> Self {
+        match arg {
+            Some("warn") => Self::Warn,
+            Some("none") => Self::None,
+            _ => Self::Strict,
+        }
+    }
+}
+
+struct Rejection {
+    promise: JsObject,
+    /// File that was running when the promise was rejected.
+    path: Option<PathBuf>,
+}
+
+/// Keeps track of rejected promises without a handler, boa tells it about them through
+/// the `promise_rejection_tracker` host hook.
+#[derive(Default)]
+pub struct RejectionTracker {
+    mode: Cell<UnhandledRejections>,
+    pending: RefCell<Vec<Rejection>>,
+}
+
+impl RejectionTracker {
+    pub fn set_mode(&self, mode: UnhandledRejections) {
+        self.mode.set(mode);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.borrow().is_empty()
+    }
+
+    /// Dispatches `unhandledrejection` for the promises that are still unhandled. If no
+    /// listener prevents it, the rejection is reported depending on the mode.
+    pub fn process(&self, ctx: &mut Context) {
+        let pending = std::mem::take(&mut *self.pending.borrow_mut());
+
+        for rejection in pending {
+            let reason = match JsPromise::from_object(rejection.promise.clone())
+                .map(|promise| promise.state())
+            {
+                Ok(PromiseState::Rejected(reason)) => reason,
+                _ => continue,
+            };
+
+            match self.dispatch(&rejection.promise, &reason, ctx) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    report_uncaught(&e, ctx);
+                    continue;
+                }
+            }
+
+            let location = match &rejection.path {
+                Some(path) => format!("\n    at {}", path.display()),
+                None => String::new(),
+            };
+
+            match self.mode.get() {
+                // Exiting would end the whole process, a worker reports it to its parent
+                UnhandledRejections::Strict if is_worker() => {
+                    let message = format!("(in promise) {}", reason.display());
+                    report_uncaught_error(message, reason, ctx);
+                }
+                UnhandledRejections::Strict => {
+                    eprintln!("Uncaught (in promise) {}{}", reason.display(), location);
+
+                    IkeGlobalObject::update_exit_code(ctx, 1);
+                    exit(ctx);
+                }
+                UnhandledRejections::Warn => {
+                    eprintln!(
+                        "Unhandled promise rejection: {}{}",
+                        reason.display(),
+                        location
+                    );
+                }
+                UnhandledRejections::None => {}
+            }
+        }
+    }
+
+    fn dispatch(&self, promise: &JsObject, reason: &JsValue, ctx: &mut Context) -> JsResult<bool> {
+        let init = JsObject::with_object_proto(ctx.intrinsics());
+        init.set(js_str!("cancelable"), true, false, ctx)?;
+        init.set(js_str!("promise"), promise.clone(), false, ctx)?;
+        init.set(js_str!("reason"), reason.clone(), false, ctx)?;
+
+        let event = new_event(
+            ctx,
+            "PromiseRejectionEvent",
+            &[js_string!("unhandledrejection").into(), init.into()],
+        )?;
+
+        dispatch(ctx, event)
+    }
+}
+
+impl HostHooks for RejectionTracker {
+    fn promise_rejection_tracker(
+        &self,
+        promise: &JsObject,
+        operation: OperationType,
+        context: &mut Context,
+    ) {
+        match operation {
+            OperationType::Reject => {
+                let path = match context.get_active_script_or_module() {
+                    Some(ActiveRunnable::Module(module)) => module.path().map(PathBuf::from),
+                    Some(ActiveRunnable::Script(script)) => script.path().map(PathBuf::from),
+                    None => None,
+                };
+
+                self.pending.borrow_mut().push(Rejection {
+                    promise: promise.clone(),
+                    path,
+                });
+            }
+            // A handler was added before the microtasks ran out, it's not unhandled
+            OperationType::Handle => self
+                .pending
+                .borrow_mut()
+                .retain(|rejection| !JsObject::equals(&rejection.promise, promise)),
+        }
+    }
+}
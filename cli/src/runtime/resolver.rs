@@ -0,0 +1,533 @@
+use fs::normalize_p;
+use ike_fs::find_nearest_file;
+use ike_logger::{elog, Logger};
+use ike_toml::{Dependency, ExportCondition, IkeToml, ModuleType};
+use oxc_resolver::{EnforceExtension, ResolveOptions, Resolver};
+use serde::Deserialize;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+use url::Url;
+
+use super::modules::is_builtin_module;
+use crate::fs::read_jsonc;
+use crate::install::STORE_DIR;
+
+#[derive(Debug)]
+pub struct IkeResolver {
+    resolver: Resolver,
+    imports: ImportMap,
+    paths: ImportMap,
+    manifests: RefCell<HashMap<PathBuf, Option<Rc<IkeToml>>>>,
+}
+
+impl Default for IkeResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IkeResolver {
+    pub fn new() -> Self {
+        let options = ResolveOptions {
+            enforce_extension: EnforceExtension::Disabled,
+            condition_names: vec!["node".into(), "import".into()],
+            extensions: vec![
+                ".js".into(),
+                ".mjs".into(),
+                ".ts".into(),
+                ".mts".into(),
+                ".cjs".into(),
+                ".cts".into(),
+            ],
+            ..ResolveOptions::default()
+        };
+
+        Self {
+            resolver: Resolver::new(options),
+            imports: ImportMap::default(),
+            paths: ImportMap::default(),
+            manifests: RefCell::default(),
+        }
+    }
+
+    /// Creates a resolver for the project at `root`, it picks up `[imports]` from the nearest
+    /// ike.toml and `compilerOptions.paths` from the nearest tsconfig.json.
+    pub fn from_root(root: &Path) -> Self {
+        let mut resolver = Self::new();
+
+        // Problems with ike.toml are reported by the command that started the runtime
+        if let Ok(Some(pkg)) = IkeToml::find_nearest_from(root.to_path_buf()) {
+            resolver.imports = ImportMap::from_ike_toml(&pkg);
+        }
+
+        if let Some(tsconfig) = find_nearest_file(root.to_path_buf(), "tsconfig.json") {
+            resolver.paths = ImportMap::from_tsconfig(&tsconfig);
+        }
+
+        resolver
+    }
+
+    /// Resolves `specifier` relative to the directory `dir` and returns an absolute path to the file.
+    pub fn resolve(&self, dir: &Path, specifier: &str) -> Result<PathBuf, String> {
+        // Relative and absolute specifiers are never remapped.
+        // ike.toml takes precedence over tsconfig.json
+        let candidates = if is_bare_specifier(specifier) {
+            self.imports
+                .map(specifier)
+                .or_else(|| self.paths.map(specifier))
+                .unwrap_or_else(|| vec![specifier.to_string()])
+        } else {
+            vec![specifier.to_string()]
+        };
+
+        let mut first_error = None;
+        for candidate in candidates {
+            match self.resolve_candidate(dir, &candidate) {
+                Ok(path) => return Ok(path),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+
+        Err(format!(
+            "Cannot resolve module `{}`: {}",
+            specifier,
+            first_error.unwrap_or_default()
+        ))
+    }
+
+    fn resolve_candidate(&self, dir: &Path, specifier: &str) -> Result<PathBuf, String> {
+        if is_bare_specifier(specifier) {
+            if let Some(result) = self.resolve_package(dir, specifier) {
+                return result;
+            }
+        }
+
+        // npm packages and everything else is handled by oxc, it understands package.json
+        self.resolver
+            .resolve(dir, specifier)
+            .map(|resolution| resolution.full_path())
+            .map_err(|err| err.to_string())
+    }
+
+    /// Resolves a bare specifier to a dependency declared in the ike.toml of the importing
+    /// package. Returns `None` if the specifier is not a dependency of that package.
+    fn resolve_package(&self, dir: &Path, specifier: &str) -> Option<Result<PathBuf, String>> {
+        let (name, subpath) = split_package_specifier(specifier);
+        let pkg = self.nearest_manifest(dir)?;
+        let dep = pkg.toml.find_dependency(name)?;
+        let dep_dir = self.dependency_dir(&pkg, name, dep)?;
+
+        if !dep_dir.exists() {
+            return Some(Err(match dep.path {
+                Some(_) => format!(
+                    "Dependency `{}` points to `{}` which does not exist",
+                    name,
+                    dep_dir.display()
+                ),
+                None => format!(
+                    "Dependency `{}` is not installed, run `ike install` first",
+                    name
+                ),
+            }));
+        }
+
+        let target = match self.manifest(&dep_dir.join("ike.toml")) {
+            Some(manifest) => match manifest
+                .toml
+                .resolve_export(subpath, ExportCondition::Import)
+            {
+                Some(target) => target,
+                None if subpath.is_empty() => {
+                    return Some(Err(format!(
+                        "Package `{}` has no `main` field and doesn't export `.`",
+                        name
+                    )))
+                }
+                None => {
+                    return Some(Err(format!(
+                        "Package `{}` doesn't export `{}`",
+                        name, subpath
+                    )))
+                }
+            },
+            // Not an Ike package, let oxc read its package.json
+            None if subpath.is_empty() => ".".to_string(),
+            None => subpath.to_string(),
+        };
+        let target = if is_bare_specifier(&target) && target != "." {
+            format!("./{}", target)
+        } else {
+            target
+        };
+
+        Some(
+            self.resolver
+                .resolve(&dep_dir, &target)
+                .map(|resolution| resolution.full_path())
+                .map_err(|err| err.to_string()),
+        )
+    }
+
+    /// Directory where the sources of `dep` live. Path dependencies and workspace members
+    /// are used in place, everything else is read from the store `ike install` puts
+    /// dependencies into. Like node, stores of parent directories are searched too, since
+    /// workspace members share the store of the workspace root.
+    fn dependency_dir(&self, pkg: &IkeToml, name: &str, dep: &Dependency) -> Option<PathBuf> {
+        let base = pkg.dir()?;
+
+        if let Some(path) = &dep.path {
+            return Some(normalize_p(base.join(path)));
+        }
+
+        if let Some(member) = pkg.workspace.as_ref().and_then(|ws| ws.member(name)) {
+            return member.dir().map(Path::to_path_buf);
+        }
+
+        let installed = base
+            .ancestors()
+            .map(|dir| dir.join(STORE_DIR).join(name))
+            .find(|dir| dir.exists());
+
+        Some(installed.unwrap_or_else(|| base.join(STORE_DIR).join(name)))
+    }
+
+    fn nearest_manifest(&self, dir: &Path) -> Option<Rc<IkeToml>> {
+        let file = find_nearest_file(dir.to_path_buf(), "ike.toml")?;
+        self.manifest(&file)
+    }
+
+    /// Manifests are parsed once and then cached.
+    fn manifest(&self, file: &Path) -> Option<Rc<IkeToml>> {
+        if let Some(manifest) = self.manifests.borrow().get(file) {
+            return manifest.clone();
+        }
+
+        let manifest = match file.exists() {
+            true => {
+                match IkeToml::from_file(file.to_path_buf()).and_then(IkeToml::with_workspace) {
+                    Ok(manifest) => Some(Rc::new(manifest)),
+                    Err(e) => {
+                        elog!(error, "{}", e);
+                        None
+                    }
+                }
+            }
+            false => None,
+        };
+        self.manifests
+            .borrow_mut()
+            .insert(file.to_path_buf(), manifest.clone());
+
+        manifest
+    }
+
+    /// Whether `file` is an ES module or CommonJS. Like in node, `.mjs` and `.cjs` decide on
+    /// their own and other files follow `type` of the nearest package.json. Without a `type`
+    /// only packages in the store are CommonJS, the project's own files are ES modules.
+    pub fn module_type(&self, file: &Path) -> ModuleType {
+        let extension = file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        match extension {
+            "mjs" | "mts" => return ModuleType::Module,
+            "cjs" | "cts" => return ModuleType::CommonJs,
+            "js" | "jsx" | "ts" | "tsx" => {}
+            _ => return ModuleType::Module,
+        }
+
+        let dir = file.parent().unwrap_or(file);
+        let declared = find_nearest_file(dir.to_path_buf(), "package.json")
+            .and_then(|package_json| self.manifest(&package_json))
+            .and_then(|manifest| manifest.toml.module_type);
+
+        match declared {
+            Some(module_type) => module_type,
+            None if file.components().any(|part| part.as_os_str() == STORE_DIR) => {
+                ModuleType::CommonJs
+            }
+            None => ModuleType::Module,
+        }
+    }
+
+    /// Same as [`IkeResolver::resolve`], but returns a URL string like `import.meta.resolve` does.
+    /// Builtin modules are returned as they are.
+    pub fn resolve_to_url(&self, dir: &Path, specifier: &str) -> Result<String, String> {
+        if is_builtin_module(specifier) {
+            return Ok(specifier.to_string());
+        }
+
+        let path = self.resolve(dir, specifier)?;
+        path_to_url(&path)
+    }
+}
+
+/// Maps specifiers to other specifiers before they are resolved, following the import map
+/// semantics: exact matches win, otherwise the longest prefix ending with `/` is used.
+#[derive(Debug, Default, Clone)]
+pub struct ImportMap {
+    exact: HashMap<String, Vec<String>>,
+    prefixes: Vec<(String, Vec<String>)>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct TsConfig {
+    #[serde(default)]
+    compiler_options: TsCompilerOptions,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct TsCompilerOptions {
+    base_url: Option<String>,
+    paths: Option<HashMap<String, Vec<String>>>,
+}
+
+impl ImportMap {
+    pub fn insert(&mut self, specifier: String, targets: Vec<String>) {
+        if specifier.ends_with('/') {
+            self.prefixes.push((specifier, targets));
+            // Longest prefix first
+            self.prefixes.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        } else {
+            self.exact.insert(specifier, targets);
+        }
+    }
+
+    /// Returns the specifiers `specifier` maps to, or `None` if there is no mapping for it.
+    pub fn map(&self, specifier: &str) -> Option<Vec<String>> {
+        if let Some(targets) = self.exact.get(specifier) {
+            return Some(targets.clone());
+        }
+
+        self.prefixes.iter().find_map(|(prefix, targets)| {
+            specifier.strip_prefix(prefix.as_str()).map(|rest| {
+                targets
+                    .iter()
+                    .map(|target| format!("{}{}", target, rest))
+                    .collect()
+            })
+        })
+    }
+
+    pub fn from_ike_toml(pkg: &IkeToml) -> Self {
+        let mut map = Self::default();
+        let base = pkg.dir().map(Path::to_path_buf).unwrap_or_default();
+
+        for (specifier, target) in pkg.toml.imports.iter() {
+            map.insert(specifier.clone(), vec![absolutize_target(&base, target)]);
+        }
+
+        map
+    }
+
+    pub fn from_tsconfig(file: &Path) -> Self {
+        let mut map = Self::default();
+        let tsconfig: TsConfig = match read_jsonc(file) {
+            Ok(tsconfig) => tsconfig,
+            Err(_) => return map,
+        };
+        let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        let options = tsconfig.compiler_options;
+        let base = match options.base_url {
+            Some(base_url) => dir.join(base_url),
+            None => dir,
+        };
+
+        for (pattern, targets) in options.paths.unwrap_or_default() {
+            // Only trailing wildcards are supported, `@/*` behaves like the `@/` prefix.
+            let (specifier, wildcard) = match pattern.strip_suffix('*') {
+                Some(prefix) => (prefix.to_string(), true),
+                None => (pattern.clone(), false),
+            };
+            let targets = targets
+                .iter()
+                .map(|target| {
+                    let target = if wildcard {
+                        target.trim_end_matches('*')
+                    } else {
+                        target.as_str()
+                    };
+                    // tsconfig paths are always relative to the base url
+                    let target = if target.starts_with('.') || target.starts_with('/') {
+                        target.to_string()
+                    } else {
+                        format!("./{}", target)
+                    };
+                    let mut target = absolutize_target(&base, &target);
+                    if wildcard && specifier.ends_with('/') && !target.ends_with('/') {
+                        target.push('/');
+                    }
+                    target
+                })
+                .collect();
+
+            if wildcard && !specifier.ends_with('/') {
+                // e.g. `~*`, we can't express it as a directory prefix so keep it as is
+                map.prefixes.push((specifier, targets));
+                map.prefixes.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+            } else {
+                map.insert(specifier, targets);
+            }
+        }
+
+        map
+    }
+}
+
+/// Splits `@scope/name/sub/path` into `@scope/name` and `sub/path`.
+pub fn split_package_specifier(specifier: &str) -> (&str, &str) {
+    let mut separators = specifier.match_indices('/').map(|(index, _)| index);
+    let index = if specifier.starts_with('@') {
+        separators.nth(1)
+    } else {
+        separators.next()
+    };
+
+    match index {
+        Some(index) => (&specifier[..index], &specifier[index + 1..]),
+        None => (specifier, ""),
+    }
+}
+
+pub fn is_bare_specifier(specifier: &str) -> bool {
+    !(specifier.starts_with("./")
+        || specifier.starts_with("../")
+        || specifier == "."
+        || specifier == ".."
+        || Path::new(specifier).is_absolute())
+}
+
+/// Relative targets are relative to the file that declares them, bare ones are left untouched
+/// so they go through the regular resolution.
+fn absolutize_target(base: &Path, target: &str) -> String {
+    if target.starts_with("./") || target.starts_with("../") || target == "." {
+        let mut absolute = normalize_p(base.join(target)).to_string_lossy().to_string();
+        if target.ends_with('/') && !absolute.ends_with('/') {
+            absolute.push('/');
+        }
+        absolute
+    } else {
+        target.to_string()
+    }
+}
+
+pub fn path_to_url(path: &Path) -> Result<String, String> {
+    Url::from_file_path(path)
+        .map(|url| url.to_string())
+        .map_err(|_| format!("Failed to convert `{}` to a file URL", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_map() {
+        let mut map = ImportMap::default();
+        map.insert("@/".to_string(), vec!["/project/src/".to_string()]);
+        map.insert("@/lib/".to_string(), vec!["/project/lib/".to_string()]);
+        map.insert("utils".to_string(), vec!["/project/utils.ts".to_string()]);
+
+        assert_eq!(
+            map.map("utils"),
+            Some(vec!["/project/utils.ts".to_string()])
+        );
+        assert_eq!(
+            map.map("@/index.ts"),
+            Some(vec!["/project/src/index.ts".to_string()])
+        );
+        assert_eq!(
+            map.map("@/lib/a.ts"),
+            Some(vec!["/project/lib/a.ts".to_string()])
+        );
+        assert_eq!(map.map("utils/a.ts"), None);
+    }
+
+    #[test]
+    fn test_split_package_specifier() {
+        assert_eq!(split_package_specifier("pkg"), ("pkg", ""));
+        assert_eq!(split_package_specifier("pkg/utils"), ("pkg", "utils"));
+        assert_eq!(split_package_specifier("@scope/pkg"), ("@scope/pkg", ""));
+        assert_eq!(
+            split_package_specifier("@scope/pkg/a/b"),
+            ("@scope/pkg", "a/b")
+        );
+    }
+
+    #[test]
+    fn test_absolutize_target() {
+        let base = Path::new("/project");
+
+        assert_eq!(absolutize_target(base, "./src/"), "/project/src/");
+        assert_eq!(absolutize_target(base, "../a.ts"), "/a.ts");
+        assert_eq!(absolutize_target(base, "lodash"), "lodash");
+    }
+
+    /// Writes `tsconfig` into an empty directory for a single test.
+    fn write_tsconfig(name: &str, tsconfig: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ike-tsconfig-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tsconfig.json"), tsconfig).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_tsconfig_paths() {
+        let dir = write_tsconfig(
+            "paths",
+            r#"{
+  // Comments and trailing commas are allowed like in tsc
+  "compilerOptions": {
+    "paths": {
+      "@/*": ["./src/*"],
+      "~*": ["./lib/*", "./vendor/*"],
+      "utils": ["./src/utils.ts"],
+    },
+  },
+}"#,
+        );
+        let map = ImportMap::from_tsconfig(&dir.join("tsconfig.json"));
+        let path = |path: &str| format!("{}/{}", dir.display(), path);
+
+        assert_eq!(map.map("@/a/b.ts"), Some(vec![path("src/a/b.ts")]));
+        assert_eq!(
+            map.map("~c.ts"),
+            Some(vec![path("lib/c.ts"), path("vendor/c.ts")])
+        );
+        assert_eq!(map.map("utils"), Some(vec![path("src/utils.ts")]));
+        assert_eq!(map.map("utils/a.ts"), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tsconfig_base_url() {
+        let dir = write_tsconfig(
+            "base-url",
+            r#"{
+  "compilerOptions": {
+    "baseUrl": "./src",
+    "paths": { "@/*": ["*"], "lib": ["../lib/index.ts"] }
+  }
+}"#,
+        );
+        let map = ImportMap::from_tsconfig(&dir.join("tsconfig.json"));
+        let path = |path: &str| format!("{}/{}", dir.display(), path);
+
+        // Targets are relative to `baseUrl`, even without a leading `./`
+        assert_eq!(map.map("@/a.ts"), Some(vec![path("src/a.ts")]));
+        assert_eq!(map.map("lib"), Some(vec![path("lib/index.ts")]));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -13,7 +13,15 @@ import {
   WritableStreamDefaultController,
   WritableStreamDefaultWriter,
 } from "@std/streams";
-import { clearTimeout, setTimeout } from "module:web/timeouts.js";
+import {
+  clearImmediate,
+  clearInterval,
+  clearTimeout,
+  queueMicrotask,
+  setImmediate,
+  setInterval,
+  setTimeout,
+} from "module:web/timeouts.js";
 import { atob, btoa } from "module:web/base64.js";
 
 const registerGlobal = (name, value) => {
@@ -36,6 +44,15 @@ import { TextDecoder, TextEncoder } from "module:web/encoding.js";
 import { Headers } from "module:web/headers.js";
 import { URL, URLSearchParams } from "module:web/url.js";
 import { Console } from "module:web/console.js";
+import {
+  CustomEvent,
+  ErrorEvent,
+  Event,
+  EventTarget,
+  MessageEvent,
+  PromiseRejectionEvent,
+} from "module:web/events.js";
+import { Worker } from "@std/_worker_";
 
 const exports = {
   ReadableStream,
@@ -57,6 +74,11 @@ const exports = {
 
   setTimeout,
   clearTimeout,
+  setInterval,
+  clearInterval,
+  setImmediate,
+  clearImmediate,
+  queueMicrotask,
 
   atob,
   btoa,
@@ -69,7 +91,20 @@ const exports = {
   URLSearchParams,
   URL,
 
+  Event,
+  CustomEvent,
+  PromiseRejectionEvent,
+  MessageEvent,
+  ErrorEvent,
+  EventTarget,
+  addEventListener: EventTarget.prototype.addEventListener.bind(globalThis),
+  removeEventListener:
+    EventTarget.prototype.removeEventListener.bind(globalThis),
+  dispatchEvent: EventTarget.prototype.dispatchEvent.bind(globalThis),
+
   console: new Console(),
+
+  Worker,
 };
 
 for (const prop in exports) {
@@ -91,6 +126,14 @@ import {
   remove,
   removeSync,
 } from "module:fs/fs.js";
+import {
+  addSignalListener,
+  exec,
+  kill,
+  removeSignalListener,
+  spawn,
+  spawnSync,
+} from "module:process/process.js";
 
 const ikeExports = {
   createDir,
@@ -105,6 +148,13 @@ const ikeExports = {
   remove,
   removeSync,
 
+  exec,
+  spawn,
+  spawnSync,
+  kill,
+  addSignalListener,
+  removeSignalListener,
+
   path: await import("@std/path"),
 };
 
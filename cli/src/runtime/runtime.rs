@@ -1,232 +1,127 @@
 use super::{
-    call::rust_function,
-    console::Console,
-    ike::IkeGlobalObject,
-    meta::Meta,
-    modules::IkeModuleLoader,
-    queue::Queue,
-    terminal::{Terminal, TerminalStdin},
+    call::rust_function, env::IkeEnv, ike::IkeGlobalObject, lifecycle::run_until_exit, meta::Meta,
+    modules::IkeModuleLoader, permissions::IkePermissions, queue::Queue,
+    rejections::UnhandledRejections, terminal::TerminalStdin,
 };
 use crate::testing::js::JsTest;
-use crate::transpiler::transpile;
 use boa_engine::{
-    builtins::promise::PromiseState, js_str, js_string, module, property::Attribute, Context,
-    JsNativeError, JsObject, JsResult, JsStr, JsString, JsValue, Module, NativeFunction, Source,
+    js_string, Context, JsError, JsNativeError, JsResult, JsValue, Module, NativeFunction, Source,
 };
 use fs::FsModule;
-use ike_core::{get_prototype_name, js_str_to_string, throw, ModuleTrait};
-use ike_logger::{cond_log, Logger};
-use smol::LocalExecutor;
-use std::{
-    path::{Path, PathBuf},
-    rc::Rc,
+use ike_core::{
+    get_prototype_name, js_str_to_string, permissions::Permissions, runtime::Runtime, throw,
 };
+use ike_logger::{cond_log, Logger};
+use process::ProcessModule;
+use std::{path::PathBuf, rc::Rc};
 use web::WebModule;
 
-pub fn start_runtime(file: &PathBuf, context: Option<&mut Context>) -> JsResult<()> {
-    let queue = Rc::new(Queue::new(LocalExecutor::new()));
-    let module_loader = Rc::new(IkeModuleLoader::new(std::env::current_dir().unwrap())?);
-    let ctx = match context {
-        Some(ctx) => ctx,
-        None => &mut Context::builder()
-            .job_queue(queue)
-            .module_loader(module_loader.clone())
-            .build()
-            .unwrap(),
-    };
-
-    load_modules(ctx, module_loader)?;
-    setup_context(ctx, Some(file));
-
-    let script_source = Source::from_bytes(include_bytes!("./runtime.js"));
-    let script_module = Module::parse(script_source, None, ctx)?;
-    evaulte_module(ctx, script_module)?;
-
-    let transpiled = match transpile(file) {
-        Ok(transpiler) => transpiler,
-        Err(e) => throw!(typ, "Failed to transpile: {:?}", e),
-    };
-    let reader = Source::from_bytes(transpiled.as_bytes()).with_path(&Path::new(&file));
-    let module = Module::parse(reader, None, ctx)?;
-
-    evaulte_module(ctx, module)?;
-
-    Ok(())
-}
-
-pub fn load_modules(ctx: &mut Context, module_loader: Rc<IkeModuleLoader>) -> JsResult<()> {
-    let modules: Vec<(&dyn ModuleTrait, Rc<IkeModuleLoader>)> = vec![
-        (&WebModule, Rc::clone(&module_loader)),
-        (&FsModule, module_loader),
-    ];
-
-    for (module, loader) in modules {
-        load_module(ctx, module, loader)?;
+/// Runs `file` until the event loop drains and returns the code the process exits with.
+pub fn start_runtime(
+    file: &PathBuf,
+    features: &[String],
+    permissions: Permissions,
+    unhandled_rejections: UnhandledRejections,
+) -> JsResult<i32> {
+    let queue = Rc::new(Queue::new());
+    queue.rejection_tracker().set_mode(unhandled_rejections);
+    let module_loader = Rc::new(IkeModuleLoader::new(current_dir()?)?);
+    module_loader.set_main(file);
+
+    let mut runtime = build_runtime(&queue, &module_loader, Some(file), features, permissions)?;
+
+    if let Err(e) = runtime.execute_module(file) {
+        let ctx = runtime.context();
+        print_module_error(&e, ctx);
+        IkeGlobalObject::update_exit_code(ctx, 1);
     }
 
-    Ok(())
+    Ok(run_until_exit(&queue, runtime.context()))
 }
 
-pub fn load_module(
-    ctx: &mut Context,
-    module: &dyn ModuleTrait,
-    module_loader: Rc<IkeModuleLoader>,
-) -> JsResult<()> {
-    let files = module.js_files();
-    let exposed = module.exposed_functions();
-    for exposed_fn in exposed.iter() {
-        ctx.register_global_builtin_callable(
-            js_string!(exposed_fn.name),
-            0,
-            NativeFunction::from_fn_ptr(exposed_fn.function),
-        )
-        .expect(&format!(
-            "Failed to set exposed function {:?}",
-            exposed_fn.name
-        ));
-    }
-
-    for (file, content) in files {
-        let path = Path::new(module.cwd()).join(file);
-        let result = Source::from_bytes(content.as_bytes()).with_path(&path);
-
-        let parsed_module = Module::parse(result, None, ctx)?;
-        module_loader.insert(PathBuf::from(module.name_for(file)), parsed_module);
-    }
-
-    Ok(())
+/// Builds the runtime of `ike run`, `ike test` and workers with the builtin modules, `Ike`
+/// and the globals of runtime.js. `Ike.meta` is the one of `file`.
+pub fn build_runtime(
+    queue: &Rc<Queue>,
+    module_loader: &Rc<IkeModuleLoader>,
+    file: Option<&PathBuf>,
+    features: &[String],
+    permissions: Permissions,
+) -> JsResult<Runtime> {
+    let file = file.cloned();
+    let features = features.to_vec();
+
+    let mut runtime = Runtime::builder()
+        .job_queue(Rc::clone(queue))
+        .host_hooks(queue.rejection_tracker())
+        .module_loader(Rc::clone(module_loader))
+        .permissions(permissions)
+        .module(WebModule)
+        .module(FsModule)
+        .module(ProcessModule)
+        .global("Ike", move |ctx| {
+            Ok(IkeGlobalObject::init(ctx, file.as_ref()).into())
+        })
+        .global_function("$rustFunction", 0, unsafe {
+            NativeFunction::from_closure(rust_function)
+        })
+        .setup(setup_context)
+        .setup(move |ctx| IkeGlobalObject::set_features(ctx, &features))
+        .build()?;
+
+    let source = Source::from_bytes(include_bytes!("./runtime.js"));
+    let module = Module::parse(source, None, runtime.context())?;
+    runtime.evaluate_module(module)?;
+
+    Ok(runtime)
 }
 
-pub fn evaulte_module(ctx: &mut Context, module: Module) -> JsResult<()> {
-    let promise = module.load_link_evaluate(ctx);
-
-    ctx.run_jobs();
-
-    match promise.state() {
-        PromiseState::Pending => panic!("module didn't execute!"),
-        PromiseState::Fulfilled(v) => {
-            assert_eq!(v, JsValue::undefined())
-        }
-        PromiseState::Rejected(err) => {
-            let obj = err.to_object(ctx).unwrap();
-            let proto = match obj.prototype() {
-                Some(proto) => proto,
-                None => {
-                    panic!("Error object has no prototype");
-                }
-            };
-            let str_name = get_prototype_name!(proto, ctx);
-
-            let message = obj.get(js_string!("message"), ctx).unwrap();
-            cond_log!(
-                true,
-                true,
-                "<r><red>error<r><d>({})<r>: {}",
-                str_name,
-                js_str_to_string!(message.to_string(ctx).unwrap())
-            );
-        }
+pub fn current_dir() -> JsResult<PathBuf> {
+    match std::env::current_dir() {
+        Ok(dir) => Ok(dir),
+        Err(e) => throw!(err, "Could not read the current directory, {}", e),
     }
-
-    Ok(())
 }
 
-pub enum SetupType {
-    Class,
-    Property,
-    Callable,
-    BuiltinCallable,
-}
+/// Prints an error a module threw while it was evaluated, like `error(TypeError): message`.
+pub fn print_module_error(err: &JsError, ctx: &mut Context) {
+    let error = err.to_opaque(ctx);
+    let (name, message) = match error.as_object() {
+        Some(obj) => {
+            let name = match obj.prototype() {
+                Some(proto) => get_prototype_name!(proto, ctx),
+                None => "Error".to_string(),
+            };
+            let message = obj
+                .get(js_string!("message"), ctx)
+                .and_then(|message| message.to_string(ctx))
+                .map(|message| js_str_to_string!(message))
+                .unwrap_or_default();
 
-pub enum SetupValue {
-    Object(JsObject),
-    Function(NativeFunction),
-}
+            (name, message)
+        }
+        None => ("Uncaught".to_string(), error.display().to_string()),
+    };
 
-pub struct SetupEntry {
-    pub setup_type: SetupType,
-    pub value: SetupValue,
-    pub name: JsStr<'static>,
-    pub length: Option<usize>,
+    cond_log!(true, true, "<r><red>error<r><d>({})<r>: {}", name, message);
 }
 
-pub fn setup_context(ctx: &mut Context, file: Option<&PathBuf>) {
-    let ike = IkeGlobalObject::init(ctx, file);
+/// Adds the parts of `Ike` that need the context, and the globals of the test runner.
+pub fn setup_context(ctx: &mut Context) -> JsResult<()> {
     JsTest::init(ctx);
 
-    let entries = [
-        SetupEntry {
-            setup_type: SetupType::Property,
-            value: SetupValue::Object(ike),
-            name: js_str!("Ike"),
-            length: None,
-        },
-        SetupEntry {
-            setup_type: SetupType::BuiltinCallable,
-            value: SetupValue::Function(unsafe { NativeFunction::from_closure(rust_function) }),
-            name: js_str!("$rustFunction"),
-            length: None,
-        },
-    ];
-
-    for entry in entries.iter() {
-        match entry.setup_type {
-            SetupType::Property => {
-                if let SetupValue::Object(ref obj) = entry.value {
-                    ctx.register_global_property(
-                        entry.name,
-                        JsValue::Object(obj.clone()),
-                        Attribute::all(),
-                    )
-                    .unwrap_or_else(|_| panic!("{:?} is already defined", entry.name));
-                }
-            }
-            SetupType::Callable => {
-                if let SetupValue::Function(ref func) = entry.value {
-                    ctx.register_global_callable(
-                        js_string!(entry.name),
-                        entry.length.unwrap_or(0),
-                        func.clone(),
-                    )
-                    .unwrap_or_else(|_| panic!("{:?} is already defined", entry.name));
-                }
-            }
-            SetupType::BuiltinCallable => {
-                if let SetupValue::Function(ref func) = entry.value {
-                    ctx.register_global_builtin_callable(
-                        js_string!(entry.name),
-                        entry.length.unwrap_or(0),
-                        func.clone(),
-                    )
-                    .unwrap_or_else(|_| panic!("{:?} is already defined", entry.name));
-                }
-            }
-            _ => todo!(),
-        }
-    }
+    let ike = IkeGlobalObject::get_ike_global_object(ctx);
 
-    let ike = ctx.global_object().get(js_string!("Ike"), ctx).unwrap();
-    let ike = ike.as_object().unwrap();
-
-    let env_vars = std::env::vars();
-    let env_obj = JsObject::default();
-
-    for (key, value) in env_vars {
-        env_obj
-            .set(
-                js_string!(key),
-                JsValue::from(js_string!(value)),
-                false,
-                ctx,
-            )
-            .expect("Failed to set env var");
-    }
-    ike.set(js_string!("env"), env_obj, false, ctx)
-        .expect("Failed to set Ike.env");
+    let env = IkeEnv::init(ctx);
+    ike.set(js_string!("env"), env, false, ctx)?;
+
+    let permissions = IkePermissions::init(ctx);
+    ike.set(js_string!("permissions"), permissions, false, ctx)?;
 
     let stdin = TerminalStdin::init(ctx);
-    ike.set(js_string!("stdin"), stdin, false, ctx)
-        .expect("Failed to set Ike.stdin");
+    ike.set(js_string!("stdin"), stdin, false, ctx)?;
+
+    Ok(())
 }
 
 pub fn get_current_path(ctx: &mut Context) -> JsValue {
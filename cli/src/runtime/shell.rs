@@ -0,0 +1,150 @@
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+use boa_engine::{
+    js_string,
+    object::builtins::{JsArray, JsArrayBuffer, JsTypedArray},
+    Context, JsError, JsNativeError, JsObject, JsResult, JsValue,
+};
+use glob::{glob_with, MatchOptions, Pattern};
+use ike_core::{
+    permissions::{self, PermissionKind},
+    str_from_jsvalue, throw,
+};
+
+use crate::assert_arg_type;
+
+fn string_arg(args: &[JsValue], index: usize, ctx: &mut Context) -> JsResult<String> {
+    let value = match args.get(index) {
+        Some(value) => value,
+        None => throw!(typ, "Expected argument {} to be a string", index),
+    };
+    assert_arg_type!(string, value);
+
+    Ok(str_from_jsvalue!(value, ctx))
+}
+
+fn io_error(path: &str, err: std::io::Error) -> JsError {
+    JsNativeError::error()
+        .with_message(format!("{}: {}", path, err))
+        .into()
+}
+
+/// Expands a glob of `@std/shell`. Relative patterns are matched from `cwd` and the
+/// matches are relative to it as well. Hidden files only match a literal leading dot.
+pub fn shell_glob(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let pattern = string_arg(args, 0, ctx)?;
+    let cwd = string_arg(args, 1, ctx)?;
+
+    let relative = !Path::new(&pattern).is_absolute();
+    let full = match relative {
+        true => format!("{}/{}", Pattern::escape(&cwd), pattern),
+        false => pattern.clone(),
+    };
+
+    let options = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: true,
+    };
+    let paths = match glob_with(&full, options) {
+        Ok(paths) => paths,
+        Err(e) => throw!(err, "Invalid glob `{}`, {}", pattern, e),
+    };
+
+    // Matches the sandbox doesn't allow reading are left out
+    let mut matches: Vec<String> = paths
+        .flatten()
+        .filter(|path| permissions::is_granted(PermissionKind::Read, path.to_str(), ctx))
+        .map(|path| match relative {
+            true => path.strip_prefix(&cwd).unwrap_or(&path).to_path_buf(),
+            false => path,
+        })
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    matches.sort();
+
+    let values: Vec<JsValue> = matches
+        .into_iter()
+        .map(|path| js_string!(path).into())
+        .collect();
+
+    Ok(JsArray::from_iter(values, ctx).into())
+}
+
+/// Sorted names of the entries of a directory, for `ls`.
+pub fn shell_read_dir(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let path = string_arg(args, 0, ctx)?;
+    permissions::check(PermissionKind::Read, Some(&path), ctx)?;
+
+    let mut names = std::fs::read_dir(&path)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().to_string()))
+                .collect::<Result<Vec<String>, _>>()
+        })
+        .map_err(|e| io_error(&path, e))?;
+    names.sort();
+
+    let values: Vec<JsValue> = names
+        .into_iter()
+        .map(|name| js_string!(name).into())
+        .collect();
+
+    Ok(JsArray::from_iter(values, ctx).into())
+}
+
+/// `{ isFile, isDir }` of a path, `null` if it doesn't exist.
+pub fn shell_stat(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let path = string_arg(args, 0, ctx)?;
+    permissions::check(PermissionKind::Read, Some(&path), ctx)?;
+
+    let metadata = match std::fs::metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(_) => return Ok(JsValue::null()),
+    };
+
+    let stat = JsObject::with_object_proto(ctx.intrinsics());
+    stat.set(js_string!("isFile"), metadata.is_file(), false, ctx)?;
+    stat.set(js_string!("isDir"), metadata.is_dir(), false, ctx)?;
+
+    Ok(stat.into())
+}
+
+/// Writes the bytes of a redirection to a file, appending for `>>`.
+pub fn shell_write_file(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let path = string_arg(args, 0, ctx)?;
+    permissions::check(PermissionKind::Write, Some(&path), ctx)?;
+    let append = args.get(2).is_some_and(JsValue::to_boolean);
+
+    let array = match args
+        .get(1)
+        .and_then(JsValue::as_object)
+        .map(|object| JsTypedArray::from_object(object.clone()))
+    {
+        Some(Ok(array)) => array,
+        _ => throw!(typ, "Expected a Uint8Array"),
+    };
+    let offset = array.byte_offset(ctx)?;
+    let length = array.byte_length(ctx)?;
+    let buffer = match array.buffer(ctx)?.as_object() {
+        Some(buffer) => JsArrayBuffer::from_object(buffer.clone())?,
+        None => throw!(typ, "Expected a Uint8Array"),
+    };
+    let bytes = buffer
+        .data()
+        .as_deref()
+        .and_then(|data| data.get(offset..offset + length))
+        .map(<[u8]>::to_vec)
+        .unwrap_or_default();
+
+    OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&path)
+        .and_then(|mut file| file.write_all(&bytes))
+        .map_err(|e| io_error(&path, e))?;
+
+    Ok(JsValue::undefined())
+}
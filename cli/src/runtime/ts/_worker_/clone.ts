@@ -0,0 +1,294 @@
+// Structured clone of the HTML spec. Values are encoded into a tree of tagged
+// arrays that is sent as JSON, the bytes of array buffers are sent next to it.
+
+type Encoded = [tag: string, ...values: any[]];
+
+const TYPED_ARRAYS = [
+  'Int8Array',
+  'Uint8Array',
+  'Uint8ClampedArray',
+  'Int16Array',
+  'Uint16Array',
+  'Int32Array',
+  'Uint32Array',
+  'Float32Array',
+  'Float64Array',
+  'BigInt64Array',
+  'BigUint64Array',
+];
+
+const ERRORS = [
+  'Error',
+  'EvalError',
+  'RangeError',
+  'ReferenceError',
+  'SyntaxError',
+  'TypeError',
+  'URIError',
+];
+
+export class DataCloneError extends Error {
+  override name = 'DataCloneError';
+}
+
+const isDetached = (buffer: ArrayBuffer): boolean =>
+  (buffer as { detached?: boolean }).detached === true;
+
+/**
+ * Encodes `value` and detaches the array buffers of `transfer`, which end up
+ * with the receiver.
+ */
+export const serialize = (
+  value: unknown,
+  transfer: unknown[] = [],
+): [data: string, buffers: ArrayBuffer[]] => {
+  const buffers: ArrayBuffer[] = [];
+  const bufferIds = new Map<ArrayBuffer, number>();
+  // Objects seen before are encoded as references, which keeps cycles intact
+  const objectIds = new Map<object, number>();
+
+  for (const buffer of transfer) {
+    if (!(buffer instanceof ArrayBuffer)) {
+      throw new DataCloneError('Only ArrayBuffers can be transferred');
+    }
+    if (bufferIds.has(buffer)) {
+      throw new DataCloneError('An ArrayBuffer is transferred twice');
+    }
+    if (isDetached(buffer)) {
+      throw new DataCloneError('A detached ArrayBuffer cannot be transferred');
+    }
+
+    bufferIds.set(buffer, buffers.push(buffer) - 1);
+  }
+
+  const bufferId = (buffer: ArrayBuffer): number => {
+    if (isDetached(buffer)) {
+      throw new DataCloneError('A detached ArrayBuffer cannot be cloned');
+    }
+
+    let id = bufferIds.get(buffer);
+    if (id === undefined) {
+      id = buffers.push(buffer) - 1;
+      bufferIds.set(buffer, id);
+    }
+
+    return id;
+  };
+
+  const encode = (value: unknown): Encoded => {
+    switch (typeof value) {
+      case 'undefined':
+        return ['u'];
+      case 'boolean':
+        return ['b', value];
+      case 'string':
+        return ['s', value];
+      case 'bigint':
+        return ['i', value.toString()];
+      case 'number':
+        // JSON has no NaN, infinities or -0
+        if (Number.isFinite(value) && !Object.is(value, -0)) {
+          return ['n', value];
+        }
+        return ['N', Object.is(value, -0) ? '-0' : String(value)];
+      case 'symbol':
+      case 'function':
+        throw new DataCloneError(`${String(value)} could not be cloned`);
+    }
+
+    if (value === null) {
+      return ['z'];
+    }
+
+    const object = value as object;
+    const seen = objectIds.get(object);
+    if (seen !== undefined) {
+      return ['r', seen];
+    }
+    objectIds.set(object, objectIds.size);
+
+    if (object instanceof ArrayBuffer) {
+      return ['B', bufferId(object)];
+    }
+    if (object instanceof DataView) {
+      return [
+        'V',
+        bufferId(object.buffer as ArrayBuffer),
+        object.byteOffset,
+        object.byteLength,
+      ];
+    }
+    if (ArrayBuffer.isView(object)) {
+      const array = object as unknown as Uint8Array;
+      const type = array[Symbol.toStringTag];
+
+      return [
+        'T',
+        type,
+        bufferId(array.buffer as ArrayBuffer),
+        array.byteOffset,
+        array.length,
+      ];
+    }
+    if (object instanceof Date) {
+      return ['d', object.getTime()];
+    }
+    if (object instanceof RegExp) {
+      return ['x', object.source, object.flags];
+    }
+    if (
+      object instanceof Boolean ||
+      object instanceof Number ||
+      object instanceof String
+    ) {
+      return ['w', encode(object.valueOf())];
+    }
+    if (object instanceof Error) {
+      const name = ERRORS.includes(object.name) ? object.name : 'Error';
+
+      return ['e', name, object.message, object.stack ?? null];
+    }
+    if (object instanceof Map) {
+      return [
+        'm',
+        Array.from(object, ([key, value]) => [encode(key), encode(value)]),
+      ];
+    }
+    if (object instanceof Set) {
+      return ['S', Array.from(object, encode)];
+    }
+    if (Array.isArray(object)) {
+      return ['a', Array.from(object, encode)];
+    }
+    if (
+      object instanceof Promise ||
+      object instanceof WeakMap ||
+      object instanceof WeakSet ||
+      object instanceof WeakRef
+    ) {
+      throw new DataCloneError(
+        `${Object.prototype.toString.call(object)} could not be cloned`,
+      );
+    }
+
+    return [
+      'o',
+      Object.keys(object).map((key) => [
+        key,
+        encode((object as Record<string, unknown>)[key]),
+      ]),
+    ];
+  };
+
+  const data = JSON.stringify(encode(value));
+
+  // Only detached once everything was encoded, a clone error leaves them be.
+  // The bytes move to the buffer `transfer` returns, which is what gets sent
+  for (const buffer of transfer as ArrayBuffer[]) {
+    const moved = (buffer as { transfer?: () => ArrayBuffer }).transfer?.();
+    if (moved !== undefined) {
+      buffers[bufferIds.get(buffer)!] = moved;
+    }
+  }
+
+  return [data, buffers];
+};
+
+/**
+ * Decodes a value encoded with {@link serialize}, `buffers` are the array
+ * buffers that were sent with it.
+ */
+export const deserialize = (data: string, buffers: ArrayBuffer[]): unknown => {
+  const objects: unknown[] = [];
+
+  // Objects are registered before their children like when encoding, so the
+  // ids of references match
+  const register = <T>(object: T): T => {
+    objects.push(object);
+    return object;
+  };
+
+  const decode = ([tag, ...values]: Encoded): unknown => {
+    switch (tag) {
+      case 'u':
+        return undefined;
+      case 'z':
+        return null;
+      case 'b':
+      case 's':
+      case 'n':
+        return values[0];
+      case 'N':
+        return values[0] === '-0' ? -0 : Number(values[0]);
+      case 'i':
+        return BigInt(values[0]);
+      case 'r':
+        return objects[values[0]];
+      case 'B':
+        return register(buffers[values[0]]);
+      case 'V':
+        return register(
+          new DataView(buffers[values[0]], values[1], values[2]),
+        );
+      case 'T': {
+        if (!TYPED_ARRAYS.includes(values[0])) {
+          throw new DataCloneError(`Unknown typed array ${values[0]}`);
+        }
+        const TypedArray = (globalThis as any)[values[0]];
+
+        return register(
+          new TypedArray(buffers[values[1]], values[2], values[3]),
+        );
+      }
+      case 'd':
+        return register(new Date(values[0]));
+      case 'x':
+        return register(new RegExp(values[0], values[1]));
+      case 'w': {
+        const index = objects.push(undefined) - 1;
+        objects[index] = Object(decode(values[0]));
+        return objects[index];
+      }
+      case 'e': {
+        const ErrorClass = (globalThis as any)[values[0]] ?? Error;
+        const error = register(new ErrorClass(values[1]));
+        if (values[2] !== null) {
+          error.stack = values[2];
+        }
+        return error;
+      }
+      case 'm': {
+        const map = register(new Map());
+        for (const [key, value] of values[0]) {
+          map.set(decode(key), decode(value));
+        }
+        return map;
+      }
+      case 'S': {
+        const set = register(new Set());
+        for (const value of values[0]) {
+          set.add(decode(value));
+        }
+        return set;
+      }
+      case 'a': {
+        const array = register(new Array(values[0].length));
+        values[0].forEach((value: Encoded, index: number) => {
+          array[index] = decode(value);
+        });
+        return array;
+      }
+      case 'o': {
+        const object = register({} as Record<string, unknown>);
+        for (const [key, value] of values[0]) {
+          object[key] = decode(value);
+        }
+        return object;
+      }
+      default:
+        throw new DataCloneError(`Unknown tag ${tag}`);
+    }
+  };
+
+  return decode(JSON.parse(data));
+};
@@ -0,0 +1,212 @@
+import { resolve } from '@std/path';
+import {
+  ErrorEvent,
+  EventTarget,
+  MessageEvent,
+} from 'module:web/events.js';
+import { deserialize, serialize } from './clone';
+
+const $create = $rustFunction('workerCreate');
+const $postMessage = $rustFunction('workerPostMessage');
+const $terminate = $rustFunction('workerTerminate');
+const $onMessage = $rustFunction('workerOnMessage');
+const $postToParent = $rustFunction('workerPostToParent');
+const $close = $rustFunction('workerClose');
+
+export { DataCloneError } from './clone';
+
+type Handler = ((event: any) => void) | null;
+
+export interface WorkerOptions {
+  /** Only module workers are supported. */
+  type?: 'module';
+  /** Name of the worker, available as `name` in its global scope. */
+  name?: string;
+}
+
+export interface StructuredSerializeOptions {
+  transfer?: ArrayBuffer[];
+}
+
+const transferList = (
+  options: ArrayBuffer[] | StructuredSerializeOptions | undefined,
+): ArrayBuffer[] => {
+  if (Array.isArray(options)) {
+    return options;
+  }
+
+  return options?.transfer ?? [];
+};
+
+const toPath = (specifier: string | URL): string => {
+  const url = String(specifier);
+  if (!url.startsWith('file:')) {
+    return resolve(Ike.cwd(), url);
+  }
+
+  const { protocol, pathname } = new URL(url);
+  if (protocol !== 'file:') {
+    throw new TypeError(`Workers can only be loaded from files, got ${url}`);
+  }
+
+  // `file:///C:/dir` is `C:/dir` on Windows
+  const path = decodeURIComponent(pathname);
+  return Ike.isWindows() ? path.replace(/^\/([a-zA-Z]:)/, '$1') : path;
+};
+
+// `onmessage` and the like replace the listener they added before
+const defineHandler = (target: object, type: string) => {
+  let handler: Handler = null;
+  const listener = (event: Event) => handler?.call(target, event);
+
+  Object.defineProperty(target, `on${type}`, {
+    get: () => handler,
+    set: (value: Handler) => {
+      handler = typeof value === 'function' ? value : null;
+      if (handler === null) {
+        (target as EventTarget).removeEventListener(type, listener);
+      } else {
+        (target as EventTarget).addEventListener(type, listener);
+      }
+    },
+    enumerable: true,
+    configurable: true,
+  });
+};
+
+// Messages that fail to deserialize are dispatched as `messageerror`
+const dispatchMessage = (
+  target: EventTarget,
+  data: string,
+  buffers: ArrayBuffer[],
+) => {
+  let message: unknown;
+  try {
+    message = deserialize(data, buffers);
+  } catch {
+    target.dispatchEvent(new MessageEvent('messageerror'));
+    return;
+  }
+
+  target.dispatchEvent(new MessageEvent('message', { data: message }));
+};
+
+/**
+ * A module running on its own thread with its own global scope. Values are
+ * passed back and forth with `postMessage` as structured clones.
+ */
+export class Worker extends EventTarget {
+  declare onmessage: Handler;
+  declare onmessageerror: Handler;
+  declare onerror: Handler;
+
+  #id: number | null;
+
+  constructor(specifier: string | URL, options: WorkerOptions = {}) {
+    super();
+
+    if ((options.type as string) === 'classic') {
+      throw new TypeError('Classic workers are not supported, use a module');
+    }
+
+    defineHandler(this, 'message');
+    defineHandler(this, 'messageerror');
+    defineHandler(this, 'error');
+
+    this.#id = $create(
+      toPath(specifier),
+      String(options.name ?? ''),
+      (type: string, ...data: any[]) => this.#receive(type, data),
+    );
+  }
+
+  #receive(type: string, data: any[]): boolean {
+    switch (type) {
+      case 'message':
+        dispatchMessage(this, data[0], data[1]);
+        break;
+      case 'error': {
+        const [message, filename] = data;
+        const event = new ErrorEvent('error', {
+          message,
+          filename,
+          cancelable: true,
+        });
+
+        // Not prevented, the runtime reports it as uncaught
+        return this.dispatchEvent(event);
+      }
+      case 'exit':
+        this.#id = null;
+        break;
+    }
+
+    return false;
+  }
+
+  /**
+   * Sends a structured clone of `message` to the worker. The array buffers of
+   * `transfer` are moved to it, they are detached here afterwards.
+   */
+  postMessage(
+    message: unknown,
+    transfer?: ArrayBuffer[] | StructuredSerializeOptions,
+  ): void {
+    const [data, buffers] = serialize(message, transferList(transfer));
+
+    if (this.#id !== null) {
+      $postMessage(this.#id, data, buffers);
+    }
+  }
+
+  /**
+   * Stops the worker once it is done with what it is running right now.
+   */
+  terminate(): void {
+    if (this.#id !== null) {
+      $terminate(this.#id);
+      this.#id = null;
+    }
+  }
+
+  get [Symbol.toStringTag]() {
+    return 'Worker';
+  }
+}
+
+/**
+ * Sets up the global scope of a worker, the runtime calls it before the module
+ * of the worker runs.
+ */
+export const initWorkerScope = (name: string) => {
+  const scope = globalThis as any;
+
+  defineHandler(scope, 'message');
+  defineHandler(scope, 'messageerror');
+  defineHandler(scope, 'error');
+
+  Object.defineProperties(scope, {
+    self: { value: scope, writable: true, configurable: true },
+    name: { value: name, writable: true, configurable: true },
+    postMessage: {
+      value: (
+        message: unknown,
+        transfer?: ArrayBuffer[] | StructuredSerializeOptions,
+      ) => {
+        const [data, buffers] = serialize(message, transferList(transfer));
+        $postToParent(data, buffers);
+      },
+      writable: true,
+      configurable: true,
+    },
+    close: {
+      value: () => $close(),
+      writable: true,
+      configurable: true,
+    },
+  });
+
+  $onMessage((data: string, buffers: ArrayBuffer[]) =>
+    dispatchMessage(scope, data, buffers),
+  );
+};
@@ -0,0 +1,29 @@
+const $parse = $rustFunction('dotenvParse');
+
+export interface LoadOptions {
+  /** Path of the file, `.env` by default. */
+  path?: string;
+  /** Replace variables that are already set. */
+  override?: boolean;
+}
+
+export const parse = (source: string): Record<string, string> => {
+  if (typeof source !== 'string') {
+    throw new TypeError('Source must be a string');
+  }
+
+  return $parse(source);
+};
+
+export const load = (options: LoadOptions = {}): Record<string, string> => {
+  const path = options.path ?? '.env';
+  const vars = parse(Ike.readTextFileSync(path));
+
+  for (const [key, value] of Object.entries(vars)) {
+    if (options.override || Ike.env[key] === undefined) {
+      Ike.env[key] = value;
+    }
+  }
+
+  return vars;
+};
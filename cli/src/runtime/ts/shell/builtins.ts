@@ -0,0 +1,205 @@
+import { resolve } from '@std/path';
+
+const $readDir = $rustFunction('shellReadDir');
+const $stat = $rustFunction('shellStat');
+
+export interface ShellState {
+  cwd: string;
+  env: Record<string, string>;
+}
+
+export interface BuiltinContext {
+  state: ShellState;
+  /** Output of the previous command of a pipeline. */
+  stdin: ReadableStream<Uint8Array> | null;
+}
+
+export interface BuiltinOutput {
+  stdout: string | Uint8Array | ReadableStream<Uint8Array>;
+}
+
+type Builtin = (args: string[], ctx: BuiltinContext) => BuiltinOutput;
+
+/**
+ * Splits `-rf` style flags from the operands, `--` ends the flags.
+ */
+const flags = (args: string[], allowed: string) => {
+  const set = new Set<string>();
+  const operands: string[] = [];
+  let done = false;
+
+  for (const arg of args) {
+    if (done || !arg.startsWith('-') || arg === '-') {
+      operands.push(arg);
+    } else if (arg === '--') {
+      done = true;
+    } else {
+      for (const flag of arg.slice(1)) {
+        if (!allowed.includes(flag)) {
+          throw new Error(`invalid option -- '${flag}'`);
+        }
+        set.add(flag);
+      }
+    }
+  }
+
+  return { set, operands };
+};
+
+const home = (ctx: BuiltinContext) => {
+  return (
+    ctx.state.env.HOME ?? Ike.env.HOME ?? Ike.env.USERPROFILE ?? ctx.state.cwd
+  );
+};
+
+const echo: Builtin = (args) => {
+  // Only a leading `-n` is an option, everything else is printed as it is
+  const newline = args[0] !== '-n';
+  const words = newline ? args : args.slice(1);
+
+  return { stdout: words.join(' ') + (newline ? '\n' : '') };
+};
+
+const cd: Builtin = (args, ctx) => {
+  if (args.length > 1) {
+    throw new Error('too many arguments');
+  }
+
+  const dir = resolve(ctx.state.cwd, args[0] ?? home(ctx));
+  const stat = $stat(dir);
+  if (stat === null) {
+    throw new Error(`${args[0]}: No such file or directory`);
+  }
+  if (!stat.isDir) {
+    throw new Error(`${args[0]}: Not a directory`);
+  }
+
+  ctx.state.cwd = dir;
+  return { stdout: '' };
+};
+
+const ls: Builtin = (args, ctx) => {
+  const { set, operands } = flags(args, 'a1');
+  const paths = operands.length > 0 ? operands : ['.'];
+  const files: string[] = [];
+  const dirs: string[] = [];
+
+  for (const path of paths) {
+    const stat = $stat(resolve(ctx.state.cwd, path));
+
+    if (stat === null) {
+      throw new Error(`cannot access '${path}': No such file or directory`);
+    }
+    (stat.isDir ? dirs : files).push(path);
+  }
+
+  const sections = files.length > 0 ? [files.join('\n')] : [];
+  for (const dir of dirs) {
+    const names: string[] = $readDir(resolve(ctx.state.cwd, dir)).filter(
+      (name: string) => set.has('a') || !name.startsWith('.'),
+    );
+    const listing = names.join('\n');
+
+    sections.push(paths.length > 1 ? `${dir}:\n${listing}` : listing);
+  }
+
+  const stdout = sections.filter((section) => section !== '').join('\n\n');
+  return { stdout: stdout === '' ? '' : `${stdout}\n` };
+};
+
+const rm: Builtin = (args, ctx) => {
+  const { set, operands } = flags(args, 'rRf');
+  const recursive = set.has('r') || set.has('R');
+
+  if (operands.length === 0 && !set.has('f')) {
+    throw new Error('missing operand');
+  }
+
+  for (const path of operands) {
+    const absolute = resolve(ctx.state.cwd, path);
+    const stat = $stat(absolute);
+
+    if (stat === null) {
+      if (set.has('f')) {
+        continue;
+      }
+      throw new Error(`cannot remove '${path}': No such file or directory`);
+    }
+    if (stat.isDir && !recursive) {
+      throw new Error(`cannot remove '${path}': Is a directory`);
+    }
+
+    Ike.removeSync(absolute, { recursive });
+  }
+
+  return { stdout: '' };
+};
+
+const mkdir: Builtin = (args, ctx) => {
+  const { set, operands } = flags(args, 'p');
+
+  if (operands.length === 0) {
+    throw new Error('missing operand');
+  }
+
+  for (const path of operands) {
+    const absolute = resolve(ctx.state.cwd, path);
+
+    if ($stat(absolute) !== null) {
+      if (set.has('p')) {
+        continue;
+      }
+      throw new Error(`cannot create directory '${path}': File exists`);
+    }
+
+    Ike.createDirSync(absolute, { recursive: set.has('p') });
+  }
+
+  return { stdout: '' };
+};
+
+const cat: Builtin = (args, ctx) => {
+  const { operands } = flags(args, '');
+
+  // Without files the input is passed through
+  if (operands.length === 0) {
+    return { stdout: ctx.stdin ?? '' };
+  }
+
+  const chunks = operands.map((path) => {
+    if (path === '-') {
+      throw new Error('reading stdin with `-` is not supported');
+    }
+
+    return Ike.readFileSync(resolve(ctx.state.cwd, path));
+  });
+  const bytes = new Uint8Array(
+    chunks.reduce((length, chunk) => length + chunk.length, 0),
+  );
+
+  let offset = 0;
+  for (const chunk of chunks) {
+    bytes.set(chunk, offset);
+    offset += chunk.length;
+  }
+
+  return { stdout: bytes };
+};
+
+const pwd: Builtin = (_, ctx) => {
+  return { stdout: `${ctx.state.cwd}\n` };
+};
+
+/**
+ * Commands that behave the same on every platform. They take precedence over
+ * executables with the same name.
+ */
+export const BUILTINS: Record<string, Builtin> = {
+  cat,
+  cd,
+  echo,
+  ls,
+  mkdir,
+  pwd,
+  rm,
+};
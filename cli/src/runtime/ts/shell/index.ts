@@ -0,0 +1,547 @@
+import { resolve } from '@std/path';
+import { BUILTINS, type ShellState } from './builtins';
+import {
+  type Command,
+  type Part,
+  type Pipeline,
+  type Word,
+  escape,
+  parse,
+} from './parse';
+
+export { escape } from './parse';
+
+const $glob = $rustFunction('shellGlob');
+const $writeFile = $rustFunction('shellWriteFile');
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+export interface ShellOptions {
+  /** Directory commands run in, the current working directory by default. */
+  cwd?: string;
+  /** Variables added to the environment of the commands. */
+  env?: Record<string, string>;
+  /** Throw a `ShellError` when the script exits with a non-zero code. */
+  throws?: boolean;
+}
+
+/**
+ * Output of a script, `stdout` and `stderr` hold what wasn't redirected.
+ */
+export class ShellOutput {
+  constructor(
+    readonly exitCode: number,
+    readonly stdout: Uint8Array,
+    readonly stderr: Uint8Array,
+  ) {}
+
+  text(): string {
+    return decoder.decode(this.stdout);
+  }
+
+  json<T = any>(): T {
+    return JSON.parse(this.text());
+  }
+
+  lines(): string[] {
+    const lines = this.text().split(/\r?\n/);
+
+    if (lines[lines.length - 1] === '') {
+      lines.pop();
+    }
+    return lines;
+  }
+
+  toString(): string {
+    return this.text();
+  }
+}
+
+export class ShellError extends Error {
+  constructor(readonly output: ShellOutput) {
+    const stderr = decoder.decode(output.stderr).trim();
+
+    const details = stderr === '' ? '' : `: ${stderr}`;
+
+    super(`Command failed with exit code ${output.exitCode}${details}`);
+    this.name = 'ShellError';
+  }
+
+  get exitCode(): number {
+    return this.output.exitCode;
+  }
+
+  get stdout(): Uint8Array {
+    return this.output.stdout;
+  }
+
+  get stderr(): Uint8Array {
+    return this.output.stderr;
+  }
+}
+
+type Stream = ReadableStream<Uint8Array>;
+
+const fromBytes = (bytes: string | Uint8Array): Stream => {
+  const chunk = typeof bytes === 'string' ? encoder.encode(bytes) : bytes;
+
+  return new ReadableStream({
+    start(controller) {
+      if (chunk.length > 0) {
+        controller.enqueue(chunk);
+      }
+      controller.close();
+    },
+  });
+};
+
+const readAll = async (stream: Stream): Promise<Uint8Array> => {
+  const chunks: Uint8Array[] = [];
+  const reader = stream.getReader();
+
+  while (true) {
+    const { done, value } = await reader.read();
+    if (done) {
+      break;
+    }
+    chunks.push(value);
+  }
+
+  return concat(chunks);
+};
+
+const concat = (chunks: Uint8Array[]): Uint8Array => {
+  const bytes = new Uint8Array(
+    chunks.reduce((length, chunk) => length + chunk.length, 0),
+  );
+
+  let offset = 0;
+  for (const chunk of chunks) {
+    bytes.set(chunk, offset);
+    offset += chunk.length;
+  }
+  return bytes;
+};
+
+/**
+ * Interleaves the chunks of several streams in the order they arrive.
+ */
+const merge = (streams: Stream[]): Stream => {
+  if (streams.length === 1) {
+    return streams[0];
+  }
+
+  const readers = streams.map((stream) => stream.getReader());
+
+  return new ReadableStream({
+    async start(controller) {
+      await Promise.all(
+        readers.map(async (reader) => {
+          while (true) {
+            const { done, value } = await reader.read();
+            if (done) {
+              break;
+            }
+            controller.enqueue(value);
+          }
+        }),
+      );
+      controller.close();
+    },
+    async cancel() {
+      await Promise.all(readers.map((reader) => reader.cancel()));
+    },
+  });
+};
+
+const writeAll = async (
+  stream: Stream,
+  writable: WritableStream<Uint8Array>,
+) => {
+  const reader = stream.getReader();
+  const writer = writable.getWriter();
+
+  try {
+    while (true) {
+      const { done, value } = await reader.read();
+      if (done) {
+        break;
+      }
+      await writer.write(value);
+    }
+    await writer.close();
+  } catch {
+    // The command exited without reading all of its input
+    await reader.cancel();
+  }
+};
+
+const lookup = (name: string, state: ShellState): string => {
+  return state.env[name] ?? Ike.env[name] ?? '';
+};
+
+const escapeGlob = (text: string) => text.replace(/[*?[\]]/g, '[$&]');
+
+/**
+ * Expands variables and globs of a word, globs without matches are kept.
+ */
+const expand = (word: Word, state: ShellState, glob = true): string[] => {
+  let text = '';
+  let pattern = '';
+  let isGlob = false;
+
+  for (const segment of word) {
+    const value =
+      segment.type === 'var' ? lookup(segment.name, state) : segment.text;
+
+    text += value;
+    if (segment.type === 'text' && segment.glob && /[*?[]/.test(value)) {
+      isGlob = true;
+      pattern += value;
+    } else {
+      pattern += escapeGlob(value);
+    }
+  }
+
+  if (glob && isGlob) {
+    const matches: string[] = $glob(pattern, state.cwd);
+
+    if (matches.length > 0) {
+      return matches;
+    }
+  }
+  return [text];
+};
+
+const expandOne = (word: Word, state: ShellState): string => {
+  return expand(word, state, false)[0];
+};
+
+interface Process {
+  stdout: Stream;
+  stderr: Stream;
+  code: Promise<number>;
+}
+
+const failed = (name: string, message: string, code = 1): Process => {
+  return {
+    stdout: fromBytes(''),
+    stderr: fromBytes(`${name}: ${message}\n`),
+    code: Promise.resolve(code),
+  };
+};
+
+const start = (
+  command: Command,
+  stdin: Stream | null,
+  state: ShellState,
+): Process => {
+  const env = Object.fromEntries(
+    command.assignments.map(([name, value]) => [name, expandOne(value, state)]),
+  );
+  const [name, ...args] = command.words.flatMap((word) => expand(word, state));
+
+  // Assignments without a command are set for the rest of the script
+  if (name === undefined) {
+    Object.assign(state.env, env);
+    stdin?.cancel();
+    return {
+      stdout: fromBytes(''),
+      stderr: fromBytes(''),
+      code: Promise.resolve(0),
+    };
+  }
+
+  const builtin = BUILTINS[name];
+  if (builtin !== undefined) {
+    try {
+      const { stdout } = builtin(args, { state, stdin });
+
+      if (stdout !== stdin) {
+        stdin?.cancel();
+      }
+      return {
+        stdout: stdout instanceof ReadableStream ? stdout : fromBytes(stdout),
+        stderr: fromBytes(''),
+        code: Promise.resolve(0),
+      };
+    } catch (e) {
+      stdin?.cancel();
+      return failed(name, e instanceof Error ? e.message : String(e));
+    }
+  }
+
+  let child: ReturnType<typeof Ike.spawn>;
+  try {
+    child = Ike.spawn(name, args, {
+      cwd: state.cwd,
+      env: { ...state.env, ...env },
+      stdin: stdin === null ? 'null' : 'pipe',
+      stdout: 'pipe',
+      stderr: 'pipe',
+    });
+  } catch {
+    stdin?.cancel();
+    return failed('ike', `command not found: ${name}`, 127);
+  }
+
+  const writing =
+    stdin === null ? Promise.resolve() : writeAll(stdin, child.stdin!);
+  return {
+    stdout: child.stdout!,
+    stderr: child.stderr!,
+    code: Promise.all([writing, child.status]).then(
+      ([, status]) => status.code ?? 128,
+    ),
+  };
+};
+
+type Target = 'stdout' | 'stderr' | { path: string; append: boolean };
+
+/**
+ * Starts a command with its redirections. Like in other shells `> file 2>&1`
+ * sends both streams to the file while `2>&1 > file` only sends stdout there.
+ */
+const run = (
+  command: Command,
+  stdin: Stream | null,
+  state: ShellState,
+): Process => {
+  let out: Target = 'stdout';
+  let err: Target = 'stderr';
+
+  for (const { op, target } of command.redirects) {
+    if (op === '2>&1') {
+      err = out;
+    } else if (op === '1>&2') {
+      out = err;
+    } else if (op !== '<') {
+      const file: Target = {
+        path: resolve(state.cwd, expandOne(target!, state)),
+        append: op.endsWith('>>'),
+      };
+
+      if (op.startsWith('2')) {
+        err = file;
+      } else if (op.startsWith('&')) {
+        out = err = file;
+      } else {
+        out = file;
+      }
+    }
+  }
+
+  const input = command.redirects.findLast(({ op }) => op === '<');
+  if (input !== undefined) {
+    stdin?.cancel();
+    try {
+      const path = resolve(state.cwd, expandOne(input.target!, state));
+      stdin = fromBytes(Ike.readFileSync(path));
+    } catch (e) {
+      return failed('ike', e instanceof Error ? e.message : String(e));
+    }
+  }
+
+  const proc = start(command, stdin, state);
+
+  const targets = new Map<Target, Stream[]>();
+  for (const [target, stream] of [
+    [out, proc.stdout],
+    [err, proc.stderr],
+  ] as const) {
+    targets.set(target, [...(targets.get(target) ?? []), stream]);
+  }
+
+  const writes: Promise<void>[] = [];
+  for (const [target, streams] of targets) {
+    if (typeof target !== 'string') {
+      writes.push(
+        readAll(merge(streams)).then((bytes) =>
+          $writeFile(target.path, bytes, target.append),
+        ),
+      );
+    }
+  }
+
+  return {
+    stdout: merge(targets.get('stdout') ?? [fromBytes('')]),
+    stderr: merge(targets.get('stderr') ?? [fromBytes('')]),
+    code: Promise.all([proc.code, ...writes]).then(([code]) => code),
+  };
+};
+
+const runPipeline = async (pipeline: Pipeline, state: ShellState) => {
+  const processes: Process[] = [];
+  let stdin: Stream | null = null;
+
+  for (const command of pipeline.commands) {
+    const proc = run(command, stdin, state);
+
+    processes.push(proc);
+    stdin = proc.stdout;
+  }
+
+  const last = processes[processes.length - 1];
+  const [stdout, stderr, codes] = await Promise.all([
+    readAll(last.stdout),
+    Promise.all(processes.map((proc) => readAll(proc.stderr))),
+    Promise.all(processes.map((proc) => proc.code)),
+  ]);
+
+  // Like other shells without `pipefail`, the last command sets the exit code
+  return { code: codes[codes.length - 1], stdout, stderr: concat(stderr) };
+};
+
+const execute = async (
+  pipelines: Pipeline[],
+  options: ShellOptions,
+): Promise<ShellOutput> => {
+  const state: ShellState = {
+    cwd: resolve(options.cwd ?? Ike.cwd()),
+    env: { ...options.env },
+  };
+  const stdout: Uint8Array[] = [];
+  const stderr: Uint8Array[] = [];
+  let code = 0;
+
+  for (const pipeline of pipelines) {
+    if (
+      (pipeline.connector === '&&' && code !== 0) ||
+      (pipeline.connector === '||' && code === 0)
+    ) {
+      continue;
+    }
+
+    const result = await runPipeline(pipeline, state);
+    code = result.code;
+    stdout.push(result.stdout);
+    stderr.push(result.stderr);
+  }
+
+  const output = new ShellOutput(code, concat(stdout), concat(stderr));
+  if (code !== 0 && options.throws !== false) {
+    throw new ShellError(output);
+  }
+  return output;
+};
+
+/**
+ * A running script. It starts on its own once the current task is done and
+ * resolves to its `ShellOutput`.
+ */
+export class ShellPromise implements PromiseLike<ShellOutput> {
+  #pipelines: Pipeline[];
+  #options: ShellOptions;
+  #promise: Promise<ShellOutput> | null = null;
+
+  constructor(pipelines: Pipeline[], options: ShellOptions) {
+    this.#pipelines = pipelines;
+    this.#options = { ...options };
+
+    // Scripts that are never awaited still run, their errors are ignored then
+    Promise.resolve().then(() => this.#run().catch(() => {}));
+  }
+
+  #run(): Promise<ShellOutput> {
+    this.#promise ??= execute(this.#pipelines, this.#options);
+    return this.#promise;
+  }
+
+  #configure(options: ShellOptions): this {
+    if (this.#promise !== null) {
+      throw new Error('The command has already started');
+    }
+    Object.assign(this.#options, options);
+    return this;
+  }
+
+  cwd(dir: string): this {
+    return this.#configure({ cwd: dir });
+  }
+
+  env(vars: Record<string, string>): this {
+    return this.#configure({ env: { ...this.#options.env, ...vars } });
+  }
+
+  nothrow(): this {
+    return this.#configure({ throws: false });
+  }
+
+  throws(throws: boolean): this {
+    return this.#configure({ throws });
+  }
+
+  then<T = ShellOutput, E = never>(
+    onfulfilled?: ((output: ShellOutput) => T | PromiseLike<T>) | null,
+    onrejected?: ((reason: any) => E | PromiseLike<E>) | null,
+  ): Promise<T | E> {
+    return this.#run().then(onfulfilled, onrejected);
+  }
+
+  catch<E = never>(
+    onrejected?: ((reason: any) => E | PromiseLike<E>) | null,
+  ): Promise<ShellOutput | E> {
+    return this.#run().catch(onrejected);
+  }
+
+  finally(onfinally?: (() => void) | null): Promise<ShellOutput> {
+    return this.#run().finally(onfinally);
+  }
+
+  async text(): Promise<string> {
+    return (await this).text();
+  }
+
+  async json<T = any>(): Promise<T> {
+    return (await this).json();
+  }
+
+  async lines(): Promise<string[]> {
+    return (await this).lines();
+  }
+}
+
+export interface Shell {
+  (strings: TemplateStringsArray, ...values: unknown[]): ShellPromise;
+  /** A shell whose scripts run in `dir`. */
+  cwd(dir: string): Shell;
+  /** A shell whose scripts get `vars` in addition to the environment. */
+  env(vars: Record<string, string>): Shell;
+  /** A shell whose scripts don't throw on non-zero exit codes. */
+  nothrow(): Shell;
+  /** A shell whose scripts throw or not on non-zero exit codes. */
+  throws(throws: boolean): Shell;
+  escape: typeof escape;
+}
+
+const createShell = (defaults: ShellOptions): Shell => {
+  const shell = (strings: TemplateStringsArray, ...values: unknown[]) => {
+    const parts: Part[] = [];
+
+    strings.raw.forEach((text, index) => {
+      parts.push({ type: 'literal', text });
+      if (index < values.length) {
+        parts.push({ type: 'value', value: values[index] });
+      }
+    });
+
+    return new ShellPromise(parse(parts), defaults);
+  };
+
+  return Object.assign(shell, {
+    cwd: (dir: string) => createShell({ ...defaults, cwd: dir }),
+    env: (vars: Record<string, string>) =>
+      createShell({ ...defaults, env: { ...defaults.env, ...vars } }),
+    nothrow: () => createShell({ ...defaults, throws: false }),
+    throws: (throws: boolean) => createShell({ ...defaults, throws }),
+    escape,
+  });
+};
+
+/**
+ * Runs a shell script, interpolated values are never parsed by the shell.
+ */
+export const $: Shell = createShell({});
+
+export default $;
@@ -0,0 +1,355 @@
+/**
+ * Part of a word. Only unquoted text can be a glob, variables are expanded when
+ * the command runs so `cd` and assignments before them are seen.
+ */
+export type Segment =
+  | { type: 'text'; text: string; glob: boolean }
+  | { type: 'var'; name: string };
+
+export type Word = Segment[];
+
+export type RedirectOp =
+  | '>'
+  | '>>'
+  | '<'
+  | '2>'
+  | '2>>'
+  | '&>'
+  | '&>>'
+  | '2>&1'
+  | '1>&2';
+
+export interface Redirect {
+  op: RedirectOp;
+  /** File of the redirection, `null` for `2>&1` and `1>&2`. */
+  target: Word | null;
+}
+
+export interface Command {
+  assignments: [string, Word][];
+  words: Word[];
+  redirects: Redirect[];
+}
+
+export type Connector = ';' | '&&' | '||';
+
+export interface Pipeline {
+  /** How the pipeline depends on the exit code of the previous one. */
+  connector: Connector;
+  commands: Command[];
+}
+
+/**
+ * Text is parsed, values are always a single word, or several for arrays.
+ */
+export type Part =
+  | { type: 'literal'; text: string }
+  | { type: 'value'; value: unknown };
+
+type Token = { type: 'word'; word: Word } | { type: 'op'; op: string };
+
+// Longer operators first, `2>>` has to win over `2>`
+const OPERATORS = [
+  '2>&1',
+  '1>&2',
+  '>&2',
+  '&>>',
+  '2>>',
+  '&>',
+  '2>',
+  '>>',
+  '&&',
+  '||',
+  '>',
+  '<',
+  '|',
+  ';',
+  '\n',
+];
+const REDIRECTS = ['>', '>>', '<', '2>', '2>>', '&>', '&>>', '2>&1', '1>&2'];
+const NAME = /^[A-Za-z_][A-Za-z0-9_]*/;
+
+const valueWords = (value: unknown): string[] => {
+  if (Array.isArray(value)) {
+    return value.flatMap(valueWords);
+  }
+
+  return [String(value)];
+};
+
+const isRaw = (value: unknown): value is { raw: string } => {
+  return (
+    typeof value === 'object' &&
+    value !== null &&
+    typeof (value as any).raw === 'string'
+  );
+};
+
+const tokenize = (parts: Part[]): Token[] => {
+  const tokens: Token[] = [];
+  let word: Word | null = null;
+  let quote: '"' | "'" | null = null;
+  // Comments can span template parts, values in them are dropped
+  let comment = false;
+
+  const push = (segment: Segment) => {
+    word ??= [];
+    word.push(segment);
+  };
+  const text = (value: string, glob: boolean) => {
+    const last = word?.[word.length - 1];
+
+    if (last?.type === 'text' && last.glob === glob) {
+      last.text += value;
+    } else {
+      push({ type: 'text', text: value, glob });
+    }
+  };
+  const end = () => {
+    if (word !== null) {
+      tokens.push({ type: 'word', word });
+      word = null;
+    }
+  };
+
+  for (const part of parts) {
+    if (part.type === 'value' && comment) {
+      continue;
+    }
+    if (part.type === 'value' && !isRaw(part.value)) {
+      const values = valueWords(part.value);
+
+      // Arrays are one word per element, an empty one adds no word
+      values.forEach((value, index) => {
+        if (index > 0) {
+          end();
+        }
+        text(value, false);
+      });
+      continue;
+    }
+
+    const source =
+      part.type === 'value' ? (part.value as { raw: string }).raw : part.text;
+    let i = 0;
+
+    while (i < source.length) {
+      const char = source[i];
+
+      if (comment && char !== '\n') {
+        i++;
+        continue;
+      }
+      comment = false;
+
+      if (quote === "'") {
+        if (char === "'") {
+          quote = null;
+        } else {
+          text(char, false);
+        }
+        i++;
+        continue;
+      }
+
+      if (char === '\\') {
+        const next = source[i + 1];
+
+        if (next === undefined) {
+          text(char, false);
+        } else if (next === '\n') {
+          // Line continuation
+        } else if (quote === '"' && !'"\\$`'.includes(next)) {
+          text(char + next, false);
+        } else {
+          text(next, false);
+        }
+        i += 2;
+        continue;
+      }
+
+      if (char === '$') {
+        const rest = source.slice(i + 1);
+        const braced = rest.match(/^\{([A-Za-z_][A-Za-z0-9_]*)\}/);
+        const name = braced?.[1] ?? rest.match(NAME)?.[0];
+
+        if (name !== undefined) {
+          push({ type: 'var', name });
+          i += 1 + (braced?.[0] ?? name).length;
+          continue;
+        }
+      }
+
+      if (quote === '"') {
+        if (char === '"') {
+          quote = null;
+        } else {
+          text(char, false);
+        }
+        i++;
+        continue;
+      }
+
+      if (char === '"' || char === "'") {
+        quote = char;
+        // `""` is an empty word
+        push({ type: 'text', text: '', glob: false });
+        i++;
+        continue;
+      }
+
+      if (char === ' ' || char === '\t' || char === '\r') {
+        end();
+        i++;
+        continue;
+      }
+
+      if (char === '#' && word === null) {
+        comment = true;
+        i++;
+        continue;
+      }
+
+      // `2>` is only a redirection at the start of a word
+      const op = OPERATORS.find(
+        (op) =>
+          source.startsWith(op, i) && (word === null || !/^[12]/.test(op)),
+      );
+      if (op !== undefined) {
+        end();
+        tokens.push({ type: 'op', op: op === '>&2' ? '1>&2' : op });
+        i += op.length;
+        continue;
+      }
+
+      if (char === '&') {
+        throw new SyntaxError(
+          'Running commands in the background with `&` is not supported',
+        );
+      }
+      if (char === '(' || char === ')' || char === '`') {
+        throw new SyntaxError(
+          `Unexpected \`${char}\`, subshells are not supported`,
+        );
+      }
+
+      text(char, true);
+      i++;
+    }
+  }
+
+  if (quote !== null) {
+    throw new SyntaxError(`Unterminated ${quote} quote`);
+  }
+  end();
+
+  return tokens;
+};
+
+/**
+ * Parses a script into pipelines that run one after another. Supported are
+ * `|`, `&&`, `||`, `;`, redirections, quotes, `$VAR` and `NAME=value` before a
+ * command.
+ */
+export const parse = (parts: Part[]): Pipeline[] => {
+  const pipelines: Pipeline[] = [];
+  let connector: Connector = ';';
+  let commands: Command[] = [];
+  let command: Command = { assignments: [], words: [], redirects: [] };
+
+  const isEmpty = (command: Command) => {
+    return (
+      command.words.length === 0 &&
+      command.assignments.length === 0 &&
+      command.redirects.length === 0
+    );
+  };
+  const endCommand = (op: string) => {
+    if (isEmpty(command)) {
+      throw new SyntaxError(`Unexpected \`${op}\``);
+    }
+    commands.push(command);
+    command = { assignments: [], words: [], redirects: [] };
+  };
+
+  const tokens = tokenize(parts);
+
+  for (let i = 0; i < tokens.length; i++) {
+    const token = tokens[i];
+
+    if (token.type === 'word') {
+      const first = token.word[0];
+
+      if (first?.type === 'text' && first.glob && command.words.length === 0) {
+        const assignment = first.text.match(/^([A-Za-z_][A-Za-z0-9_]*)=/);
+
+        if (assignment !== null) {
+          const value: Word = [
+            { ...first, text: first.text.slice(assignment[0].length) },
+            ...token.word.slice(1),
+          ];
+          command.assignments.push([assignment[1], value]);
+          continue;
+        }
+      }
+
+      command.words.push(token.word);
+      continue;
+    }
+
+    const op = token.op;
+
+    if (REDIRECTS.includes(op)) {
+      if (op === '2>&1' || op === '1>&2') {
+        command.redirects.push({ op, target: null });
+        continue;
+      }
+
+      const target = tokens[i + 1];
+      if (target?.type !== 'word') {
+        throw new SyntaxError(`Expected a file after \`${op}\``);
+      }
+      command.redirects.push({ op: op as RedirectOp, target: target.word });
+      i++;
+      continue;
+    }
+
+    if (op === '|') {
+      endCommand(op);
+      continue;
+    }
+
+    // Empty lines and a `;` without a command before it are skipped
+    const separator = op === ';' || op === '\n';
+    if (separator && isEmpty(command) && commands.length === 0) {
+      continue;
+    }
+
+    endCommand(op === '\n' ? ';' : op);
+    pipelines.push({ connector, commands });
+    connector = op === '\n' ? ';' : (op as Connector);
+    commands = [];
+  }
+
+  if (!isEmpty(command)) {
+    commands.push(command);
+  } else if (commands.length > 0 || connector !== ';') {
+    throw new SyntaxError('Unexpected end of script');
+  }
+  if (commands.length > 0) {
+    pipelines.push({ connector, commands });
+  }
+
+  return pipelines;
+};
+
+/**
+ * Quotes a string so it's parsed as a single word.
+ */
+export const escape = (value: string): string => {
+  if (value !== '' && /^[A-Za-z0-9_\-+=.,:/@%]+$/.test(value)) {
+    return value;
+  }
+
+  return `'${value.replaceAll("'", `'\\''`)}'`;
+};
@@ -5,15 +5,25 @@ const $it = $rustFunction('it');
 const beforeAll = $rustFunction('beforeAll');
 const afterAll = $rustFunction('afterAll');
 
-const it = (test: string, fn: () => void) => {
+type TestFn = () => void | Promise<void>;
+
+const it = (test: string, fn: TestFn) => {
   return $it(test, () => {
     return $try_it(fn);
   });
 };
 
-const $try_it = (fn: () => void) => {
+// async tests resolve to their result, the runner waits for it
+const $try_it = (fn: TestFn) => {
   try {
-    fn();
+    const result = fn();
+
+    if (result instanceof Promise) {
+      return result.then(
+        () => ({ status: 'pass' }),
+        (e) => ({ status: 'fail', error: e }),
+      );
+    }
 
     return {
       status: 'pass',
@@ -26,7 +36,7 @@ const $try_it = (fn: () => void) => {
   }
 };
 
-it.skip = (test: string, fn: () => void) => {
+it.skip = (test: string, fn: TestFn) => {
   return $it(test, () => {
     return {
       status: 'skip',
@@ -34,7 +44,7 @@ it.skip = (test: string, fn: () => void) => {
   });
 };
 
-it.todo = (test: string, fn: () => void) => {
+it.todo = (test: string, fn: TestFn) => {
   return $it(test, () => {
     return {
       status: 'todo',
@@ -42,7 +52,7 @@ it.todo = (test: string, fn: () => void) => {
   });
 };
 
-it.if = (condition: boolean, test: string, fn: () => void) => {
+it.if = (condition: boolean, test: string, fn: TestFn) => {
   if (condition) {
     return it(test, fn);
   } else {
@@ -0,0 +1,479 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    path::PathBuf,
+    rc::Rc,
+    thread,
+};
+
+use boa_engine::{
+    job::NativeJob,
+    js_str, js_string,
+    object::builtins::{JsArray, JsArrayBuffer, JsFunction},
+    Context, JsError, JsNativeError, JsObject, JsResult, JsValue, Module, NativeFunction, Source,
+};
+use futures_util::future::{AbortHandle, AbortRegistration, Abortable};
+use ike_core::{
+    permissions::{self, Permissions},
+    runtime::{Runtime, RuntimeModuleLoader},
+    str_from_jsvalue, throw,
+};
+use smol::channel::{unbounded, Receiver, Sender};
+
+use super::{
+    ike::IkeGlobalObject,
+    lifecycle::{dispatch, new_event, report_uncaught, report_uncaught_error},
+    modules::IkeModuleLoader,
+    queue::Queue,
+    runtime::{build_runtime, current_dir},
+};
+use crate::assert_arg_type;
+
+/// A structured clone made by `@std/_worker_`, the JSON of the value and the bytes of the
+/// `ArrayBuffer`s it references.
+struct Message {
+    data: String,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Message {
+    fn from_js(data: &JsValue, buffers: &JsValue, ctx: &mut Context) -> JsResult<Self> {
+        assert_arg_type!(string, data);
+        let data = str_from_jsvalue!(data, ctx);
+
+        let buffers = match buffers.as_object() {
+            Some(buffers) => JsArray::from_object(buffers.clone())?,
+            None => throw!(typ, "Expected an array of ArrayBuffers"),
+        };
+
+        let mut bytes = Vec::new();
+        for index in 0..buffers.length(ctx)? {
+            let buffer = match buffers.get(index, ctx)?.as_object() {
+                Some(buffer) => JsArrayBuffer::from_object(buffer.clone())?,
+                None => throw!(typ, "Expected an ArrayBuffer"),
+            };
+
+            bytes.push(
+                buffer
+                    .data()
+                    .as_deref()
+                    .map(<[u8]>::to_vec)
+                    .unwrap_or_default(),
+            );
+        }
+
+        Ok(Self {
+            data,
+            buffers: bytes,
+        })
+    }
+
+    fn into_js(self, ctx: &mut Context) -> JsResult<[JsValue; 2]> {
+        let buffers = self
+            .buffers
+            .into_iter()
+            .map(|bytes| JsArrayBuffer::from_byte_block(bytes, ctx).map(JsValue::from))
+            .collect::<JsResult<Vec<_>>>()?;
+
+        Ok([
+            js_string!(self.data).into(),
+            JsArray::from_iter(buffers, ctx).into(),
+        ])
+    }
+}
+
+enum ToWorker {
+    Message(Message),
+    Terminate,
+}
+
+enum FromWorker {
+    Message(Message),
+    Error { message: String, filename: String },
+}
+
+/// What the thread of a worker starts it with.
+struct WorkerOptions {
+    path: PathBuf,
+    name: String,
+    features: Vec<String>,
+    /// Workers can't do more than the thread that starts them.
+    permissions: Permissions,
+}
+
+/// A worker started by this thread.
+struct WorkerHandle {
+    sender: Sender<ToWorker>,
+    abort: AbortHandle,
+}
+
+/// The worker running on this thread, if it is one.
+struct WorkerScope {
+    path: PathBuf,
+    parent: Sender<FromWorker>,
+    queue: Rc<Queue>,
+    on_message: Option<JsFunction>,
+}
+
+thread_local! {
+    static WORKERS: RefCell<HashMap<u32, WorkerHandle>> = RefCell::default();
+    static NEXT_ID: Cell<u32> = const { Cell::new(1) };
+    static SCOPE: RefCell<Option<WorkerScope>> = const { RefCell::new(None) };
+}
+
+pub fn is_worker() -> bool {
+    SCOPE.with(|scope| scope.borrow().is_some())
+}
+
+fn string_arg(args: &[JsValue], index: usize, ctx: &mut Context) -> JsResult<String> {
+    let value = match args.get(index) {
+        Some(value) => value,
+        None => throw!(typ, "Expected argument {} to be a string", index),
+    };
+    assert_arg_type!(string, value);
+
+    Ok(str_from_jsvalue!(value, ctx))
+}
+
+fn function_arg(args: &[JsValue], index: usize) -> JsResult<JsFunction> {
+    match args.get(index).and_then(JsValue::as_object).cloned() {
+        Some(function) if function.is_callable() => Ok(JsFunction::from_object(function).unwrap()),
+        _ => throw!(typ, "Expected argument {} to be a function", index),
+    }
+}
+
+/// Starts a worker running the module at `path` on its own thread. Everything the worker
+/// sends is passed to the callback as `(type, ...data)`, `exit` once it stopped. The
+/// worker keeps this thread alive until then. Returns the id of the worker.
+pub fn worker_create(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let options = WorkerOptions {
+        path: PathBuf::from(string_arg(args, 0, ctx)?),
+        name: string_arg(args, 1, ctx)?,
+        features: IkeGlobalObject::get_features(ctx)?,
+        permissions: permissions::permissions(ctx),
+    };
+    let callback = function_arg(args, 2)?;
+
+    let (sender, worker_receiver) = unbounded();
+    let (worker_sender, receiver) = unbounded();
+
+    let spawned = thread::Builder::new()
+        .name(format!("worker {}", options.name))
+        .spawn(move || run_worker(options, worker_receiver, worker_sender));
+    if let Err(e) = spawned {
+        throw!(err, "Failed to start the worker, {}", e);
+    }
+
+    let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+    let (abort, registration) = AbortHandle::new_pair();
+    WORKERS.with(|workers| {
+        workers
+            .borrow_mut()
+            .insert(id, WorkerHandle { sender, abort })
+    });
+    receive_from_worker(id, receiver, registration, callback, ctx);
+
+    Ok(JsValue::from(id))
+}
+
+fn receive_from_worker(
+    id: u32,
+    receiver: Receiver<FromWorker>,
+    registration: AbortRegistration,
+    callback: JsFunction,
+    ctx: &mut Context,
+) {
+    let future = async move {
+        let received = Abortable::new(receiver.recv(), registration).await;
+
+        NativeJob::new(move |context| -> JsResult<JsValue> {
+            let mut error = None;
+            let args = match received {
+                // Terminated, the worker doesn't get to send anything anymore
+                Err(_) => return Ok(JsValue::undefined()),
+                Ok(Ok(FromWorker::Message(message))) => {
+                    let [data, buffers] = message.into_js(context)?;
+                    vec![js_string!("message").into(), data, buffers]
+                }
+                Ok(Ok(FromWorker::Error { message, filename })) => {
+                    let args = vec![
+                        js_string!("error").into(),
+                        js_string!(message.as_str()).into(),
+                        js_string!(filename).into(),
+                    ];
+                    error = Some(message);
+                    args
+                }
+                // The thread of the worker ended and dropped its sender
+                Ok(Err(_)) => {
+                    WORKERS.with(|workers| workers.borrow_mut().remove(&id));
+                    let args = [js_string!("exit").into()];
+
+                    return callback.call(&JsValue::undefined(), &args, context);
+                }
+            };
+
+            let (abort, registration) = AbortHandle::new_pair();
+            let running = WORKERS.with(|workers| match workers.borrow_mut().get_mut(&id) {
+                Some(worker) => {
+                    worker.abort = abort;
+                    true
+                }
+                None => false,
+            });
+            if running {
+                receive_from_worker(id, receiver, registration, callback.clone(), context);
+            }
+
+            let dispatched = callback.call(&JsValue::undefined(), &args, context)?;
+            // No listener of the `Worker` prevented the error, it's uncaught here as well
+            if let (Some(message), true) = (error, dispatched.to_boolean()) {
+                report_uncaught_error(message, JsValue::null(), context);
+            }
+
+            Ok(JsValue::undefined())
+        })
+    };
+
+    ctx.job_queue().enqueue_future_job(Box::pin(future), ctx);
+}
+
+pub fn worker_post_message(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let id = args.first().cloned().unwrap_or_default().to_u32(ctx)?;
+    let message = Message::from_js(
+        &args.get(1).cloned().unwrap_or_default(),
+        &args.get(2).cloned().unwrap_or_default(),
+        ctx,
+    )?;
+
+    let sender = WORKERS.with(|workers| workers.borrow().get(&id).map(|w| w.sender.clone()));
+    // Messages to a worker that already stopped are dropped
+    if let Some(sender) = sender {
+        let _ = sender.try_send(ToWorker::Message(message));
+    }
+
+    Ok(JsValue::undefined())
+}
+
+/// Stops a worker as soon as it is done with what it is running right now.
+pub fn worker_terminate(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let id = args.first().cloned().unwrap_or_default().to_u32(ctx)?;
+
+    if let Some(worker) = WORKERS.with(|workers| workers.borrow_mut().remove(&id)) {
+        worker.abort.abort();
+        let _ = worker.sender.try_send(ToWorker::Terminate);
+    }
+
+    Ok(JsValue::undefined())
+}
+
+/// Sets the function the worker on this thread passes its messages to.
+pub fn worker_on_message(_: &JsValue, args: &[JsValue], _: &mut Context) -> JsResult<JsValue> {
+    let callback = function_arg(args, 0)?;
+
+    SCOPE.with(|scope| match scope.borrow_mut().as_mut() {
+        Some(scope) => {
+            scope.on_message = Some(callback);
+            Ok(JsValue::undefined())
+        }
+        None => throw!(typ, "Not running in a worker"),
+    })
+}
+
+pub fn worker_post_to_parent(
+    _: &JsValue,
+    args: &[JsValue],
+    ctx: &mut Context,
+) -> JsResult<JsValue> {
+    let message = Message::from_js(
+        &args.first().cloned().unwrap_or_default(),
+        &args.get(1).cloned().unwrap_or_default(),
+        ctx,
+    )?;
+
+    let parent = SCOPE.with(|scope| scope.borrow().as_ref().map(|s| s.parent.clone()));
+    match parent {
+        Some(parent) => {
+            let _ = parent.try_send(FromWorker::Message(message));
+        }
+        None => throw!(typ, "Not running in a worker"),
+    }
+
+    Ok(JsValue::undefined())
+}
+
+/// Stops the event loop of the worker on this thread, its thread ends afterwards.
+pub fn worker_close(_: &JsValue, _: &[JsValue], _: &mut Context) -> JsResult<JsValue> {
+    close();
+
+    Ok(JsValue::undefined())
+}
+
+fn close() {
+    let queue = SCOPE.with(|scope| scope.borrow().as_ref().map(|s| Rc::clone(&s.queue)));
+
+    if let Some(queue) = queue {
+        queue.stop();
+    }
+}
+
+/// Dispatches an uncaught error of the worker on this thread as an `ErrorEvent` on its
+/// global, and sends it to the `Worker` object if no listener prevents it.
+pub fn forward_error(message: String, error: JsValue, ctx: &mut Context) {
+    let (filename, parent) = match SCOPE.with(|scope| {
+        let scope = scope.borrow();
+        scope
+            .as_ref()
+            .map(|s| (s.path.display().to_string(), s.parent.clone()))
+    }) {
+        Some(scope) => scope,
+        None => return,
+    };
+
+    let prevented =
+        match error_event(&message, &filename, error, ctx).and_then(|event| dispatch(ctx, event)) {
+            Ok(dispatched) => !dispatched,
+            Err(_) => false,
+        };
+
+    if !prevented {
+        let _ = parent.try_send(FromWorker::Error { message, filename });
+    }
+}
+
+fn error_event(
+    message: &str,
+    filename: &str,
+    error: JsValue,
+    ctx: &mut Context,
+) -> JsResult<JsObject> {
+    let init = JsObject::with_object_proto(ctx.intrinsics());
+    init.set(js_str!("cancelable"), true, false, ctx)?;
+    init.set(js_str!("message"), js_string!(message), false, ctx)?;
+    init.set(js_str!("filename"), js_string!(filename), false, ctx)?;
+    init.set(js_str!("error"), error, false, ctx)?;
+
+    new_event(
+        ctx,
+        "ErrorEvent",
+        &[js_string!("error").into(), init.into()],
+    )
+}
+
+fn run_worker(options: WorkerOptions, receiver: Receiver<ToWorker>, parent: Sender<FromWorker>) {
+    let filename = options.path.display().to_string();
+
+    if let Err(e) = start_worker(options, receiver, parent.clone()) {
+        let _ = parent.try_send(FromWorker::Error {
+            message: e.to_string(),
+            filename,
+        });
+    }
+
+    // Dropping the last sender tells the `Worker` object that the worker stopped
+}
+
+fn start_worker(
+    options: WorkerOptions,
+    receiver: Receiver<ToWorker>,
+    parent: Sender<FromWorker>,
+) -> JsResult<()> {
+    let WorkerOptions {
+        path,
+        name,
+        features,
+        permissions,
+    } = options;
+
+    let queue = Rc::new(Queue::new());
+    let module_loader = Rc::new(IkeModuleLoader::new(current_dir()?)?);
+    module_loader.set_main(&path);
+    let mut runtime = build_runtime(&queue, &module_loader, Some(&path), &features, permissions)?;
+
+    SCOPE.with(|scope| {
+        scope.replace(Some(WorkerScope {
+            path: path.clone(),
+            parent,
+            queue: Rc::clone(&queue),
+            on_message: None,
+        }))
+    });
+
+    let result = evaluate_worker(&path, &name, &module_loader, receiver, &mut runtime);
+
+    // The callbacks of the scope must go before the context does
+    SCOPE.with(|scope| scope.take());
+
+    result
+}
+
+fn evaluate_worker(
+    path: &PathBuf,
+    name: &str,
+    module_loader: &IkeModuleLoader,
+    receiver: Receiver<ToWorker>,
+    runtime: &mut Runtime,
+) -> JsResult<()> {
+    let name = serde_json::to_string(name).unwrap();
+    let scope = format!(
+        "import {{ initWorkerScope }} from \"@std/_worker_\";\ninitWorkerScope({});",
+        name
+    );
+    let scope = Source::from_bytes(scope.as_bytes());
+    let scope = Module::parse(scope, None, runtime.context())?;
+    runtime.evaluate_module(scope)?;
+
+    let ctx = runtime.context();
+    let module = module_loader.load_entry(path, ctx)?;
+    let promise = module.load_link_evaluate(ctx);
+
+    // Messages can arrive while the module is still evaluating, errors of it are
+    // reported like uncaught ones right away
+    let report = NativeFunction::from_fn_ptr(|_, args, ctx| {
+        let error = args.first().cloned().unwrap_or_default();
+        report_uncaught(&JsError::from_opaque(error), ctx);
+
+        Ok(JsValue::undefined())
+    });
+    let report = report.to_js_function(ctx.realm());
+    let _ = promise.then(None, Some(report), ctx);
+
+    receive_from_parent(receiver, ctx);
+    runtime.run_event_loop();
+
+    Ok(())
+}
+
+fn receive_from_parent(receiver: Receiver<ToWorker>, ctx: &mut Context) {
+    let future = async move {
+        let received = receiver.recv().await;
+
+        NativeJob::new(move |context| -> JsResult<JsValue> {
+            let message = match received {
+                Ok(ToWorker::Message(message)) => message,
+                // Terminated, or the `Worker` object is gone with the thread that made it
+                Ok(ToWorker::Terminate) | Err(_) => {
+                    close();
+                    return Ok(JsValue::undefined());
+                }
+            };
+
+            receive_from_parent(receiver, context);
+
+            let on_message = SCOPE.with(|scope| {
+                scope
+                    .borrow()
+                    .as_ref()
+                    .and_then(|scope| scope.on_message.clone())
+            });
+            if let Some(on_message) = on_message {
+                let args = message.into_js(context)?;
+                on_message.call(&JsValue::undefined(), &args, context)?;
+            }
+
+            Ok(JsValue::undefined())
+        })
+    };
+
+    ctx.job_queue().enqueue_future_job(Box::pin(future), ctx);
+}
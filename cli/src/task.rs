@@ -0,0 +1,51 @@
+use anyhow::Result;
+use ike_logger::{log, Logger};
+use ike_toml::IkeToml;
+use std::{path::Path, process::Command};
+
+use crate::error::IkeError::{TaskFailed, TaskNotFound};
+
+/// Command that runs `script` with the system shell, `sh` or `cmd` on Windows.
+pub fn shell_command(script: &str) -> Command {
+    #[cfg(windows)]
+    {
+        let mut command = Command::new("cmd");
+        command.args(["/C", script]);
+        command
+    }
+    #[cfg(not(windows))]
+    {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+}
+
+/// Runs `task` from the `[tasks]` of every package in `pkgs` that defines it, in order.
+/// Stops at the first task that fails.
+pub fn run_tasks(pkgs: &[&IkeToml], task: &str) -> Result<()> {
+    let targets: Vec<(&IkeToml, &String)> = pkgs
+        .iter()
+        .filter_map(|pkg| Some((*pkg, pkg.toml.tasks.get(task)?)))
+        .collect();
+
+    if targets.is_empty() {
+        return Err(TaskNotFound(task.to_string()).into());
+    }
+
+    for (pkg, script) in targets {
+        let dir = pkg.dir().unwrap_or(Path::new("."));
+        let name = &pkg.toml.package.name;
+
+        log!(info, "<cyan>{}<r> <d>$ {}<r>", name, script);
+
+        let status = shell_command(script).current_dir(dir).status()?;
+        if !status.success() {
+            return Err(
+                TaskFailed(task.to_string(), name.clone(), status.code().unwrap_or(1)).into(),
+            );
+        }
+    }
+
+    Ok(())
+}
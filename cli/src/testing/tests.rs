@@ -1,18 +1,18 @@
 use std::{
     collections::HashMap,
-    path::{Path, PathBuf},
+    path::PathBuf,
     rc::Rc,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use boa_engine::{
     builtins::promise::PromiseState,
     js_string,
-    object::builtins::{JsArray, JsFunction},
-    Context, JsNativeError, JsResult, JsValue, Module, Source,
+    object::builtins::{JsArray, JsFunction, JsPromise},
+    Context, JsResult, JsValue,
 };
-use ike_core::{get_prototype_name, js_str_to_string, throw};
-use smol::LocalExecutor;
+use ike_core::{get_prototype_name, js_str_to_string, permissions::Permissions};
+use ike_toml::TestReporter;
 
 use ike_logger::{cond_log, log, new_line, print_indent, Logger};
 
@@ -20,13 +20,14 @@ use crate::{
     cli::run_command::Entry,
     format::format_time,
     runtime::{
+        ike::IkeGlobalObject,
         modules::IkeModuleLoader,
         queue::Queue,
-        runtime::{setup_context, update_meta_property},
+        rejections::UnhandledRejections,
+        runtime::{build_runtime, current_dir, print_module_error, update_meta_property},
     },
+    utils::compare_paths,
 };
-use crate::{runtime::runtime::evaulte_module, transpiler::transpile};
-use crate::{runtime::runtime::load_modules, utils::compare_paths};
 
 lazy_static::lazy_static! {
     static ref ICONS: HashMap<&'static str, &'static str> = {
@@ -37,6 +38,30 @@ lazy_static::lazy_static! {
         m.insert("todo", "<r><magenta>⚑<r>");
         m
     };
+    static ref DOTS: HashMap<&'static str, &'static str> = {
+        let mut m = HashMap::new();
+        m.insert("pass", "<r><green>.<r>");
+        m.insert("fail", "<r><red>F<r>");
+        m.insert("skip", "<r><yellow>s<r>");
+        m.insert("todo", "<r><magenta>t<r>");
+        m
+    };
+}
+
+/// How tests are run, from `[test]` of ike.toml and the flags of `ike test`.
+#[derive(Clone, Debug)]
+pub struct TestOptions {
+    pub features: Vec<String>,
+    /// Time a single test may take, async ones included.
+    pub timeout: Duration,
+    /// Files evaluated before each test file.
+    pub setup: Vec<PathBuf>,
+    pub reporter: TestReporter,
+    pub permissions: Permissions,
+}
+
+impl TestOptions {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(5000);
 }
 
 #[derive(Clone, Debug)]
@@ -47,6 +72,8 @@ pub struct TestResults {
     pub todo: u32,
     pub tests: u32,
     pub files: u32,
+    /// Failed tests as `(test, error name, message)`, the dot reporter prints them at the end.
+    pub failures: Vec<(String, String, String)>,
 }
 
 pub enum TestStatus {
@@ -65,6 +92,7 @@ impl TestResults {
             todo: 0,
             tests: 0,
             files: 0,
+            failures: Vec::new(),
         }
     }
 
@@ -73,72 +101,50 @@ impl TestResults {
     }
 }
 
-pub fn run_tests(paths: Vec<PathBuf>, root: PathBuf) -> JsResult<()> {
-    let queue = Rc::new(Queue::new(LocalExecutor::new()));
-    let module_loader = Rc::new(IkeModuleLoader::new(std::env::current_dir().unwrap())?);
-    let ctx = &mut Context::builder()
-        .job_queue(queue)
-        .module_loader(module_loader.clone())
-        .build()
-        .unwrap();
+pub fn run_tests(paths: Vec<PathBuf>, root: PathBuf, options: &TestOptions) -> JsResult<()> {
+    let queue = Rc::new(Queue::new());
+    // A rejection nothing handles is printed, the remaining tests still run
+    queue
+        .rejection_tracker()
+        .set_mode(UnhandledRejections::Warn);
+    let module_loader = Rc::new(IkeModuleLoader::new(current_dir()?)?);
 
-    load_modules(ctx, module_loader)?;
-    setup_context(ctx, None);
+    // we evaulte injected code before, so it doesn't get executed every file, about 2s saved
+    let mut runtime = build_runtime(
+        &queue,
+        &module_loader,
+        None,
+        &options.features,
+        options.permissions.clone(),
+    )?;
+    let pretty = options.reporter == TestReporter::Pretty;
     let start_time = Instant::now();
     let mut results = TestResults::new();
     let mut test_groups_by_file: HashMap<String, Vec<JsValue>> = HashMap::new();
     let mut alone_tests_by_file: HashMap<String, Vec<JsValue>> = HashMap::new();
 
-    // we evaulte injected code before, so it doesn't get executed every file, about 2s saved
-    let script_source = Source::from_bytes(include_bytes!("../runtime/runtime.js"));
-    let script_module = Module::parse(script_source, None, ctx)?;
-
-    evaulte_module(ctx, script_module)?;
-
     for path in paths {
         let entry = Entry::new(true, Some(path.clone()), None);
-        update_meta_property(ctx, &entry.path.clone().unwrap());
+        update_meta_property(runtime.context(), &entry.path.clone().unwrap());
         let path = entry.path.unwrap().as_path().to_path_buf();
+        module_loader.set_main(&path);
 
-        let transpiled = match transpile(&path) {
-            Ok(transpiler) => transpiler,
-            Err(e) => throw!(typ, "Failed to transpile: {:?}", e),
-        };
-        let reader = Source::from_bytes(transpiled.as_bytes()).with_path(&Path::new(&path));
-        let module = Module::parse(reader, None, ctx)?;
-        let promise = module.load_link_evaluate(ctx);
-
-        ctx.run_jobs();
-
-        match promise.state() {
-            PromiseState::Pending => panic!("module didn't execute!"),
-            PromiseState::Fulfilled(v) => {
-                assert_eq!(v, JsValue::undefined())
-            }
-            PromiseState::Rejected(err) => {
-                let obj = err.to_object(ctx).unwrap();
-                let proto = match obj.prototype() {
-                    Some(proto) => proto,
-                    None => {
-                        panic!("Error object has no prototype");
-                    }
-                };
-                let str_name = get_prototype_name!(proto, ctx);
-
-                let message = obj.get(js_string!("message"), ctx).unwrap();
-                cond_log!(
-                    true,
-                    true,
-                    "<r><red>error<r><d>({})<r>: {}",
-                    str_name,
-                    js_str_to_string!(message.to_string(ctx).unwrap())
-                );
+        for setup in &options.setup {
+            if let Err(e) = runtime.execute_module(setup) {
+                let ctx = runtime.context();
+                print_module_error(&e, ctx);
+                IkeGlobalObject::update_exit_code(ctx, 1);
             }
         }
 
+        if let Err(e) = runtime.execute_module(&path) {
+            print_module_error(&e, runtime.context());
+        }
+
         results.files += 1;
     }
 
+    let ctx = runtime.context();
     let test = ctx
         .global_object()
         .get(js_string!("IKE_INTERNAL_TEST"), ctx)
@@ -203,7 +209,9 @@ pub fn run_tests(paths: Vec<PathBuf>, root: PathBuf) -> JsResult<()> {
     for (path_str, tests) in tests_by_files {
         let path_buf = PathBuf::from(path_str.clone());
         let path = strip_prefix_from_path(root.clone(), path_buf.clone());
-        log!("{} <r><d>{}<r>", ICONS["skip"], path.display());
+        if pretty {
+            log!("{} <r><d>{}<r>", ICONS["skip"], path.display());
+        }
 
         if !global_before_all_val.is_undefined() {
             let global_before_all =
@@ -215,7 +223,8 @@ pub fn run_tests(paths: Vec<PathBuf>, root: PathBuf) -> JsResult<()> {
             if group_name.eq("alone") {
                 for test_val in test_group {
                     results.tests += 1;
-                    run_single_test(test_val, ctx, &mut results, 1);
+                    let scope = path.display().to_string();
+                    run_single_test(test_val, ctx, &queue, &mut results, options, &scope, 1);
                 }
             } else {
                 for test_val in test_group {
@@ -231,12 +240,11 @@ pub fn run_tests(paths: Vec<PathBuf>, root: PathBuf) -> JsResult<()> {
                         .get(js_string!("afterAll"), ctx)
                         .unwrap_or_else(|_| JsValue::undefined());
 
-                    print_indent!(1);
-                    log!(
-                        "{} <r><d>{}<r>",
-                        ICONS["skip"],
-                        name.to_string(ctx).unwrap().to_std_string_escaped()
-                    );
+                    let name = name.to_string(ctx).unwrap().to_std_string_escaped();
+                    if pretty {
+                        print_indent!(1);
+                        log!("{} <r><d>{}<r>", ICONS["skip"], name);
+                    }
 
                     if !before_all_val.is_undefined() {
                         let hooks_arr =
@@ -254,7 +262,8 @@ pub fn run_tests(paths: Vec<PathBuf>, root: PathBuf) -> JsResult<()> {
                     for j in 0..tests.length(ctx)? {
                         let single_test = tests.get(j, ctx)?;
                         results.tests += 1;
-                        run_single_test(single_test, ctx, &mut results, 2);
+                        let scope = format!("{} > {}", path.display(), name);
+                        run_single_test(single_test, ctx, &queue, &mut results, options, &scope, 2);
                     }
 
                     if !after_all_val.is_undefined() {
@@ -277,7 +286,29 @@ pub fn run_tests(paths: Vec<PathBuf>, root: PathBuf) -> JsResult<()> {
             run_after_all_hooks(&global_after_all_val, ctx, path_buf)?;
         }
 
+        if pretty {
+            new_line!();
+        }
+    }
+
+    if !pretty {
+        new_line!();
         new_line!();
+
+        for (test, name, message) in &results.failures {
+            log!("{} {}", ICONS["fail"], test);
+            cond_log!(
+                true,
+                true,
+                "  <r><red>└─ error<r><d>({})<r>: {}",
+                name,
+                message
+            );
+        }
+
+        if !results.failures.is_empty() {
+            new_line!();
+        }
     }
 
     let all_success = results.failed == 0;
@@ -373,9 +404,18 @@ pub fn strip_prefix_from_path(mut root: PathBuf, mut path: PathBuf) -> PathBuf {
     path.strip_prefix(&root).unwrap().to_path_buf()
 }
 
-pub fn run_single_test(test: JsValue, ctx: &mut Context, results: &mut TestResults, indent: usize) {
+pub fn run_single_test(
+    test: JsValue,
+    ctx: &mut Context,
+    queue: &Queue,
+    results: &mut TestResults,
+    options: &TestOptions,
+    scope: &str,
+    indent: usize,
+) {
     let test_obj = test.as_object().unwrap();
     let name = test_obj.get(js_string!("name"), ctx).unwrap();
+    let name = name.to_string(ctx).unwrap().to_std_string_escaped();
     let func_obj = test_obj.get(js_string!("func"), ctx).unwrap();
     let func = func_obj.as_object().unwrap();
 
@@ -384,18 +424,37 @@ pub fn run_single_test(test: JsValue, ctx: &mut Context, results: &mut TestResul
         .unwrap()
         .call(&JsValue::undefined(), &[], ctx)
         .unwrap();
-    let result_obj = result.as_object().unwrap();
-    let status = result_obj
-        .get(js_string!("status"), ctx)
-        .unwrap()
-        .to_string(ctx)
-        .unwrap()
-        .to_std_string_escaped();
-
+    let result = settle(result, queue, start, options.timeout, ctx);
     let duration = start.elapsed();
-    let formatted_time = format_time(duration, true);
 
-    print_indent!(indent);
+    let (mut status, mut error) = match result {
+        Ok(result) => {
+            let result_obj = result.as_object().unwrap();
+            let status = result_obj
+                .get(js_string!("status"), ctx)
+                .unwrap()
+                .to_string(ctx)
+                .unwrap()
+                .to_std_string_escaped();
+            let error = match status.as_str() {
+                "fail" => {
+                    let error = result_obj.get(js_string!("error"), ctx).unwrap();
+                    Some(describe_error(&error, ctx))
+                }
+                _ => None,
+            };
+
+            (status, error)
+        }
+        Err(error) => ("fail".to_string(), Some(error)),
+    };
+
+    // A test that blocks the thread can't be stopped, it fails once it returns
+    if status == "pass" && duration > options.timeout {
+        status = "fail".to_string();
+        error = Some(timed_out(options.timeout));
+    }
+
     match status.as_str() {
         "pass" => {
             results.pass += 1;
@@ -414,11 +473,23 @@ pub fn run_single_test(test: JsValue, ctx: &mut Context, results: &mut TestResul
         }
     }
 
+    if options.reporter == TestReporter::Dot {
+        log!(wt, "{}", DOTS.get(status.as_str()).unwrap());
+
+        if let Some((error_name, message)) = error {
+            let test = format!("{} > {}", scope, name);
+            results.failures.push((test, error_name, message));
+        }
+
+        return;
+    }
+
+    print_indent!(indent);
     log!(
         "{} {} {}{}",
         ICONS.get(status.as_str()).unwrap(),
-        name.to_string(ctx).unwrap().to_std_string_escaped(),
-        formatted_time,
+        name,
+        format_time(duration, true),
         if status.eq("skip") {
             " <d>(skipped)<r>"
         } else {
@@ -426,26 +497,72 @@ pub fn run_single_test(test: JsValue, ctx: &mut Context, results: &mut TestResul
         }
     );
 
-    if status.eq("fail") {
-        let error = result_obj.get(js_string!("error"), ctx).unwrap();
-        let error = error.as_object().unwrap();
-
-        let proto = match error.prototype() {
-            Some(proto) => proto,
-            None => {
-                panic!("No prototype found for test result object");
-            }
-        };
-        let str_name = get_prototype_name!(proto, ctx);
-
-        let message = error.get(js_string!("message"), ctx).unwrap();
+    if let Some((error_name, message)) = error {
         cond_log!(
             true,
             true,
             "{}<r><red>└─ error<r><d>({})<r>: {}",
             "  ".repeat(indent),
-            str_name,
-            js_str_to_string!(message.to_string(ctx).unwrap())
+            error_name,
+            message
         );
     }
 }
+
+/// Waits for the result of an async test while the queue runs, a test that didn't settle
+/// within `timeout` of its `start` times out.
+fn settle(
+    result: JsValue,
+    queue: &Queue,
+    start: Instant,
+    timeout: Duration,
+    ctx: &mut Context,
+) -> Result<JsValue, (String, String)> {
+    let promise = match result.as_object().cloned().map(JsPromise::from_object) {
+        Some(Ok(promise)) => promise,
+        _ => return Ok(result),
+    };
+
+    let in_time = queue.run_jobs_until(ctx, start + timeout, || {
+        !matches!(promise.state(), PromiseState::Pending)
+    });
+
+    match promise.state() {
+        PromiseState::Fulfilled(value) => Ok(value),
+        PromiseState::Rejected(error) => Err(describe_error(&error, ctx)),
+        PromiseState::Pending if !in_time => Err(timed_out(timeout)),
+        PromiseState::Pending => Err((
+            "TimeoutError".to_string(),
+            "Test never finished, its promise is still pending".to_string(),
+        )),
+    }
+}
+
+fn timed_out(timeout: Duration) -> (String, String) {
+    (
+        "TimeoutError".to_string(),
+        format!("Test timed out after {}ms", timeout.as_millis()),
+    )
+}
+
+/// Name and message of a thrown value, which doesn't have to be an error.
+fn describe_error(error: &JsValue, ctx: &mut Context) -> (String, String) {
+    let error = match error.as_object() {
+        Some(error) => error,
+        None => {
+            let message = error
+                .to_string(ctx)
+                .map(|message| js_str_to_string!(message))
+                .unwrap_or_default();
+            return ("Uncaught".to_string(), message);
+        }
+    };
+
+    let name = match error.prototype() {
+        Some(proto) => get_prototype_name!(proto, ctx),
+        None => "Object".to_string(),
+    };
+    let message = error.get(js_string!("message"), ctx).unwrap();
+
+    (name, js_str_to_string!(message.to_string(ctx).unwrap()))
+}
@@ -3,17 +3,25 @@ use ike_fs::read_to_string;
 use ike_fs::FsError::FailedToReadFileWithError;
 use oxc_allocator::Allocator;
 use oxc_codegen::CodeGenerator;
+use oxc_isolated_declarations::IsolatedDeclarations;
 use oxc_parser::Parser;
 use oxc_span::SourceType;
 use oxc_transformer::{
     ArrowFunctionsOptions, ES2015Options, ReactOptions, TransformOptions, Transformer,
     TypeScriptOptions,
 };
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::error::IkeError::{FailedToEmitDeclarations, UnsupportedSourceType};
+
+fn source_type(path: &Path) -> Result<SourceType> {
+    SourceType::from_path(path)
+        .map_err(|_| UnsupportedSourceType(path.display().to_string()).into())
+}
 
 pub fn transpile_with_text(path: &PathBuf, source_text: String) -> Result<String> {
     let allocator = Allocator::default();
-    let source_type = SourceType::from_path(path).unwrap();
+    let source_type = source_type(path)?;
 
     let ret = Parser::new(&allocator, &source_text, source_type).parse();
 
@@ -62,3 +70,34 @@ pub fn transpile(path: &PathBuf) -> Result<String> {
 
     transpile_with_text(path, source_text)
 }
+
+/// Emits `.d.ts` declarations for a TypeScript file. Only explicit types are used, like
+/// `--isolatedDeclarations` in tsc, so no type checking is involved.
+pub fn declarations(path: &PathBuf) -> Result<String> {
+    let source_text = match read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            return Err(FailedToReadFileWithError(e.to_string()).into());
+        }
+    };
+
+    let allocator = Allocator::default();
+    let source_type = source_type(path)?;
+    let ret = Parser::new(&allocator, &source_text, source_type).parse();
+
+    // Declarations of a file with errors would be incomplete, so they fail the pack
+    let declarations = IsolatedDeclarations::new(&allocator).build(&ret.program);
+    let errors: Vec<String> = ret
+        .errors
+        .into_iter()
+        .chain(declarations.errors)
+        .map(|error| format!("{:?}", error.with_source_code(source_text.clone())))
+        .collect();
+    if !errors.is_empty() {
+        return Err(FailedToEmitDeclarations(path.display().to_string(), errors.join("\n")).into());
+    }
+
+    Ok(CodeGenerator::new()
+        .build(&declarations.program)
+        .source_text)
+}
@@ -0,0 +1,75 @@
+use std::cell::Cell;
+
+thread_local! {
+    static UNREFS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Every future of the job queue keeps the event loop alive until it completes. A future
+/// that shouldn't, like a timer after `unref()`, owns a `LoopRef` and unrefs it.
+#[derive(Debug)]
+pub struct LoopRef {
+    is_ref: Cell<bool>,
+}
+
+impl LoopRef {
+    pub fn new() -> Self {
+        Self {
+            is_ref: Cell::new(true),
+        }
+    }
+
+    pub fn is_ref(&self) -> bool {
+        self.is_ref.get()
+    }
+
+    pub fn set_ref(&self, is_ref: bool) {
+        if self.is_ref.replace(is_ref) == is_ref {
+            return;
+        }
+
+        UNREFS.with(|unrefs| match is_ref {
+            true => unrefs.set(unrefs.get() - 1),
+            false => unrefs.set(unrefs.get() + 1),
+        });
+    }
+}
+
+impl Default for LoopRef {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for LoopRef {
+    fn drop(&mut self) {
+        self.set_ref(true);
+    }
+}
+
+/// How many of the pending futures don't keep the event loop alive.
+pub fn unref_count() -> usize {
+    UNREFS.with(Cell::get)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_unref_until_dropped() {
+        let first = LoopRef::new();
+        let second = LoopRef::new();
+        assert_eq!(unref_count(), 0);
+
+        first.set_ref(false);
+        first.set_ref(false);
+        second.set_ref(false);
+        assert_eq!(unref_count(), 2);
+
+        second.set_ref(true);
+        assert_eq!(unref_count(), 1);
+
+        drop(first);
+        assert_eq!(unref_count(), 0);
+    }
+}
@@ -1,9 +1,12 @@
 use exposed::ExposedFunction;
 
+pub mod event_loop;
 pub mod exposed;
 pub mod macros;
 pub mod module;
+pub mod permissions;
 pub mod promise;
+pub mod runtime;
 
 pub trait ModuleTrait {
     fn js_files(&self) -> &'static [(&'static str, &'static str)];
@@ -0,0 +1,363 @@
+use std::{
+    fmt,
+    io::{stderr, stdin, IsTerminal, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use boa_engine::{js_str, js_string, Context, JsData, JsError, JsNativeError, JsResult};
+use boa_gc::{Finalize, Trace};
+
+/// What a script can be allowed to access in a sandbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionKind {
+    Read,
+    Write,
+    Env,
+    Run,
+    /// Reserved for network APIs, nothing checks it yet so granting it has no effect.
+    Net,
+}
+
+impl PermissionKind {
+    pub const ALL: [Self; 5] = [Self::Read, Self::Write, Self::Env, Self::Run, Self::Net];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Env => "env",
+            Self::Run => "run",
+            Self::Net => "net",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    fn is_path(self) -> bool {
+        matches!(self, Self::Read | Self::Write)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionState {
+    Granted,
+    /// Not granted, but `Ike.permissions.request` can ask the user for it.
+    Prompt,
+    Denied,
+}
+
+impl PermissionState {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Granted => "granted",
+            Self::Prompt => "prompt",
+            Self::Denied => "denied",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+enum Grant {
+    #[default]
+    None,
+    All,
+    /// Paths for read and write, which grant everything below them as well, names of
+    /// variables, commands or hosts otherwise.
+    Some(Vec<String>),
+}
+
+/// Access to something a sandboxed script wasn't allowed to, thrown as a JS error named
+/// `PermissionDenied`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionDenied {
+    pub kind: PermissionKind,
+    pub target: Option<String>,
+}
+
+impl fmt::Display for PermissionDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = self.kind.name();
+
+        match &self.target {
+            Some(target) => write!(
+                f,
+                "Requires {kind} access to \"{target}\", run again with --allow-{kind}"
+            ),
+            None => write!(f, "Requires {kind} access, run again with --allow-{kind}"),
+        }
+    }
+}
+
+impl std::error::Error for PermissionDenied {}
+
+impl PermissionDenied {
+    pub fn into_js_error(self, ctx: &mut Context) -> JsError {
+        let error = JsNativeError::error()
+            .with_message(self.to_string())
+            .to_opaque(ctx);
+        let _ = error.set(js_str!("name"), js_string!("PermissionDenied"), false, ctx);
+
+        JsError::from_opaque(error.into())
+    }
+}
+
+/// What the scripts of a runtime are allowed to access. Outside of a sandbox, which is
+/// the default, everything is.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Trace, Finalize, JsData)]
+#[boa_gc(unsafe_empty_trace)]
+pub struct Permissions {
+    sandboxed: bool,
+    grants: [Grant; 5],
+}
+
+impl Permissions {
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Nothing is allowed until it is granted with `allow`.
+    pub fn sandboxed() -> Self {
+        Self {
+            sandboxed: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_sandboxed(&self) -> bool {
+        self.sandboxed
+    }
+
+    /// Grants `kind` for `targets`, or for everything without any. Relative paths are
+    /// resolved from `base`.
+    pub fn allow(&mut self, kind: PermissionKind, targets: Option<&[String]>, base: &Path) {
+        let grant = &mut self.grants[kind as usize];
+
+        let targets = match targets {
+            Some(targets) if !targets.is_empty() => targets,
+            _ => {
+                *grant = Grant::All;
+                return;
+            }
+        };
+
+        let targets = targets.iter().map(|target| match kind.is_path() {
+            true => resolve_path(base, Path::new(target)),
+            false => target.clone(),
+        });
+
+        match grant {
+            Grant::All => {}
+            Grant::Some(granted) => granted.extend(targets),
+            Grant::None => *grant = Grant::Some(targets.collect()),
+        }
+    }
+
+    /// Whether `kind` is granted for `target`, or for everything without one.
+    pub fn is_granted(&self, kind: PermissionKind, target: Option<&str>) -> bool {
+        if !self.sandboxed {
+            return true;
+        }
+
+        match (&self.grants[kind as usize], target) {
+            (Grant::All, _) => true,
+            (Grant::None, _) | (Grant::Some(_), None) => false,
+            (Grant::Some(granted), Some(target)) if kind.is_path() => {
+                let target = resolve_path(&current_dir(), Path::new(target));
+                granted
+                    .iter()
+                    .any(|path| Path::new(&target).starts_with(path))
+            }
+            (Grant::Some(granted), Some(target)) => granted.iter().any(|name| name == target),
+        }
+    }
+
+    pub fn query(&self, kind: PermissionKind, target: Option<&str>) -> PermissionState {
+        if self.is_granted(kind, target) {
+            PermissionState::Granted
+        } else if is_interactive() {
+            PermissionState::Prompt
+        } else {
+            PermissionState::Denied
+        }
+    }
+
+    pub fn check(
+        &self,
+        kind: PermissionKind,
+        target: Option<&str>,
+    ) -> Result<(), PermissionDenied> {
+        match self.is_granted(kind, target) {
+            true => Ok(()),
+            false => Err(PermissionDenied {
+                kind,
+                target: target.map(String::from),
+            }),
+        }
+    }
+}
+
+/// Sets the permissions of the scripts running in `ctx`. They are kept with its realm, so
+/// every runtime has its own.
+pub fn set_permissions(permissions: Permissions, ctx: &mut Context) {
+    ctx.realm().host_defined_mut().insert(permissions);
+}
+
+/// The permissions of `ctx`, workers start with the ones of their parent.
+pub fn permissions(ctx: &Context) -> Permissions {
+    ctx.realm()
+        .host_defined()
+        .get::<Permissions>()
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn with_permissions<R>(ctx: &Context, f: impl FnOnce(&Permissions) -> R) -> R {
+    match ctx.realm().host_defined().get::<Permissions>() {
+        Some(permissions) => f(permissions),
+        None => f(&Permissions::allow_all()),
+    }
+}
+
+/// Throws `PermissionDenied` unless `kind` is granted for `target`.
+pub fn check(kind: PermissionKind, target: Option<&str>, ctx: &mut Context) -> JsResult<()> {
+    with_permissions(ctx, |permissions| permissions.check(kind, target))
+        .map_err(|denied| denied.into_js_error(ctx))
+}
+
+pub fn check_path(kind: PermissionKind, path: &Path, ctx: &mut Context) -> JsResult<()> {
+    check(kind, Some(&path.to_string_lossy()), ctx)
+}
+
+pub fn is_granted(kind: PermissionKind, target: Option<&str>, ctx: &Context) -> bool {
+    with_permissions(ctx, |permissions| permissions.is_granted(kind, target))
+}
+
+pub fn query(kind: PermissionKind, target: Option<&str>, ctx: &Context) -> PermissionState {
+    with_permissions(ctx, |permissions| permissions.query(kind, target))
+}
+
+/// Asks on the terminal for a permission that isn't granted yet. What the user allows
+/// stays granted for the rest of the run.
+pub fn request(kind: PermissionKind, target: Option<&str>, ctx: &mut Context) -> PermissionState {
+    match query(kind, target, ctx) {
+        PermissionState::Prompt => {}
+        state => return state,
+    }
+
+    let what = match target {
+        Some(target) => format!("{} access to \"{}\"", kind.name(), target),
+        None => format!("{} access", kind.name()),
+    };
+    let mut stderr = stderr();
+    let _ = write!(stderr, "Allow {what}? [y/N] ");
+    let _ = stderr.flush();
+
+    let mut answer = String::new();
+    if stdin().read_line(&mut answer).is_err() || !answer.trim().eq_ignore_ascii_case("y") {
+        return PermissionState::Denied;
+    }
+
+    // Only a sandbox prompts, so its permissions were set
+    let targets = target.map(|target| vec![target.to_string()]);
+    if let Some(permissions) = ctx.realm().host_defined_mut().get_mut::<Permissions>() {
+        permissions.allow(kind, targets.as_deref(), &current_dir());
+    }
+
+    PermissionState::Granted
+}
+
+fn is_interactive() -> bool {
+    stdin().is_terminal() && stderr().is_terminal()
+}
+
+fn current_dir() -> PathBuf {
+    std::env::current_dir().unwrap_or_default()
+}
+
+/// Makes `path` absolute without `.` and `..`. Symlinks in the part of it that exists are
+/// resolved, so a link can't point out of a granted directory.
+fn resolve_path(base: &Path, path: &Path) -> String {
+    let mut resolved = PathBuf::new();
+    for component in base.join(path).components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            component => resolved.push(component),
+        }
+    }
+
+    // Files that don't exist yet, like ones about to be written, keep their name
+    let mut missing = Vec::new();
+    let mut existing = resolved.as_path();
+    let canonical = loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            break canonical;
+        }
+
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_owned());
+                existing = parent;
+            }
+            _ => return resolved.to_string_lossy().to_string(),
+        }
+    };
+
+    missing
+        .iter()
+        .rev()
+        .fold(canonical, |path, name| path.join(name))
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grants_everything_outside_of_a_sandbox() {
+        let permissions = Permissions::allow_all();
+
+        assert!(permissions.is_granted(PermissionKind::Read, Some("/etc/passwd")));
+        assert!(permissions.is_granted(PermissionKind::Run, None));
+    }
+
+    #[test]
+    fn grants_paths_below_an_allowed_directory() {
+        let mut permissions = Permissions::sandboxed();
+        let base = Path::new("/project");
+        permissions.allow(PermissionKind::Read, Some(&["data".to_string()]), base);
+
+        assert!(permissions.is_granted(PermissionKind::Read, Some("/project/data/a.txt")));
+        assert!(!permissions.is_granted(PermissionKind::Read, Some("/project/data2")));
+        assert!(!permissions.is_granted(PermissionKind::Read, Some("/project/data/../x")));
+        assert!(!permissions.is_granted(PermissionKind::Write, Some("/project/data")));
+        assert!(!permissions.is_granted(PermissionKind::Read, None));
+    }
+
+    #[test]
+    fn grants_names_exactly() {
+        let mut permissions = Permissions::sandboxed();
+        permissions.allow(
+            PermissionKind::Env,
+            Some(&["HOME".to_string()]),
+            Path::new("/"),
+        );
+        permissions.allow(PermissionKind::Run, None, Path::new("/"));
+
+        assert!(permissions.check(PermissionKind::Env, Some("HOME")).is_ok());
+        assert_eq!(
+            permissions.check(PermissionKind::Env, Some("HOMEPATH")),
+            Err(PermissionDenied {
+                kind: PermissionKind::Env,
+                target: Some("HOMEPATH".to_string()),
+            })
+        );
+        assert!(permissions.is_granted(PermissionKind::Run, Some("git")));
+    }
+}
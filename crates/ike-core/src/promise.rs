@@ -1,6 +1,8 @@
+use std::{fmt::Display, future::Future};
+
 use boa_engine::{
-    js_string, object::builtins::JsPromise, Context, JsArgs, JsError, JsNativeError, JsValue,
-    NativeFunction,
+    job::NativeJob, js_string, object::builtins::JsPromise, Context, JsArgs, JsError,
+    JsNativeError, JsResult, JsValue, NativeFunction,
 };
 
 use crate::{get_prototype_name, js_str_to_string};
@@ -46,3 +48,36 @@ pub fn base_promise(promise: JsPromise, ctx: &mut Context) -> JsPromise {
             ctx,
         )
 }
+
+/// Returns a promise that settles with `to_js` of the output of `future`, once the event
+/// loop completed it. An `Err` rejects the promise with an `Error` of its message.
+pub fn promise_from_future<T, E, F, C>(future: F, to_js: C, ctx: &mut Context) -> JsPromise
+where
+    T: 'static,
+    E: Display + 'static,
+    F: Future<Output = Result<T, E>> + 'static,
+    C: FnOnce(T, &mut Context) -> JsResult<JsValue> + 'static,
+{
+    let (promise, resolvers) = JsPromise::new_pending(ctx);
+
+    let job = async move {
+        let result = future.await;
+
+        NativeJob::new(move |ctx| {
+            let result = result
+                .map_err(|e| JsError::from(JsNativeError::error().with_message(e.to_string())))
+                .and_then(|value| to_js(value, ctx));
+
+            match result {
+                Ok(value) => resolvers.resolve.call(&JsValue::undefined(), &[value], ctx),
+                Err(e) => {
+                    let reason = e.to_opaque(ctx);
+                    resolvers.reject.call(&JsValue::undefined(), &[reason], ctx)
+                }
+            }
+        })
+    };
+    ctx.job_queue().enqueue_future_job(Box::pin(job), ctx);
+
+    promise
+}
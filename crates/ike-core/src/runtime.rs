@@ -0,0 +1,457 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use boa_engine::{
+    builtins::promise::PromiseState,
+    context::{ContextBuilder, HostHooks},
+    job::JobQueue,
+    js_string,
+    module::{ModuleLoader, Referrer},
+    object::builtins::JsPromise,
+    property::Attribute,
+    Context, JsError, JsNativeError, JsObject, JsResult, JsString, JsValue, Module, NativeFunction,
+    Source,
+};
+
+use crate::{
+    permissions::{self, Permissions},
+    ModuleTrait,
+};
+
+/// A module loader the JS files of `ModuleTrait` implementors are registered with.
+pub trait RuntimeModuleLoader: ModuleLoader {
+    /// Registers `module` under `specifier`, like `module:web/events.js`.
+    fn insert(&self, specifier: PathBuf, module: Module);
+
+    /// Parses the module at `path` for `Runtime::execute_module`.
+    fn load_entry(&self, path: &Path, ctx: &mut Context) -> JsResult<Module> {
+        parse_file(path, ctx)
+    }
+}
+
+/// The module loader of a runtime that wasn't given one. Registered modules are found by
+/// their specifier, everything else is a JavaScript file relative to the importing module.
+#[derive(Debug, Default)]
+pub struct FileModuleLoader {
+    root: PathBuf,
+    modules: RefCell<HashMap<PathBuf, Module>>,
+}
+
+impl FileModuleLoader {
+    /// Imports of code without a path, like `Runtime::eval`, are resolved from `root`.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self {
+            root: root.into(),
+            modules: RefCell::default(),
+        }
+    }
+
+    fn get(&self, path: &Path) -> Option<Module> {
+        self.modules.borrow().get(path).cloned()
+    }
+
+    fn load_file_module(&self, dir: &Path, spec: &str, ctx: &mut Context) -> JsResult<Module> {
+        let path = dir.join(spec).canonicalize().map_err(|e| {
+            JsNativeError::typ().with_message(format!("Could not find module `{spec}`, {e}"))
+        })?;
+
+        if let Some(module) = self.get(&path) {
+            return Ok(module);
+        }
+
+        let module = parse_file(&path, ctx)?;
+        self.insert(path, module.clone());
+
+        Ok(module)
+    }
+}
+
+impl RuntimeModuleLoader for FileModuleLoader {
+    fn insert(&self, specifier: PathBuf, module: Module) {
+        self.modules.borrow_mut().insert(specifier, module);
+    }
+}
+
+impl ModuleLoader for FileModuleLoader {
+    fn load_imported_module(
+        &self,
+        referrer: Referrer,
+        specifier: JsString,
+        finish_load: Box<dyn FnOnce(JsResult<Module>, &mut Context)>,
+        context: &mut Context,
+    ) {
+        let spec = specifier.to_std_string_escaped();
+
+        if let Some(module) = self.get(Path::new(&spec)) {
+            return finish_load(Ok(module), context);
+        }
+
+        let dir = referrer
+            .path()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.root.clone());
+
+        let result = self.load_file_module(&dir, &spec, context);
+        finish_load(result, context);
+    }
+}
+
+fn parse_file(path: &Path, ctx: &mut Context) -> JsResult<Module> {
+    let source = std::fs::read(path).map_err(|e| {
+        JsNativeError::typ().with_message(format!("Could not read `{}`, {e}", path.display()))
+    })?;
+
+    Module::parse(Source::from_bytes(&source).with_path(path), None, ctx)
+}
+
+enum Global {
+    Value(Box<dyn FnOnce(&mut Context) -> JsResult<JsValue>>),
+    Function(NativeFunction, usize),
+}
+
+type Setup = Box<dyn FnOnce(&mut Context) -> JsResult<()>>;
+
+/// Configures a `Runtime`, which is how Rust programs embed Ike.
+///
+/// ```ignore
+/// let mut runtime = Runtime::builder()
+///     .module(FsModule)
+///     .global("answer", |_| Ok(JsValue::from(42)))
+///     .permissions(Permissions::sandboxed())
+///     .build()?;
+///
+/// let exports = runtime.execute_module("main.js")?;
+/// let result = runtime.call(&exports, "handle", &[JsValue::from(1)])?;
+/// ```
+pub struct RuntimeBuilder {
+    context: ContextBuilder,
+    loader: Option<Rc<dyn RuntimeModuleLoader>>,
+    modules: Vec<Box<dyn ModuleTrait>>,
+    globals: Vec<(String, Global)>,
+    setups: Vec<Setup>,
+    permissions: Option<Permissions>,
+}
+
+impl RuntimeBuilder {
+    pub fn new() -> Self {
+        Self {
+            context: Context::builder(),
+            loader: None,
+            modules: Vec::new(),
+            globals: Vec::new(),
+            setups: Vec::new(),
+            permissions: None,
+        }
+    }
+
+    /// Adds the exposed functions and JS files of `module`, in the order they are added.
+    pub fn module<M: ModuleTrait + 'static>(mut self, module: M) -> Self {
+        self.modules.push(Box::new(module));
+        self
+    }
+
+    /// Defines a global, `init` creates its value once the context exists.
+    pub fn global<F>(mut self, name: &str, init: F) -> Self
+    where
+        F: FnOnce(&mut Context) -> JsResult<JsValue> + 'static,
+    {
+        self.globals
+            .push((name.to_string(), Global::Value(Box::new(init))));
+        self
+    }
+
+    pub fn global_function(mut self, name: &str, length: usize, function: NativeFunction) -> Self {
+        self.globals
+            .push((name.to_string(), Global::Function(function, length)));
+        self
+    }
+
+    /// Runs `setup` once the modules and globals are defined, for anything else the host
+    /// needs before scripts run.
+    pub fn setup<F>(mut self, setup: F) -> Self
+    where
+        F: FnOnce(&mut Context) -> JsResult<()> + 'static,
+    {
+        self.setups.push(Box::new(setup));
+        self
+    }
+
+    /// A `FileModuleLoader` for the current directory is used without one.
+    pub fn module_loader<L: RuntimeModuleLoader + 'static>(mut self, loader: Rc<L>) -> Self {
+        self.context = self.context.module_loader(Rc::clone(&loader));
+        self.loader = Some(loader);
+        self
+    }
+
+    /// The job queue drives promises and futures, boa's default runs them to completion
+    /// one after the other.
+    pub fn job_queue<Q: JobQueue + 'static>(mut self, queue: Rc<Q>) -> Self {
+        self.context = self.context.job_queue(queue);
+        self
+    }
+
+    pub fn host_hooks<H: HostHooks + 'static>(mut self, hooks: Rc<H>) -> Self {
+        self.context = self.context.host_hooks(hooks);
+        self
+    }
+
+    /// What the scripts of the runtime can access, everything is allowed without.
+    pub fn permissions(mut self, permissions: Permissions) -> Self {
+        self.permissions = Some(permissions);
+        self
+    }
+
+    pub fn build(self) -> JsResult<Runtime> {
+        let mut builder = self.context;
+        let loader: Rc<dyn RuntimeModuleLoader> = match self.loader {
+            Some(loader) => loader,
+            None => {
+                let root = std::env::current_dir().unwrap_or_default();
+                let loader = Rc::new(FileModuleLoader::new(root));
+                builder = builder.module_loader(Rc::clone(&loader));
+                loader
+            }
+        };
+        let mut context = builder.build()?;
+
+        permissions::set_permissions(self.permissions.unwrap_or_default(), &mut context);
+
+        for module in &self.modules {
+            load_module(&mut context, module.as_ref(), loader.as_ref())?;
+        }
+
+        for (name, global) in self.globals {
+            match global {
+                Global::Value(init) => {
+                    let value = init(&mut context)?;
+                    context.register_global_property(js_string!(name), value, Attribute::all())?;
+                }
+                Global::Function(function, length) => {
+                    context.register_global_builtin_callable(js_string!(name), length, function)?;
+                }
+            }
+        }
+
+        for setup in self.setups {
+            setup(&mut context)?;
+        }
+
+        Ok(Runtime { context, loader })
+    }
+}
+
+impl Default for RuntimeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn load_module(
+    ctx: &mut Context,
+    module: &dyn ModuleTrait,
+    loader: &dyn RuntimeModuleLoader,
+) -> JsResult<()> {
+    for exposed in module.exposed_functions() {
+        ctx.register_global_builtin_callable(
+            js_string!(exposed.name),
+            0,
+            NativeFunction::from_fn_ptr(exposed.function),
+        )?;
+    }
+
+    for (file, content) in module.js_files() {
+        let path = Path::new(module.cwd()).join(file);
+        let source = Source::from_bytes(content.as_bytes()).with_path(&path);
+
+        let parsed = Module::parse(source, None, ctx)?;
+        loader.insert(PathBuf::from(module.name_for(file)), parsed);
+    }
+
+    Ok(())
+}
+
+/// A JS context with Ike's modules and globals. Everything that runs JS returns once the
+/// job queue ran out of work, with what was thrown or rejected as the error.
+pub struct Runtime {
+    context: Context,
+    loader: Rc<dyn RuntimeModuleLoader>,
+}
+
+impl Runtime {
+    pub fn builder() -> RuntimeBuilder {
+        RuntimeBuilder::new()
+    }
+
+    pub fn context(&mut self) -> &mut Context {
+        &mut self.context
+    }
+
+    /// Evaluates the module at `path` and returns its namespace, the object of its exports.
+    pub fn execute_module<P: AsRef<Path>>(&mut self, path: P) -> JsResult<JsObject> {
+        let module = self.loader.load_entry(path.as_ref(), &mut self.context)?;
+
+        self.evaluate_module(module)
+    }
+
+    /// Like `execute_module`, for a module that was already parsed.
+    pub fn evaluate_module(&mut self, module: Module) -> JsResult<JsObject> {
+        let promise = module.load_link_evaluate(&mut self.context);
+        mark_handled(&promise, &mut self.context);
+
+        self.run_event_loop();
+
+        match promise.state() {
+            PromiseState::Fulfilled(_) => Ok(module.namespace(&mut self.context)),
+            PromiseState::Rejected(error) => Err(JsError::from_opaque(error)),
+            PromiseState::Pending => {
+                let path = module.path().map(Path::display);
+                Err(JsNativeError::error()
+                    .with_message(match path {
+                        Some(path) => format!("Module `{path}` didn't finish evaluating"),
+                        None => "Module didn't finish evaluating".to_string(),
+                    })
+                    .into())
+            }
+        }
+    }
+
+    /// Evaluates `source` as a script. A promise it returns is awaited.
+    pub fn eval(&mut self, source: &str) -> JsResult<JsValue> {
+        let value = self.context.eval(Source::from_bytes(source))?;
+
+        self.settle(value)
+    }
+
+    /// Calls the function exported as `name` from the namespace of a module. A promise it
+    /// returns is awaited.
+    pub fn call(&mut self, exports: &JsObject, name: &str, args: &[JsValue]) -> JsResult<JsValue> {
+        let function = exports.get(js_string!(name), &mut self.context)?;
+        let value = match function.as_callable() {
+            Some(function) => function.call(&JsValue::undefined(), args, &mut self.context)?,
+            None => {
+                return Err(JsNativeError::typ()
+                    .with_message(format!("Export `{name}` is not a function"))
+                    .into())
+            }
+        };
+
+        self.settle(value)
+    }
+
+    /// Runs the job queue until it ran out of work.
+    pub fn run_event_loop(&mut self) {
+        self.context.run_jobs();
+    }
+
+    /// Runs the event loop and returns what a promise settled with, or `value` if it isn't
+    /// one.
+    fn settle(&mut self, value: JsValue) -> JsResult<JsValue> {
+        let promise = match value
+            .as_object()
+            .map(|object| JsPromise::from_object(object.clone()))
+        {
+            Some(Ok(promise)) => promise,
+            _ => {
+                self.run_event_loop();
+                return Ok(value);
+            }
+        };
+        mark_handled(&promise, &mut self.context);
+
+        self.run_event_loop();
+
+        match promise.state() {
+            PromiseState::Fulfilled(value) => Ok(value),
+            PromiseState::Rejected(error) => Err(JsError::from_opaque(error)),
+            PromiseState::Pending => Err(JsNativeError::error()
+                .with_message("Promise didn't settle before the event loop ran out of work")
+                .into()),
+        }
+    }
+}
+
+/// The rejection of `promise` ends up in a `Result`, so it isn't an unhandled one as well.
+fn mark_handled(promise: &JsPromise, ctx: &mut Context) {
+    let ignore = NativeFunction::from_fn_ptr(|_, _, _| Ok(JsValue::undefined()));
+    let ignore = ignore.to_js_function(ctx.realm());
+
+    let _ = promise.then(None, Some(ignore), ctx);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{exposed::ExposedFunction, permissions::PermissionKind};
+
+    struct MathModule;
+
+    impl ModuleTrait for MathModule {
+        fn js_files(&self) -> &'static [(&'static str, &'static str)] {
+            &[("math.js", "export const add = (a, b) => a + b;")]
+        }
+
+        fn spec(&self) -> &'static str {
+            "math"
+        }
+
+        fn exposed_functions(&self) -> &'static [ExposedFunction] {
+            &[]
+        }
+    }
+
+    #[test]
+    fn evaluates_scripts_and_awaits_promises() {
+        let mut runtime = Runtime::builder()
+            .global("answer", |_| Ok(JsValue::from(40)))
+            .build()
+            .unwrap();
+
+        let value = runtime.eval("Promise.resolve(answer + 2)").unwrap();
+        assert_eq!(value.as_number(), Some(42.0));
+        assert!(runtime.eval("Promise.reject(new Error('no'))").is_err());
+    }
+
+    #[test]
+    fn calls_exports_of_a_module() {
+        let dir = std::env::temp_dir().join(format!("ike-runtime-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let main = dir.join("main.js");
+        std::fs::write(
+            &main,
+            "import { add } from 'module:math/math.js';\n\
+             export const sum = async (a, b) => add(a, b);\n\
+             export const fail = () => { throw new TypeError('nope'); };\n",
+        )
+        .unwrap();
+
+        let mut runtime = Runtime::builder().module(MathModule).build().unwrap();
+        let exports = runtime.execute_module(&main).unwrap();
+
+        let sum = runtime.call(&exports, "sum", &[1.into(), 2.into()]);
+        assert_eq!(sum.unwrap().as_number(), Some(3.0));
+        assert!(runtime.call(&exports, "fail", &[]).is_err());
+        assert!(runtime.call(&exports, "missing", &[]).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_permissions_per_runtime() {
+        let mut sandboxed = Runtime::builder()
+            .permissions(Permissions::sandboxed())
+            .build()
+            .unwrap();
+        let mut unrestricted = Runtime::builder().build().unwrap();
+
+        let granted = |runtime: &mut Runtime| {
+            permissions::is_granted(PermissionKind::Env, Some("HOME"), runtime.context())
+        };
+        assert!(!granted(&mut sandboxed));
+        assert!(granted(&mut unrestricted));
+    }
+}
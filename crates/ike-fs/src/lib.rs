@@ -3,6 +3,10 @@ use std::io::{ErrorKind, Read};
 use std::path::PathBuf;
 use thiserror::Error;
 
+mod which;
+
+pub use which::which;
+
 #[derive(Debug, Error)]
 pub enum FsError {
     #[error("File not found: {0}")]
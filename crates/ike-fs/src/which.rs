@@ -6,7 +6,8 @@ use std::path::{Path, PathBuf};
 use std::os::unix::fs::PermissionsExt;
 
 pub fn which(command: &str, path: Option<String>, cwd: Option<PathBuf>) -> Option<PathBuf> {
-    let path = path.unwrap_or_else(|| env::var("PATH").unwrap());
+    // A missing PATH is searched like an empty one
+    let path = path.or_else(|| env::var("PATH").ok()).unwrap_or_default();
 
     let cmd_path = PathBuf::from(command);
 
@@ -48,8 +49,13 @@ pub fn which(command: &str, path: Option<String>, cwd: Option<PathBuf>) -> Optio
         None
     }
 
-    if let Some(cwd) = cwd {
-        let cwd_cmd = cwd.join(&cmd_path);
+    // Paths like `./bin/tool` are relative to `cwd`, bare names are only looked up in
+    // PATH so a file in the working directory can't stand in for a command
+    if cmd_path.components().count() > 1 {
+        let cwd_cmd = match cwd {
+            Some(cwd) => cwd.join(&cmd_path),
+            None => cmd_path,
+        };
         if is_executable(&cwd_cmd) {
             return Some(cwd_cmd);
         }
@@ -58,9 +64,16 @@ pub fn which(command: &str, path: Option<String>, cwd: Option<PathBuf>) -> Optio
         if let Some(path_with_ext) = check_extensions(&cwd_cmd) {
             return Some(path_with_ext);
         }
+
+        return None;
     }
 
     for dir in env::split_paths(&path) {
+        // An empty entry would mean the working directory
+        if dir.as_os_str().is_empty() {
+            continue;
+        }
+
         let full_path = dir.join(&cmd_path);
         if is_executable(&full_path) {
             return Some(full_path);
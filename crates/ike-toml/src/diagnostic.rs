@@ -0,0 +1,362 @@
+use std::{
+    fmt,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+use toml_edit::{ImDocument, Item};
+
+use crate::IkeTomlError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem in an ike.toml file, pointing at the line and column it happened at.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: PathBuf,
+    pub message: String,
+    /// 1-based line and column, `None` when the problem can't be tied to a place in the file.
+    pub location: Option<(usize, usize)>,
+    frame: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: Severity,
+        file: &Path,
+        source: &str,
+        span: Option<Range<usize>>,
+        message: impl Into<String>,
+    ) -> Self {
+        let span = span.filter(|span| span.start <= source.len());
+
+        Self {
+            severity,
+            file: file.to_path_buf(),
+            message: message.into(),
+            location: span
+                .as_ref()
+                .map(|span| line_and_column(source, span.start)),
+            frame: span.map(|span| code_frame(source, span, severity)),
+        }
+    }
+
+    pub fn error(
+        file: &Path,
+        source: &str,
+        span: Option<Range<usize>>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::new(Severity::Error, file, source, span, message)
+    }
+
+    pub fn warning(
+        file: &Path,
+        source: &str,
+        span: Option<Range<usize>>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::new(Severity::Warning, file, source, span, message)
+    }
+}
+
+/// Formatted for the logger: the message, where it happened and a code frame.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        match self.location {
+            Some((line, column)) => write!(
+                f,
+                "\n  <d>--><r> {}:{}:{}",
+                self.file.display(),
+                line,
+                column
+            )?,
+            None => write!(f, "\n  <d>--><r> {}", self.file.display())?,
+        }
+
+        if let Some(frame) = &self.frame {
+            write!(f, "\n{}", frame)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..floor_char_boundary(source, offset)];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count()
+        + 1;
+
+    (line, column)
+}
+
+fn floor_char_boundary(source: &str, mut offset: usize) -> usize {
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// The line `span` starts on, with the line before it for context and the span underlined.
+fn code_frame(source: &str, span: Range<usize>, severity: Severity) -> String {
+    let (line, column) = line_and_column(source, span.start);
+    let lines: Vec<&str> = source.lines().collect();
+    let width = (line + 1).to_string().len();
+    let color = match severity {
+        Severity::Error => "red",
+        Severity::Warning => "yellow",
+    };
+
+    let mut frame = format!("{:width$} <d>|<r>", "");
+    let first = line.saturating_sub(2).max(1);
+    for number in first..=line {
+        let content = lines.get(number - 1).copied().unwrap_or_default();
+        frame.push_str(&format!("\n{:>width$} <d>|<r> {}", number, escape(content)));
+    }
+
+    // Underline up to the end of the line for spans that cover several lines
+    let content = lines.get(line - 1).copied().unwrap_or_default();
+    let rest = content.chars().count().saturating_sub(column - 1);
+    let start = floor_char_boundary(source, span.start);
+    let end = floor_char_boundary(source, span.end.min(source.len()));
+    let length = source[start..end].chars().count().clamp(1, rest.max(1));
+    frame.push_str(&format!(
+        "\n{:width$} <d>|<r> {}<{}>{}<r>",
+        "",
+        " ".repeat(column - 1),
+        color,
+        "^".repeat(length)
+    ));
+
+    frame
+}
+
+/// The logger treats `<` and `>` as markup.
+fn escape(content: &str) -> String {
+    content.replace('<', "\\<").replace('>', "\\>")
+}
+
+/// Span of the last key of `path`, if every key of it exists.
+fn key_span(doc: &ImDocument<&str>, path: &[&str]) -> Option<Range<usize>> {
+    let mut item = doc.as_item();
+    let mut span = None;
+
+    for key in path {
+        let (found, value) = item.as_table_like()?.get_key_value(key)?;
+        span = found.span();
+        item = value;
+    }
+
+    span
+}
+
+/// Where in the file a validation error comes from, as key paths to try in order.
+fn error_paths(error: &IkeTomlError) -> Vec<Vec<&str>> {
+    match error {
+        IkeTomlError::MissingDependencyFields(name)
+        | IkeTomlError::ConflictingDependencyFields(name)
+        | IkeTomlError::ConflictingGitFields(name) => {
+            vec![vec!["dependencies", name], vec!["dev-dependencies", name]]
+        }
+        IkeTomlError::InvalidImportMapping(specifier) => vec![vec!["imports", specifier]],
+        IkeTomlError::MissingExportImport(key) => {
+            vec![vec!["exports", key], vec!["package", "exports", key]]
+        }
+        IkeTomlError::InvalidWorkspaceMember(..) => vec![vec!["workspace", "members"]],
+        IkeTomlError::UnknownFeature(_, by) => vec![vec!["features", by]],
+        IkeTomlError::FeatureCycle(cycle) => {
+            let first = cycle.split(" -> ").next().unwrap_or_default();
+            vec![vec!["features", first]]
+        }
+        IkeTomlError::InvalidDefaultFeatures => vec![vec!["features", "default"]],
+        _ => vec![],
+    }
+}
+
+/// Turns a validation error into a diagnostic pointing at the entry that caused it.
+pub(crate) fn locate(
+    error: IkeTomlError,
+    file: &Path,
+    source: &str,
+    doc: Option<&ImDocument<&str>>,
+) -> IkeTomlError {
+    let span = doc.and_then(|doc| {
+        error_paths(&error)
+            .iter()
+            .find_map(|path| key_span(doc, path))
+    });
+
+    Diagnostic::error(file, source, span, error.to_string()).into()
+}
+
+const DEPENDENCY_KEYS: [&str; 5] = ["version", "path", "git", "branch", "rev"];
+
+/// Keys ike doesn't know about, most likely typos, as `(dotted path, span)`.
+pub(crate) fn unknown_keys(doc: &ImDocument<&str>) -> Vec<(String, Option<Range<usize>>)> {
+    let mut unknown = Vec::new();
+
+    let top = [
+        "package",
+        "dependencies",
+        "dev-dependencies",
+        "tasks",
+        "exports",
+        "imports",
+        "install",
+        "workspace",
+        "features",
+        "test",
+        "env-file",
+        "envFile",
+        "permissions",
+    ];
+    check_keys(doc.as_item(), "", &top, &mut unknown);
+
+    if let Some(package) = doc.get("package") {
+        let keys = [
+            "name",
+            "version",
+            "description",
+            "files",
+            "main",
+            "types",
+            "repository",
+            "exports",
+        ];
+        check_keys(package, "package", &keys, &mut unknown);
+
+        if let Some(repository) = package.get("repository") {
+            check_keys(
+                repository,
+                "package.repository",
+                &["type", "url"],
+                &mut unknown,
+            );
+        }
+    }
+
+    let configs = [
+        ("install", &["registry", "trusted-dependencies"][..]),
+        ("workspace", &["members", "exclude"][..]),
+        (
+            "test",
+            &["include", "exclude", "timeout", "setup", "reporter", "env"][..],
+        ),
+        ("permissions", &["read", "write", "env", "run", "net"][..]),
+    ];
+    for (name, keys) in configs {
+        if let Some(item) = doc.get(name) {
+            check_keys(item, name, keys, &mut unknown);
+        }
+    }
+
+    for table in ["dependencies", "dev-dependencies"] {
+        if let Some(deps) = doc.get(table) {
+            check_dependencies(deps, table, &mut unknown);
+        }
+    }
+
+    if let Some(features) = doc.get("features").and_then(Item::as_table_like) {
+        for (name, feature) in features.iter() {
+            let path = format!("features.{}", name);
+            let keys = ["dependencies", "files", "depends-on"];
+            check_keys(feature, &path, &keys, &mut unknown);
+
+            if let Some(deps) = feature.get("dependencies") {
+                check_dependencies(deps, &format!("{}.dependencies", path), &mut unknown);
+            }
+        }
+    }
+
+    unknown
+}
+
+fn check_dependencies(deps: &Item, path: &str, unknown: &mut Vec<(String, Option<Range<usize>>)>) {
+    if let Some(deps) = deps.as_table_like() {
+        for (name, dep) in deps.iter() {
+            let path = format!("{}.{}", path, name);
+            check_keys(dep, &path, &DEPENDENCY_KEYS, unknown);
+        }
+    }
+}
+
+fn check_keys(
+    item: &Item,
+    path: &str,
+    known: &[&str],
+    unknown: &mut Vec<(String, Option<Range<usize>>)>,
+) {
+    let table = match item.as_table_like() {
+        Some(table) => table,
+        None => return,
+    };
+
+    for (key, _) in table.iter() {
+        if known.contains(&key) {
+            continue;
+        }
+
+        let span = table.get_key_value(key).and_then(|(key, _)| key.span());
+        let full = match path.is_empty() {
+            true => key.to_string(),
+            false => format!("{}.{}", path, key),
+        };
+        unknown.push((full, span));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic_location() {
+        let source = "[package]\nname = \"app\"\nversoin = \"1.0.0\"\n";
+        let span = source.find("versoin").map(|start| start..start + 7);
+        let diagnostic = Diagnostic::warning(Path::new("ike.toml"), source, span, "unknown key");
+
+        assert_eq!(diagnostic.location, Some((3, 1)));
+        let output = diagnostic.to_string();
+        assert!(output.contains("ike.toml:3:1"));
+        assert!(output.contains("3 <d>|<r> versoin = \"1.0.0\""));
+        assert!(output.contains("<yellow>^^^^^^^<r>"));
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let source = r#"
+[package]
+name = "app"
+verison = "1.0.0"
+
+[dependencies]
+zod = { version = "^3", feature = "x" }
+
+[features]
+json = { files = ["json.ts"], deps = [] }
+"#;
+        let doc = ImDocument::parse(source).unwrap();
+        let keys: Vec<String> = unknown_keys(&doc).into_iter().map(|(key, _)| key).collect();
+
+        assert_eq!(
+            keys,
+            [
+                "package.verison",
+                "dependencies.zod.feature",
+                "features.json.deps"
+            ]
+        );
+    }
+}
@@ -1,11 +1,23 @@
 use ike_fs::{find_nearest_file, read_to_string};
-use ike_logger::{elog, Logger};
 use serde::Deserialize;
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
+use toml_edit::ImDocument;
+
+mod diagnostic;
+mod package_json;
+mod workspace;
+
+pub use diagnostic::{Diagnostic, Severity};
+pub use workspace::Workspace;
 
 #[derive(Deserialize, Debug, Default, Clone)]
 pub struct IkeTomlStruct {
+    /// A workspace root doesn't have to be a package itself.
+    #[serde(default)]
     pub package: IkePackage,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dependencies: Option<HashMap<String, DependencyOrString>>,
@@ -16,6 +28,88 @@ pub struct IkeTomlStruct {
     pub tasks: Option<HashMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exports: Option<HashMap<String, HashMap<String, String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imports: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub install: Option<InstallConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<WorkspaceConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub features: Option<HashMap<String, FeatureOrList>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test: Option<TestConfig>,
+    /// .env files loaded by `ike run` and `ike test`, relative to the package.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "env-file", alias = "envFile")]
+    pub env_file: Option<FileOrList>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<PermissionsConfig>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct WorkspaceConfig {
+    /// Globs of member directories relative to the workspace root, e.g. `packages/*`.
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct InstallConfig {
+    /// URL of an npm compatible registry, `IKE_REGISTRY` takes precedence over it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    /// Packages that are allowed to run lifecycle scripts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_dependencies: Option<Vec<String>>,
+}
+
+/// `[test]`, paths and globs are relative to the package.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct TestConfig {
+    /// Globs of test files, files named like `*.test.ts` or `*_spec.js` when empty.
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Milliseconds a single test may take.
+    pub timeout: Option<u64>,
+    /// Files evaluated before each test file.
+    pub setup: Vec<String>,
+    pub reporter: Option<TestReporter>,
+    /// Environment variables set while tests run.
+    pub env: HashMap<String, String>,
+}
+
+/// `[permissions]`, runs the package in a sandbox that only allows what is listed. Paths are
+/// relative to the package.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct PermissionsConfig {
+    pub read: Option<AllowList>,
+    pub write: Option<AllowList>,
+    pub env: Option<AllowList>,
+    pub run: Option<AllowList>,
+    /// Reserved, there are no network APIs to sandbox yet.
+    pub net: Option<AllowList>,
+}
+
+/// `true` allows everything, a list only the paths, variables, commands or hosts in it.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum AllowList {
+    All(bool),
+    List(Vec<String>),
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TestReporter {
+    /// Every file, group and test on its own line.
+    #[default]
+    Pretty,
+    /// A single character per test, failures are listed at the end.
+    Dot,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -25,6 +119,32 @@ pub enum DependencyOrString {
     String(String),
 }
 
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum FileOrList {
+    File(String),
+    List(Vec<String>),
+}
+
+/// A feature is either a table or, like `default`, just a list of other features.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum FeatureOrList {
+    List(Vec<String>),
+    Feature(Feature),
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Feature {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<HashMap<String, DependencyOrString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<Vec<String>>,
+}
+
 #[derive(Deserialize, Debug, Default, Clone)]
 pub struct ParsedFeature {
     pub dependencies: HashMap<String, Dependency>,
@@ -60,6 +180,10 @@ pub struct PackageRepository {
 pub struct IkeToml {
     pub toml: ParsedIkeTomlStruct,
     pub file_path: Option<PathBuf>,
+    /// Workspace the package is the root or a member of.
+    pub workspace: Option<Workspace>,
+    /// Problems that don't stop the package from being used, like unknown keys.
+    pub warnings: Vec<Diagnostic>,
 }
 
 #[derive(Deserialize, Debug, Default, Clone)]
@@ -76,6 +200,30 @@ pub struct Dependency {
     pub rev: Option<String>,
 }
 
+impl Dependency {
+    /// Checks that the dependency has exactly one source and no conflicting git fields.
+    pub fn validate(&self, name: &str) -> Result<(), IkeTomlError> {
+        if self.version.is_none() && self.path.is_none() && self.git.is_none() {
+            return Err(IkeTomlError::MissingDependencyFields(name.to_string()));
+        }
+
+        if (self.version.is_some() && (self.path.is_some() || self.git.is_some()))
+            || (self.path.is_some() && self.git.is_some())
+        {
+            return Err(IkeTomlError::ConflictingDependencyFields(name.to_string()));
+        }
+
+        if self.git.is_some()
+            && self.branch.is_some()
+            && (self.rev.is_some() || self.path.is_some())
+        {
+            return Err(IkeTomlError::ConflictingGitFields(name.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Deserialize, Debug, Default, Clone)]
 pub struct Export {
     pub import: String,
@@ -90,10 +238,25 @@ pub struct ParsedIkeTomlStruct {
     pub dev_dependencies: HashMap<String, Dependency>,
     pub tasks: HashMap<String, String>,
     pub exports: Option<HashMap<String, Export>>,
+    pub imports: HashMap<String, String>,
+    pub install: InstallConfig,
+    pub test: TestConfig,
+    pub env_files: Vec<String>,
+    pub permissions: Option<PermissionsConfig>,
+    pub workspace: Option<WorkspaceConfig>,
+    pub features: HashMap<String, ParsedFeature>,
+    /// Features that are active unless they are turned off, from `features.default`.
+    pub default_features: Vec<String>,
+    /// `type` of a package.json, ike.toml packages are always ES modules.
+    pub module_type: Option<ModuleType>,
 }
 
 #[derive(Debug, Error)]
 pub enum IkeTomlError {
+    #[error("Could not read {0}")]
+    FailedToRead(PathBuf),
+    #[error("{0}")]
+    Diagnostic(Box<Diagnostic>),
     #[error("Dependency {0} must have at least one of 'version', 'path', or 'git'")]
     MissingDependencyFields(String),
     #[error("Dependency {0} has conflicting fields: 'version', 'path', and 'git' cannot be used together")]
@@ -102,6 +265,139 @@ pub enum IkeTomlError {
     ConflictingGitFields(String),
     #[error("Failed to parse dependencies: {0}")]
     FailedToParseDependencies(String),
+    #[error("Import {0} ends with '/' so its target must end with '/' too")]
+    InvalidImportMapping(String),
+    #[error("Export {0} must have an 'import' field")]
+    MissingExportImport(String),
+    #[error("Invalid workspace member pattern {0}: {1}")]
+    InvalidWorkspaceMember(String, String),
+    #[error("Workspace members {1} and {2} are both named {0}")]
+    DuplicateWorkspaceMember(String, PathBuf, PathBuf),
+    #[error("Feature {0} used by {1} is not defined in [features]")]
+    UnknownFeature(String, String),
+    #[error("Features depend on each other in a cycle: {0}")]
+    FeatureCycle(String),
+    #[error("features.default must be a list of features")]
+    InvalidDefaultFeatures,
+}
+
+impl From<Diagnostic> for IkeTomlError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self::Diagnostic(Box::new(diagnostic))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleType {
+    Module,
+    CommonJs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportCondition {
+    Import,
+    Types,
+}
+
+impl ParsedIkeTomlStruct {
+    /// Looks up a dependency by name in both `dependencies` and `dev-dependencies`.
+    pub fn find_dependency(&self, name: &str) -> Option<&Dependency> {
+        self.dependencies
+            .get(name)
+            .or_else(|| self.dev_dependencies.get(name))
+    }
+
+    /// Features that end up active when `requested` are turned on, including the default
+    /// ones (unless `no_default` is set) and everything they depend on. Sorted by name.
+    pub fn active_features(
+        &self,
+        requested: &[String],
+        no_default: bool,
+    ) -> Result<Vec<String>, IkeTomlError> {
+        let mut active = BTreeSet::new();
+        let mut pending: Vec<&String> = requested.iter().collect();
+        if !no_default {
+            pending.extend(self.default_features.iter());
+        }
+
+        while let Some(name) = pending.pop() {
+            let feature = match self.features.get(name) {
+                Some(feature) => feature,
+                None => {
+                    return Err(IkeTomlError::UnknownFeature(
+                        name.clone(),
+                        "--features".to_string(),
+                    ))
+                }
+            };
+
+            if active.insert(name.clone()) {
+                pending.extend(feature.depends_on.iter().flatten());
+            }
+        }
+
+        Ok(active.into_iter().collect())
+    }
+
+    /// Dependencies added by the `active` features.
+    pub fn feature_dependencies<'a>(
+        &'a self,
+        active: &'a [String],
+    ) -> impl Iterator<Item = (&'a String, &'a Dependency)> {
+        active
+            .iter()
+            .filter_map(|name| self.features.get(name))
+            .flat_map(|feature| feature.dependencies.iter())
+    }
+
+    /// Returns the file exported under `subpath` for the given condition. The root of the
+    /// package is `.` and falls back to `package.main` when there is no export for it.
+    pub fn resolve_export(&self, subpath: &str, condition: ExportCondition) -> Option<String> {
+        let subpath = normalize_export_key(subpath);
+
+        let export = self.exports.as_ref().and_then(|exports| {
+            exports
+                .iter()
+                .find(|(key, _)| normalize_export_key(key) == subpath)
+                .map(|(_, export)| export)
+        });
+
+        match (export, condition) {
+            (Some(export), ExportCondition::Import) => Some(export.import.clone()),
+            (Some(export), ExportCondition::Types) => export.types.clone(),
+            (None, ExportCondition::Import) if subpath.is_empty() => self.package.main.clone(),
+            (None, ExportCondition::Types) if subpath.is_empty() => self.package.types.clone(),
+            (None, _) => None,
+        }
+    }
+}
+
+fn find_feature_cycle<'a>(
+    features: &'a HashMap<String, ParsedFeature>,
+    name: &'a String,
+    path: &mut Vec<&'a String>,
+) -> Result<(), IkeTomlError> {
+    if let Some(start) = path.iter().position(|feature| *feature == name) {
+        let mut cycle: Vec<&str> = path[start..].iter().map(|f| f.as_str()).collect();
+        cycle.push(name);
+        return Err(IkeTomlError::FeatureCycle(cycle.join(" -> ")));
+    }
+
+    path.push(name);
+    for dependency in features[name].depends_on.iter().flatten() {
+        find_feature_cycle(features, dependency, path)?;
+    }
+    path.pop();
+
+    Ok(())
+}
+
+/// `.`, `./` and `` all point to the root of the package, `./utils` is the same as `utils`.
+fn normalize_export_key(key: &str) -> &str {
+    if key == "." {
+        return "";
+    }
+    key.strip_prefix("./").unwrap_or(key)
 }
 
 impl IkeTomlStruct {
@@ -124,53 +420,129 @@ impl IkeTomlStruct {
                     },
                 };
 
-                if dep.version.is_none() && dep.path.is_none() && dep.git.is_none() {
-                    return Err(IkeTomlError::MissingDependencyFields(name));
-                }
+                dep.validate(&name)?;
+                parsed_deps.insert(name, dep);
+            }
+        }
 
-                if (dep.version.is_some() && (dep.path.is_some() || dep.git.is_some()))
-                    || (dep.path.is_some() && dep.git.is_some())
-                {
-                    return Err(IkeTomlError::ConflictingDependencyFields(name));
-                }
+        Ok(parsed_deps)
+    }
+
+    fn parse_imports(
+        &self,
+        imports: Option<HashMap<String, String>>,
+    ) -> Result<HashMap<String, String>, IkeTomlError> {
+        let imports = imports.unwrap_or_default();
+
+        for (specifier, target) in imports.iter() {
+            if specifier.ends_with('/') && !target.ends_with('/') {
+                return Err(IkeTomlError::InvalidImportMapping(specifier.clone()));
+            }
+        }
+
+        Ok(imports)
+    }
+
+    fn parse_exports(
+        &self,
+        exports: HashMap<String, HashMap<String, String>>,
+    ) -> Result<HashMap<String, Export>, IkeTomlError> {
+        let mut parsed_exports = HashMap::new();
 
-                if dep.git.is_some()
-                    && dep.branch.is_some()
-                    && (dep.rev.is_some() || dep.path.is_some())
-                {
-                    return Err(IkeTomlError::ConflictingGitFields(name));
+        for (key, value) in exports {
+            let import = match value.get("import") {
+                Some(import) => import.clone(),
+                None => return Err(IkeTomlError::MissingExportImport(key)),
+            };
+
+            parsed_exports.insert(
+                key,
+                Export {
+                    import,
+                    types: value.get("types").cloned(),
+                },
+            );
+        }
+
+        Ok(parsed_exports)
+    }
+
+    fn parse_features(
+        &self,
+        features: Option<HashMap<String, FeatureOrList>>,
+    ) -> Result<(HashMap<String, ParsedFeature>, Vec<String>), IkeTomlError> {
+        let mut parsed_features = HashMap::new();
+        let mut default_features = Vec::new();
+
+        for (name, feature) in features.unwrap_or_default() {
+            let feature = match (name.as_str(), feature) {
+                ("default", FeatureOrList::List(features)) => {
+                    default_features = features;
+                    continue;
+                }
+                ("default", FeatureOrList::Feature(_)) => {
+                    return Err(IkeTomlError::InvalidDefaultFeatures)
                 }
+                (_, FeatureOrList::List(depends_on)) => ParsedFeature {
+                    depends_on: Some(depends_on),
+                    ..Default::default()
+                },
+                (_, FeatureOrList::Feature(feature)) => ParsedFeature {
+                    dependencies: self.parse_dependencies(feature.dependencies)?,
+                    files: feature.files.unwrap_or_default(),
+                    depends_on: feature.depends_on,
+                },
+            };
 
-                parsed_deps.insert(name, dep);
+            parsed_features.insert(name, feature);
+        }
+
+        for feature in &default_features {
+            if !parsed_features.contains_key(feature) {
+                return Err(IkeTomlError::UnknownFeature(
+                    feature.clone(),
+                    "default".to_string(),
+                ));
             }
         }
 
-        Ok(parsed_deps)
+        for (name, feature) in &parsed_features {
+            for dependency in feature.depends_on.iter().flatten() {
+                if !parsed_features.contains_key(dependency) {
+                    return Err(IkeTomlError::UnknownFeature(
+                        dependency.clone(),
+                        name.clone(),
+                    ));
+                }
+            }
+        }
+
+        // Sorted, so the same cycle is always reported the same way
+        let mut names: Vec<&String> = parsed_features.keys().collect();
+        names.sort();
+        for name in names {
+            find_feature_cycle(&parsed_features, name, &mut Vec::new())?;
+        }
+
+        Ok((parsed_features, default_features))
     }
 
     pub fn to_parsed(self) -> Result<ParsedIkeTomlStruct, IkeTomlError> {
-        let parsed_dependencies = self
-            .parse_dependencies(self.dependencies.clone())
-            .map_err(|e| IkeTomlError::FailedToParseDependencies(e.to_string()))?;
+        let parsed_dependencies = self.parse_dependencies(self.dependencies.clone())?;
+        let parsed_dev_dependencies = self.parse_dependencies(self.dev_dependencies.clone())?;
 
-        let parsed_dev_dependencies = self
-            .parse_dependencies(self.dev_dependencies.clone())
-            .map_err(|e| IkeTomlError::FailedToParseDependencies(e.to_string()))?;
+        let parsed_imports = self.parse_imports(self.imports.clone())?;
+        let (features, default_features) = self.parse_features(self.features.clone())?;
 
-        let parsed_exports = self.exports.map(|exports| {
-            exports
-                .iter()
-                .map(|(key, value)| {
-                    (
-                        key.clone(),
-                        Export {
-                            import: value.get("import").expect("Missing 'import' field").clone(),
-                            types: value.get("types").cloned(),
-                        },
-                    )
-                })
-                .collect()
-        });
+        // Top level `[exports]` takes precedence over `package.exports`
+        let exports = self
+            .exports
+            .clone()
+            .or_else(|| self.package.exports.clone());
+        let parsed_exports = match exports {
+            Some(exports) => Some(self.parse_exports(exports)?),
+            None => None,
+        };
 
         Ok(ParsedIkeTomlStruct {
             package: self.package,
@@ -178,38 +550,206 @@ impl IkeTomlStruct {
             dev_dependencies: parsed_dev_dependencies,
             tasks: self.tasks.unwrap_or_default(),
             exports: parsed_exports,
+            imports: parsed_imports,
+            install: self.install.unwrap_or_default(),
+            test: self.test.unwrap_or_default(),
+            env_files: match self.env_file {
+                Some(FileOrList::File(file)) => vec![file],
+                Some(FileOrList::List(files)) => files,
+                None => vec![],
+            },
+            permissions: self.permissions,
+            workspace: self.workspace,
+            features,
+            default_features,
+            module_type: None,
         })
     }
 }
 
 impl IkeToml {
     pub fn from_file(file_path: PathBuf) -> Result<Self, IkeTomlError> {
-        let toml_str = read_to_string(&file_path).expect("Could not read file");
-        let serialized_toml: IkeTomlStruct = toml::from_str(&toml_str).unwrap();
-        let parsed_toml = serialized_toml.to_parsed()?;
+        let source = read_to_string(&file_path)
+            .map_err(|_| IkeTomlError::FailedToRead(file_path.clone()))?;
+
+        if file_path
+            .file_name()
+            .is_some_and(|name| name == "package.json")
+        {
+            return Ok(Self {
+                toml: package_json::parse(&source, &file_path)?,
+                file_path: Some(file_path),
+                workspace: None,
+                warnings: Vec::new(),
+            });
+        }
+
+        Self::from_source(&source, file_path)
+    }
+
+    /// Parses and validates `source`, errors and warnings point at the place in the file
+    /// they come from.
+    pub fn from_source(source: &str, file_path: PathBuf) -> Result<Self, IkeTomlError> {
+        let serialized_toml: IkeTomlStruct = toml::from_str(source)
+            .map_err(|e| Diagnostic::error(&file_path, source, e.span(), e.message()))?;
+
+        // Only used for locations, it can't fail once the file deserialized
+        let doc = ImDocument::parse(source).ok();
+        let parsed_toml = serialized_toml
+            .to_parsed()
+            .map_err(|e| diagnostic::locate(e, &file_path, source, doc.as_ref()))?;
+
+        let mut warnings = Vec::new();
+        if let Some(doc) = &doc {
+            for (key, span) in diagnostic::unknown_keys(doc) {
+                let message = format!("Unknown key <cyan>{}<r> is ignored", key);
+                warnings.push(Diagnostic::warning(&file_path, source, span, message));
+            }
+
+            let version = &parsed_toml.package.version;
+            if !version.is_empty() && semver::Version::parse(version).is_err() {
+                let span = doc
+                    .get("package")
+                    .and_then(|package| package.get("version"))
+                    .and_then(|version| version.span());
+                let message = format!("Version <cyan>{}<r> is not valid semver", version);
+                warnings.push(Diagnostic::warning(&file_path, source, span, message));
+            }
+        }
 
         Ok(Self {
             toml: parsed_toml,
             file_path: Some(file_path),
+            workspace: None,
+            warnings,
         })
     }
 
-    pub fn find_nearest_from(dir: PathBuf) -> Option<Self> {
-        let file_path = find_nearest_file(dir, "ike.toml");
+    /// Attaches the workspace this package is the root or a member of.
+    pub fn with_workspace(mut self) -> Result<Self, IkeTomlError> {
+        self.workspace = Workspace::find_for(&self)?;
+        Ok(self)
+    }
+
+    /// Whether this package is the root of a workspace.
+    pub fn is_workspace_root(&self) -> bool {
+        self.toml.workspace.is_some()
+    }
+
+    /// Directory containing the ike.toml file.
+    pub fn dir(&self) -> Option<&Path> {
+        self.file_path.as_deref().and_then(Path::parent)
+    }
+
+    /// Whether the package was read from a package.json instead of an ike.toml.
+    pub fn is_package_json(&self) -> bool {
+        self.file_path
+            .as_deref()
+            .and_then(Path::file_name)
+            .is_some_and(|name| name == "package.json")
+    }
+
+    /// Loads the nearest ike.toml in `dir` or any of its parents. Projects without one fall
+    /// back to the nearest package.json, `None` if there is neither.
+    pub fn find_nearest_from(dir: PathBuf) -> Result<Option<Self>, IkeTomlError> {
+        let file_path = find_nearest_file(dir.clone(), "ike.toml")
+            .or_else(|| find_nearest_file(dir, "package.json"));
 
         match file_path {
-            Some(file_path) => {
-                let toml = IkeToml::from_file(file_path);
-
-                match toml {
-                    Ok(toml) => Some(toml),
-                    Err(e) => {
-                        elog!(error, "{}", e);
-                        None
-                    }
-                }
-            }
-            None => None,
+            Some(file_path) => IkeToml::from_file(file_path)
+                .and_then(IkeToml::with_workspace)
+                .map(Some),
+            None => Ok(None),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Result<ParsedIkeTomlStruct, IkeTomlError> {
+        toml::from_str::<IkeTomlStruct>(content)
+            .unwrap()
+            .to_parsed()
+    }
+
+    #[test]
+    fn test_active_features() {
+        let toml = parse(
+            r#"
+            [features]
+            default = ["json"]
+            json = { depends-on = ["base"], dependencies = { zod = "^3" } }
+            yaml = ["base"]
+            base = {}
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(toml.active_features(&[], false).unwrap(), ["base", "json"]);
+        assert_eq!(
+            toml.active_features(&["yaml".to_string()], true).unwrap(),
+            ["base", "yaml"]
+        );
+        assert!(toml.active_features(&["xml".to_string()], false).is_err());
+
+        let active = toml.active_features(&[], false).unwrap();
+        let deps: Vec<_> = toml.feature_dependencies(&active).collect();
+        assert_eq!(deps.len(), 1);
+        assert_eq!(deps[0].0, "zod");
+    }
+
+    #[test]
+    fn test_invalid_features() {
+        let unknown = parse("[features]\ndefault = [\"missing\"]\n");
+        assert!(matches!(unknown, Err(IkeTomlError::UnknownFeature(..))));
+
+        let cycle = parse("[features]\na = [\"b\"]\nb = [\"a\"]\n");
+        match cycle {
+            Err(IkeTomlError::FeatureCycle(cycle)) => assert_eq!(cycle, "a -> b -> a"),
+            other => panic!("expected a cycle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_errors_have_locations() {
+        let file = PathBuf::from("ike.toml");
+        let located = |source: &str| match IkeToml::from_source(source, file.clone()) {
+            Err(IkeTomlError::Diagnostic(diagnostic)) => diagnostic.location,
+            other => panic!("expected a diagnostic, got {:?}", other),
+        };
+
+        assert_eq!(located("[package]\nname = \"app\n"), Some((2, 12)));
+        assert_eq!(
+            located("[package]\nname = \"app\"\nversion = \"1.0.0\"\n\n[dependencies]\nzod = {}\n"),
+            Some((6, 1))
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let source = "[package]\nname = \"app\"\nversion = \"1.0\"\nmian = \"index.ts\"\n";
+        let toml = IkeToml::from_source(source, PathBuf::from("ike.toml")).unwrap();
+        let warnings: Vec<_> = toml.warnings.iter().map(|w| w.location).collect();
+
+        assert_eq!(warnings, [Some((4, 1)), Some((3, 11))]);
+    }
+
+    #[test]
+    fn test_permissions() {
+        let toml = parse("[permissions]\nread = [\"data\"]\nenv = true\n").unwrap();
+        let permissions = toml.permissions.unwrap();
+
+        assert_eq!(
+            permissions.read,
+            Some(AllowList::List(vec!["data".to_string()]))
+        );
+        assert_eq!(permissions.env, Some(AllowList::All(true)));
+        assert_eq!(permissions.run, None);
+        assert!(parse("[package]\nname = \"app\"\nversion = \"1.0.0\"\n")
+            .unwrap()
+            .permissions
+            .is_none());
+    }
+}
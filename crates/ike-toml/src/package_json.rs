@@ -0,0 +1,239 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    diagnostic::{self, Diagnostic},
+    Dependency, DependencyOrString, IkePackage, IkeTomlError, IkeTomlStruct, ModuleType,
+    ParsedIkeTomlStruct,
+};
+
+/// The parts of a package.json Ike understands.
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct PackageJson {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    version: String,
+    description: Option<String>,
+    main: Option<String>,
+    types: Option<String>,
+    typings: Option<String>,
+    files: Option<Vec<String>>,
+    #[serde(default)]
+    scripts: HashMap<String, String>,
+    #[serde(default)]
+    dependencies: HashMap<String, String>,
+    #[serde(default)]
+    dev_dependencies: HashMap<String, String>,
+    exports: Option<Value>,
+    r#type: Option<String>,
+}
+
+/// Reads a package.json as if it was an ike.toml: `scripts` become tasks and npm
+/// dependency specs are turned into their ike.toml equivalents.
+pub(crate) fn parse(source: &str, file: &Path) -> Result<ParsedIkeTomlStruct, IkeTomlError> {
+    let json: PackageJson = serde_json::from_str(source).map_err(|e| {
+        let span = offset_of(source, e.line(), e.column()).map(|offset| offset..offset + 1);
+        Diagnostic::error(file, source, span, e.to_string())
+    })?;
+
+    let module_type = match json.r#type.as_deref() {
+        Some("module") => Some(ModuleType::Module),
+        Some("commonjs") => Some(ModuleType::CommonJs),
+        _ => None,
+    };
+
+    let toml = IkeTomlStruct {
+        package: IkePackage {
+            name: json.name,
+            version: json.version,
+            description: json.description,
+            files: json.files,
+            main: json.main,
+            types: json.types.or(json.typings),
+            ..Default::default()
+        },
+        dependencies: Some(dependencies(json.dependencies)),
+        dev_dependencies: Some(dependencies(json.dev_dependencies)),
+        tasks: Some(json.scripts),
+        exports: json.exports.as_ref().map(exports),
+        ..Default::default()
+    };
+
+    let mut parsed = toml
+        .to_parsed()
+        .map_err(|e| diagnostic::locate(e, file, source, None))?;
+    parsed.module_type = module_type;
+
+    Ok(parsed)
+}
+
+fn dependencies(deps: HashMap<String, String>) -> HashMap<String, DependencyOrString> {
+    deps.into_iter()
+        .map(|(name, spec)| (name, DependencyOrString::Dependency(dependency(&spec))))
+        .collect()
+}
+
+/// `file:` and `link:` are path dependencies, `git+`, `git://` and `github:` are git ones
+/// with an optional `#ref`. Anything else is a version range.
+fn dependency(spec: &str) -> Dependency {
+    if let Some(path) = spec
+        .strip_prefix("file:")
+        .or_else(|| spec.strip_prefix("link:"))
+    {
+        return Dependency {
+            path: Some(path.to_string()),
+            ..Default::default()
+        };
+    }
+
+    let git = match spec.strip_prefix("github:") {
+        Some(repo) => Some(format!("https://github.com/{}", repo)),
+        None if spec.starts_with("git://") => Some(spec.to_string()),
+        None => spec.strip_prefix("git+").map(str::to_string),
+    };
+    if let Some(git) = git {
+        let (url, rev) = match git.split_once('#') {
+            Some((url, rev)) => (url.to_string(), Some(rev.to_string())),
+            None => (git, None),
+        };
+
+        return Dependency {
+            git: Some(url),
+            rev,
+            ..Default::default()
+        };
+    }
+
+    // Workspace members are matched by name, whatever the range is
+    let version = match spec.strip_prefix("workspace:") {
+        Some(_) => "*",
+        None => spec,
+    };
+
+    Dependency {
+        version: Some(version.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Flattens `exports` into the `import`/`types` form of ike.toml. A string or an object of
+/// conditions is the export of `.`, otherwise keys are subpaths.
+fn exports(value: &Value) -> HashMap<String, HashMap<String, String>> {
+    let subpaths = match value {
+        Value::Object(map) if map.keys().all(|key| key.starts_with('.')) => map
+            .iter()
+            .map(|(key, value)| (key.clone(), value))
+            .collect(),
+        _ => vec![(".".to_string(), value)],
+    };
+
+    subpaths
+        .into_iter()
+        .filter_map(|(subpath, value)| {
+            let mut export = HashMap::new();
+            export.insert("import".to_string(), import_target(value)?);
+            if let Some(types) = types_target(value) {
+                export.insert("types".to_string(), types);
+            }
+
+            Some((subpath, export))
+        })
+        .collect()
+}
+
+/// Conditions that apply to `import`, in order of preference.
+const IMPORT_CONDITIONS: [&str; 4] = ["import", "node", "default", "require"];
+
+fn import_target(value: &Value) -> Option<String> {
+    match value {
+        Value::String(target) => Some(target.clone()),
+        Value::Object(map) => IMPORT_CONDITIONS
+            .iter()
+            .find_map(|key| import_target(map.get(*key)?)),
+        Value::Array(targets) => targets.iter().find_map(import_target),
+        _ => None,
+    }
+}
+
+/// `types` can be next to the other conditions or nested in one of them.
+fn types_target(value: &Value) -> Option<String> {
+    let map = value.as_object()?;
+
+    map.get("types")
+        .or_else(|| map.get("typings"))
+        .and_then(import_target)
+        .or_else(|| {
+            IMPORT_CONDITIONS
+                .iter()
+                .find_map(|key| types_target(map.get(*key)?))
+        })
+}
+
+/// Byte offset of a 1-based line and column.
+fn offset_of(source: &str, line: usize, column: usize) -> Option<usize> {
+    let start: usize = source
+        .split_inclusive('\n')
+        .take(line.checked_sub(1)?)
+        .map(str::len)
+        .sum();
+
+    Some((start + column.saturating_sub(1)).min(source.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_json() {
+        let source = r#"{
+  "name": "app",
+  "version": "1.0.0",
+  "type": "commonjs",
+  "main": "index.js",
+  "scripts": { "dev": "ike run index.js" },
+  "dependencies": {
+    "zod": "^3.0.0",
+    "lib": "file:../lib",
+    "utils": "github:ike/utils#main",
+    "member": "workspace:^"
+  },
+  "exports": {
+    ".": { "types": "./index.d.ts", "import": "./index.mjs", "require": "./index.js" },
+    "./utils": "./utils.js"
+  }
+}"#;
+        let toml = parse(source, Path::new("package.json")).unwrap();
+
+        assert_eq!(toml.package.name, "app");
+        assert_eq!(toml.module_type, Some(ModuleType::CommonJs));
+        assert_eq!(toml.tasks["dev"], "ike run index.js");
+        assert_eq!(toml.dependencies["zod"].version.as_deref(), Some("^3.0.0"));
+        assert_eq!(toml.dependencies["lib"].path.as_deref(), Some("../lib"));
+        assert_eq!(
+            toml.dependencies["utils"].git.as_deref(),
+            Some("https://github.com/ike/utils")
+        );
+        assert_eq!(toml.dependencies["utils"].rev.as_deref(), Some("main"));
+        assert_eq!(toml.dependencies["member"].version.as_deref(), Some("*"));
+
+        let exports = toml.exports.unwrap();
+        assert_eq!(exports["."].import, "./index.mjs");
+        assert_eq!(exports["."].types.as_deref(), Some("./index.d.ts"));
+        assert_eq!(exports["./utils"].import, "./utils.js");
+    }
+
+    #[test]
+    fn test_parse_error_location() {
+        let source = "{\n  \"name\": \"app\",\n  \"version\": 1\n}";
+        match parse(source, Path::new("package.json")) {
+            Err(IkeTomlError::Diagnostic(diagnostic)) => {
+                assert_eq!(diagnostic.location.map(|(line, _)| line), Some(3))
+            }
+            other => panic!("expected a diagnostic, got {:?}", other),
+        }
+    }
+}
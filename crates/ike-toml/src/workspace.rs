@@ -0,0 +1,141 @@
+use glob::Pattern;
+use ike_fs::find_nearest_file;
+use std::path::{Path, PathBuf};
+
+use crate::{IkeToml, IkeTomlError, WorkspaceConfig};
+
+/// A workspace groups several packages under one root. Members depend on each other by
+/// name, share a single lockfile and can be tested or run together.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub members: Vec<IkeToml>,
+}
+
+impl Workspace {
+    /// Loads the members of the workspace rooted at `root`. Member globs match directories
+    /// containing an ike.toml, other matches are ignored.
+    pub fn load(root: &Path, config: &WorkspaceConfig) -> Result<Self, IkeTomlError> {
+        let exclude = config
+            .exclude
+            .iter()
+            .map(|pattern| {
+                Pattern::new(pattern).map_err(|e| {
+                    IkeTomlError::InvalidWorkspaceMember(pattern.clone(), e.to_string())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut dirs = Vec::new();
+        for pattern in &config.members {
+            let full_pattern = root.join(pattern).to_string_lossy().to_string();
+            let paths = glob::glob(&full_pattern).map_err(|e| {
+                IkeTomlError::InvalidWorkspaceMember(pattern.clone(), e.to_string())
+            })?;
+
+            for dir in paths.flatten() {
+                let relative = dir.strip_prefix(root).unwrap_or(&dir);
+                if exclude.iter().any(|pattern| pattern.matches_path(relative)) {
+                    continue;
+                }
+                if dir.join("ike.toml").is_file() && !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        dirs.sort();
+
+        let mut members: Vec<IkeToml> = Vec::new();
+        for dir in dirs {
+            let member = IkeToml::from_file(dir.join("ike.toml"))?;
+
+            let name = &member.toml.package.name;
+            if let Some(existing) = members.iter().find(|m| m.toml.package.name == *name) {
+                return Err(IkeTomlError::DuplicateWorkspaceMember(
+                    name.clone(),
+                    existing.dir().unwrap_or(root).to_path_buf(),
+                    dir,
+                ));
+            }
+
+            members.push(member);
+        }
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            members,
+        })
+    }
+
+    /// Finds the workspace `pkg` belongs to: its own one if it's a workspace root,
+    /// otherwise the nearest workspace above it that lists it as a member.
+    pub fn find_for(pkg: &IkeToml) -> Result<Option<Self>, IkeTomlError> {
+        let dir = match pkg.dir() {
+            Some(dir) => dir,
+            None => return Ok(None),
+        };
+
+        if let Some(config) = &pkg.toml.workspace {
+            return Self::load(dir, config).map(Some);
+        }
+
+        let mut parent = dir.parent().map(Path::to_path_buf);
+        while let Some(search_dir) = parent {
+            let file = match find_nearest_file(search_dir.clone(), "ike.toml") {
+                Some(file) => file,
+                None => return Ok(None),
+            };
+            let root = IkeToml::from_file(file)?;
+            let root_dir = root.dir().unwrap_or(&search_dir).to_path_buf();
+
+            if let Some(config) = &root.toml.workspace {
+                let workspace = Self::load(&root_dir, config)?;
+                return Ok(workspace.contains(dir).then_some(workspace));
+            }
+
+            parent = root_dir.parent().map(Path::to_path_buf);
+        }
+
+        Ok(None)
+    }
+
+    /// Whether the package in `dir` is a member of the workspace.
+    pub fn contains(&self, dir: &Path) -> bool {
+        self.member_by_dir(dir).is_some()
+    }
+
+    pub fn member(&self, name: &str) -> Option<&IkeToml> {
+        self.members
+            .iter()
+            .find(|member| member.toml.package.name == name)
+    }
+
+    pub fn member_by_dir(&self, dir: &Path) -> Option<&IkeToml> {
+        let dir = dir.canonicalize().ok()?;
+
+        self.members.iter().find(|member| {
+            member
+                .dir()
+                .and_then(|member_dir| member_dir.canonicalize().ok())
+                .is_some_and(|member_dir| member_dir == dir)
+        })
+    }
+
+    /// Members selected by `--filter`, which is a package name or a glob of names like
+    /// `@app/*`. Without a filter every member is selected.
+    pub fn filter(&self, filter: Option<&str>) -> Vec<&IkeToml> {
+        let pattern = filter.and_then(|filter| Pattern::new(filter).ok());
+
+        self.members
+            .iter()
+            .filter(|member| {
+                let name = &member.toml.package.name;
+                match (filter, &pattern) {
+                    (None, _) => true,
+                    (Some(filter), Some(pattern)) => name == filter || pattern.matches(name),
+                    (Some(filter), None) => name == filter,
+                }
+            })
+            .collect()
+    }
+}
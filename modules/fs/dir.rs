@@ -1,16 +1,13 @@
-use boa_engine::{
-    builtins::promise::ResolvingFunctions, js_string, object::builtins::JsPromise, Context,
-    JsNativeError, JsResult, JsValue,
-};
-use ike_core::promise::base_promise;
-use std::path::Path;
+use boa_engine::{js_string, Context, JsNativeError, JsResult, JsValue};
+use ike_core::permissions::PermissionKind;
+use ike_core::promise::{base_promise, promise_from_future};
+use std::path::PathBuf;
 
-use crate::{resolve_path_from_args, FileSystem};
+use crate::{checked_path_from_args, FileSystem};
 
 pub fn create_dir_sync_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
     let (path, recursive, mode) = resolve_create_dir_args(args, ctx)?;
-    let path = Path::new(&path);
-    let result = FileSystem::create_dir(path, recursive, mode);
+    let result = FileSystem::create_dir(&path, recursive, mode);
 
     match result {
         Ok(_) => Ok(JsValue::undefined()),
@@ -20,42 +17,21 @@ pub fn create_dir_sync_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> J
 
 pub fn create_dir_async_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
     let (path, recursive, mode) = resolve_create_dir_args(args, ctx)?;
-    let path = Path::new(&path);
 
-    let result = smol::block_on(async {
-        let result = FileSystem::create_dir_async(path, recursive, mode)
-            .await
-            .map_err(|e| JsNativeError::error().with_message(e.to_string()))
-            .err();
+    let promise = promise_from_future(
+        async move { FileSystem::create_dir_async(&path, recursive, mode).await },
+        |_, _| Ok(JsValue::undefined()),
+        ctx,
+    );
 
-        let promise = JsPromise::new(
-            |resolvers: &ResolvingFunctions, context| {
-                if result.is_some() {
-                    return Err(result.unwrap().into());
-                }
-
-                resolvers
-                    .resolve
-                    .call(&JsValue::undefined(), &[JsValue::undefined()], context)?;
-                Ok(JsValue::undefined())
-            },
-            ctx,
-        );
-
-        let promise = base_promise(promise, ctx);
-
-        Ok(promise.into())
-    });
-
-    result
+    Ok(base_promise(promise, ctx).into())
 }
 
 pub fn resolve_create_dir_args(
     args: &[JsValue],
     ctx: &mut Context,
-) -> JsResult<(String, bool, u32)> {
-    let path = resolve_path_from_args(args, ctx)?;
-    let path = &path.to_std_string().unwrap();
+) -> JsResult<(PathBuf, bool, u32)> {
+    let path = checked_path_from_args(args, PermissionKind::Write, ctx)?;
     let recursive = get_recursive_flag(args, ctx)?;
     let mode = args
         .get(2)
@@ -64,7 +40,7 @@ pub fn resolve_create_dir_args(
         .unwrap_or(0o777)
         & 0o777;
 
-    Ok((path.clone(), recursive, mode))
+    Ok((path, recursive, mode))
 }
 
 pub fn get_recursive_flag(args: &[JsValue], ctx: &mut Context) -> JsResult<bool> {
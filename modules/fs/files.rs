@@ -1,21 +1,18 @@
-use boa_engine::builtins::promise::ResolvingFunctions;
-use boa_engine::object::builtins::{JsArrayBuffer, JsPromise, JsUint8Array};
+use boa_engine::object::builtins::{JsArrayBuffer, JsUint8Array};
 use boa_engine::{js_string, Context, JsNativeError, JsResult, JsValue};
-use ike_core::promise::base_promise;
-use smol::block_on;
+use ike_core::permissions::PermissionKind;
+use ike_core::promise::{base_promise, promise_from_future};
 use std::path::Path;
 
-use crate::{resolve_path_from_args, FileSystem};
+use crate::{checked_path_from_args, FileSystem};
 
 use super::{open_file, File};
 use ike_core::throw;
 
 pub fn read_file_sync_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
-    let str_path = resolve_path_from_args(args, ctx)?;
-    let str_path = &str_path.to_std_string().unwrap();
-    let path = Path::new(&str_path);
+    let path = checked_path_from_args(args, PermissionKind::Read, ctx)?;
 
-    let file = FileSystem::open_sync(path);
+    let file = FileSystem::open_sync(&path);
 
     match file {
         Ok(file) => {
@@ -30,52 +27,25 @@ pub fn read_file_sync_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> Js
     }
 }
 
-pub async fn read_file_async_base(path: &Path, ctx: &mut Context) -> anyhow::Result<JsUint8Array> {
-    let file = match open_file(path) {
-        Ok(file) => file,
-        Err(e) => return Err(e),
-    };
-    let file = File::new(file);
+pub async fn read_file_async_base(path: &Path) -> anyhow::Result<Vec<u8>> {
+    let file = File::new(open_file(path)?);
 
-    let contents = file.read_async().await.unwrap();
-    let array_buffer = JsArrayBuffer::from_byte_block(contents, ctx).unwrap();
-    let uint8_array = JsUint8Array::from_array_buffer(array_buffer, ctx).unwrap();
-
-    Ok(uint8_array)
+    Ok(file.read_async().await?)
 }
 
 pub fn read_file_async_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
-    let str_path = resolve_path_from_args(args, ctx)?;
-    let str_path = &str_path.to_std_string().unwrap();
-    let path = Path::new(&str_path);
-
-    let result = block_on(async {
-        let result = read_file_async_base(path, ctx).await;
-
-        let promise = JsPromise::new(
-            |resolvers: &ResolvingFunctions, context| {
-                if result.is_err() {
-                    return Err(JsNativeError::error()
-                        .with_message(result.err().unwrap().to_string())
-                        .into());
-                }
-
-                resolvers.resolve.call(
-                    &JsValue::undefined(),
-                    &[JsValue::from(result.unwrap())],
-                    context,
-                )?;
-                Ok(JsValue::undefined())
-            },
-            ctx,
-        );
-
-        let promise = base_promise(promise, ctx);
-
-        Ok(promise.into())
-    });
-
-    result
+    let path = checked_path_from_args(args, PermissionKind::Read, ctx)?;
+
+    let promise = promise_from_future(
+        async move { read_file_async_base(&path).await },
+        |contents, ctx| {
+            let array_buffer = JsArrayBuffer::from_byte_block(contents, ctx)?;
+            Ok(JsUint8Array::from_array_buffer(array_buffer, ctx)?.into())
+        },
+        ctx,
+    );
+
+    Ok(base_promise(promise, ctx).into())
 }
 
 pub fn read_text_file_sync_ex(
@@ -83,11 +53,9 @@ pub fn read_text_file_sync_ex(
     args: &[JsValue],
     ctx: &mut Context,
 ) -> JsResult<JsValue> {
-    let str_path = resolve_path_from_args(args, ctx)?;
-    let str_path = &str_path.to_std_string().unwrap();
-    let path = Path::new(&str_path);
+    let path = checked_path_from_args(args, PermissionKind::Read, ctx)?;
 
-    let file = FileSystem::open_sync(path);
+    let file = FileSystem::open_sync(&path);
 
     match file {
         Ok(file) => {
@@ -102,15 +70,9 @@ pub fn read_text_file_sync_ex(
 }
 
 pub async fn read_text_file_async_base(path: &Path) -> anyhow::Result<String> {
-    let file = match open_file(path) {
-        Ok(file) => file,
-        Err(e) => return Err(e),
-    };
-    let file = File::new(file);
-
-    let contents = file.read_async().await.unwrap();
+    let contents = read_file_async_base(path).await?;
 
-    Ok(String::from_utf8(contents).unwrap())
+    Ok(String::from_utf8(contents)?)
 }
 
 pub fn read_text_file_async_ex(
@@ -118,81 +80,36 @@ pub fn read_text_file_async_ex(
     args: &[JsValue],
     ctx: &mut Context,
 ) -> JsResult<JsValue> {
-    let str_path = resolve_path_from_args(args, ctx)?;
-    let str_path = &str_path.to_std_string().unwrap();
-    let path = Path::new(&str_path);
-
-    let result = block_on(async {
-        let result = read_text_file_async_base(path)
-            .await
-            .map_err(|e| JsNativeError::error().with_message(e.to_string()));
-
-        let promise = JsPromise::new(
-            |resolvers: &ResolvingFunctions, context| {
-                if result.is_err() {
-                    return Err(result.err().unwrap().into());
-                }
-                let res = result.unwrap().to_string();
-
-                let val = JsValue::from(js_string!(res));
-                resolvers
-                    .resolve
-                    .call(&JsValue::undefined(), &[val], context)?;
-
-                Ok(JsValue::Undefined)
-            },
-            ctx,
-        );
-
-        let promise = base_promise(promise, ctx);
-
-        Ok(promise.into())
-    });
-
-    result
+    let path = checked_path_from_args(args, PermissionKind::Read, ctx)?;
+
+    let promise = promise_from_future(
+        async move { read_text_file_async_base(&path).await },
+        |contents, _| Ok(js_string!(contents).into()),
+        ctx,
+    );
+
+    Ok(base_promise(promise, ctx).into())
 }
 
 // TODO: implement FsFile and return it in both create_file_sync and create_file_async
 
 pub fn create_file_sync_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
-    let str_path = resolve_path_from_args(args, ctx)?;
-    let str_path = &str_path.to_std_string().unwrap();
-    let path = Path::new(&str_path);
+    let path = checked_path_from_args(args, PermissionKind::Write, ctx)?;
 
-    match FileSystem::create_file_sync(path) {
+    match FileSystem::create_file_sync(&path) {
         Ok(_) => Ok(JsValue::undefined()),
         Err(err) => Err(JsNativeError::error().with_message(err.to_string()).into()),
     }
 }
 
 pub fn create_file_async_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
-    let str_path = resolve_path_from_args(args, ctx)?;
-    let str_path = str_path.to_std_string().unwrap();
-    let path = Path::new(&str_path);
-
-    let result = block_on(async move {
-        let result = FileSystem::create_file_async(path)
-            .await
-            .map_err(|e| JsNativeError::error().with_message(e.to_string()))
-            .err();
-        let promise = JsPromise::new(
-            |resolvers: &ResolvingFunctions, context| {
-                if let Some(err) = result {
-                    return Err(err.into());
-                }
-
-                resolvers
-                    .resolve
-                    .call(&JsValue::undefined(), &[JsValue::undefined()], context)?;
-                Ok(JsValue::undefined())
-            },
-            ctx,
-        );
-
-        let promise = base_promise(promise, ctx);
-
-        Ok(promise.into())
-    });
-
-    result
+    let path = checked_path_from_args(args, PermissionKind::Write, ctx)?;
+
+    let promise = promise_from_future(
+        async move { FileSystem::create_file_async(&path).await },
+        |_, _| Ok(JsValue::undefined()),
+        ctx,
+    );
+
+    Ok(base_promise(promise, ctx).into())
 }
@@ -1,17 +1,15 @@
 use anyhow::{anyhow, Result};
-use boa_engine::builtins::promise::ResolvingFunctions;
-use boa_engine::object::builtins::JsPromise;
 use boa_engine::{Context, JsNativeError, JsResult, JsString, JsValue};
 use dir::get_recursive_flag;
 use ike_core::module;
-use ike_core::promise::base_promise;
+use ike_core::permissions::{check_path, PermissionKind};
+use ike_core::promise::{base_promise, promise_from_future};
 use ike_core::throw;
-use smol::block_on;
+use smol::unblock;
 use std::env::current_dir;
 use std::io::Read;
 use std::path::{Component, Path, PathBuf};
 use std::{fs, io};
-use tokio::task::spawn_blocking;
 
 pub mod dir;
 pub mod files;
@@ -26,6 +24,19 @@ pub fn resolve_path_from_args(args: &[JsValue], ctx: &mut Context) -> JsResult<J
     Ok(path)
 }
 
+/// The path an fs function is called with, if the sandbox allows `kind` access to it.
+pub fn checked_path_from_args(
+    args: &[JsValue],
+    kind: PermissionKind,
+    ctx: &mut Context,
+) -> JsResult<PathBuf> {
+    let path = resolve_path_from_args(args, ctx)?;
+    let path = PathBuf::from(path.to_std_string_escaped());
+    check_path(kind, &path, ctx)?;
+
+    Ok(path)
+}
+
 pub struct FileSystem {}
 
 impl FileSystem {
@@ -77,7 +88,7 @@ impl FileSystem {
 
     pub async fn remove_async(path: &Path, recursive: bool) -> std::io::Result<()> {
         let path = path.to_owned();
-        spawn_blocking(move || Self::remove(path.as_path(), recursive)).await?
+        unblock(move || Self::remove(path.as_path(), recursive)).await
     }
 
     pub fn create_dir(path: &Path, recursive: bool, mode: u32) -> std::io::Result<()> {
@@ -95,7 +106,7 @@ impl FileSystem {
 
     pub async fn create_dir_async(path: &Path, recursive: bool, mode: u32) -> std::io::Result<()> {
         let path = path.to_owned();
-        spawn_blocking(move || Self::create_dir(path.as_path(), recursive, mode)).await?
+        unblock(move || Self::create_dir(path.as_path(), recursive, mode)).await
     }
 
     pub fn create_file_sync(path: &Path) -> std::io::Result<fs::File> {
@@ -104,61 +115,37 @@ impl FileSystem {
 
     pub async fn create_file_async(path: &Path) -> std::io::Result<fs::File> {
         let path = path.to_owned();
-        spawn_blocking(move || fs::File::create(&path)).await?
+        unblock(move || fs::File::create(&path)).await
     }
 }
 
 pub fn remove_sync_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
-    let str_path = resolve_path_from_args(args, ctx)?;
-    let str_path = &str_path.to_std_string().unwrap();
-    let path = Path::new(&str_path);
+    let path = checked_path_from_args(args, PermissionKind::Write, ctx)?;
     let recursive = get_recursive_flag(args, ctx)?;
 
-    match FileSystem::remove(path, recursive) {
+    match FileSystem::remove(&path, recursive) {
         Ok(_) => Ok(JsValue::undefined()),
         Err(err) => Err(JsNativeError::error().with_message(err.to_string()).into()),
     }
 }
 
 pub fn remove_async_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
-    let str_path = resolve_path_from_args(args, ctx)?;
-    let str_path = &str_path.to_std_string().unwrap();
-    let path = Path::new(&str_path);
+    let path = checked_path_from_args(args, PermissionKind::Write, ctx)?;
     let recursive = get_recursive_flag(args, ctx)?;
 
-    let result = block_on(async {
-        let result = FileSystem::remove_async(path, recursive)
-            .await
-            .map_err(|e| JsNativeError::error().with_message(e.to_string()))
-            .err();
-        let promise = JsPromise::new(
-            |resolvers: &ResolvingFunctions, context| {
-                if result.is_some() {
-                    return Err(result.unwrap().into());
-                }
-
-                resolvers
-                    .resolve
-                    .call(&JsValue::undefined(), &[JsValue::undefined()], context)?;
-                Ok(JsValue::undefined())
-            },
-            ctx,
-        );
-
-        let promise = base_promise(promise, ctx);
-
-        Ok(promise.into())
-    });
+    let promise = promise_from_future(
+        async move { FileSystem::remove_async(&path, recursive).await },
+        |_, _| Ok(JsValue::undefined()),
+        ctx,
+    );
 
-    result
+    Ok(base_promise(promise, ctx).into())
 }
 
 pub fn exists_sync_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
-    let str_path = resolve_path_from_args(args, ctx)?;
-    let str_path = &str_path.to_std_string().unwrap();
-    let path = Path::new(&str_path);
+    let path = checked_path_from_args(args, PermissionKind::Read, ctx)?;
 
-    Ok(JsValue::from(FileSystem::exists_sync(path)))
+    Ok(JsValue::from(FileSystem::exists_sync(&path)))
 }
 
 pub struct File {
@@ -183,14 +170,12 @@ impl File {
 
     pub async fn read_async(self) -> Result<Vec<u8>, io::Error> {
         let mut file = self.file;
-        let buf: Vec<u8> = spawn_blocking(move || {
+        unblock(move || {
             let mut buf = Vec::new();
             file.read_to_end(&mut buf)?;
-            Ok::<Vec<u8>, io::Error>(buf)
+            Ok(buf)
         })
-        .await??;
-
-        Ok(buf)
+        .await
     }
 }
 
@@ -0,0 +1,168 @@
+declare module 'module:process/process.js' {
+  /**
+   * Where a stdio stream of a child goes. `pipe` exposes it as a web stream, `inherit`
+   * shares it with Ike and `null` discards it.
+   */
+  type Stdio = 'pipe' | 'inherit' | 'null';
+
+  /**
+   * Name of a signal. On Windows only `SIGINT` and `SIGBREAK` can be listened for, they
+   * are sent by Ctrl-C and Ctrl-Break.
+   */
+  type Signal =
+    | 'SIGABRT'
+    | 'SIGALRM'
+    | 'SIGBREAK'
+    | 'SIGCHLD'
+    | 'SIGCONT'
+    | 'SIGHUP'
+    | 'SIGINT'
+    | 'SIGKILL'
+    | 'SIGPIPE'
+    | 'SIGQUIT'
+    | 'SIGSTOP'
+    | 'SIGTERM'
+    | 'SIGTSTP'
+    | 'SIGUSR1'
+    | 'SIGUSR2'
+    | 'SIGWINCH';
+
+  type SpawnOptions = {
+    /**
+     * Directory the command runs in, the current working directory by default.
+     */
+    cwd?: string;
+    /**
+     * Variables added to the environment the child inherits. A `PATH` in here is also
+     * where the command is looked up.
+     */
+    env?: Record<string, string>;
+    /**
+     * @default 'inherit'
+     */
+    stdin?: Stdio;
+    /**
+     * @default 'pipe'
+     */
+    stdout?: Stdio;
+    /**
+     * @default 'pipe'
+     */
+    stderr?: Stdio;
+  };
+
+  type ChildStatus = {
+    /**
+     * Exit code, `null` if the child was killed by a signal.
+     */
+    code: number | null;
+    /**
+     * Signal that killed the child, e.g. `SIGTERM`. Always `null` on Windows.
+     */
+    signal: string | null;
+    success: boolean;
+  };
+
+  type SpawnSyncResult = ChildStatus & {
+    pid: number;
+    /**
+     * Output of the child, `null` unless it's piped.
+     */
+    stdout: Uint8Array | null;
+    stderr: Uint8Array | null;
+  };
+
+  type ExecResult = ChildStatus & {
+    stdout: string;
+    stderr: string;
+  };
+
+  class ChildProcess {
+    readonly pid: number;
+    /**
+     * Resolves once the child exited.
+     */
+    readonly status: Promise<ChildStatus>;
+    readonly stdin: WritableStream<Uint8Array | string> | null;
+    readonly stdout: ReadableStream<Uint8Array> | null;
+    readonly stderr: ReadableStream<Uint8Array> | null;
+
+    /**
+     * Sends a signal to the child. Windows has no signals, the child is always
+     * terminated there.
+     *
+     * @param signal Name of the signal, `SIGTERM` by default.
+     */
+    kill(signal?: Signal): void;
+  }
+
+  /**
+   * Spawns a child process. The command is looked up like `Ike.which` does.
+   *
+   * @example
+   * ```ts
+   * const child = Ike.spawn('git', ['status'], { cwd: 'repo' });
+   *
+   * for await (const chunk of child.stdout) {
+   *   console.log(new TextDecoder().decode(chunk));
+   * }
+   * const { code } = await child.status;
+   * ```
+   *
+   * @throws {Error} If the command can't be found or spawned.
+   */
+  function spawn(command: string, args?: string[], options?: SpawnOptions): ChildProcess;
+
+  /**
+   * Runs a child process to completion and blocks until it exited. stdin can't be piped.
+   *
+   * @example
+   * ```ts
+   * const { success, stdout } = Ike.spawnSync('git', ['rev-parse', 'HEAD']);
+   * ```
+   */
+  function spawnSync(command: string, args?: string[], options?: SpawnOptions): SpawnSyncResult;
+
+  /**
+   * Runs a child process and collects its output as text.
+   *
+   * @example
+   * ```ts
+   * const { stdout } = await Ike.exec('ls', ['-la']);
+   * ```
+   */
+  function exec(command: string, args?: string[], options?: SpawnOptions): Promise<ExecResult>;
+
+  /**
+   * Sends a signal to a process. Windows has no signals, the process is always
+   * terminated there.
+   *
+   * @param signal `SIGTERM` by default.
+   * @throws {TypeError} If the signal is unknown.
+   * @throws {Error} If the process doesn't exist or can't be signaled.
+   */
+  function kill(pid: number, signal?: Signal): void;
+
+  /**
+   * Calls `listener` when the process receives `signal`, instead of the default behavior
+   * like exiting on `SIGINT`. The process keeps running while listeners are registered.
+   *
+   * @example
+   * ```ts
+   * const shutdown = () => {
+   *   server.close();
+   *   Ike.removeSignalListener('SIGINT', shutdown);
+   * };
+   * Ike.addSignalListener('SIGINT', shutdown);
+   * ```
+   *
+   * @throws {TypeError} If the signal is unknown or can't be listened for, like `SIGKILL`.
+   */
+  function addSignalListener(signal: Signal, listener: (signal: Signal) => void): void;
+
+  /**
+   * Removes a listener added with `addSignalListener`. Once a signal has no listeners it
+   * gets its default behavior back.
+   */
+  function removeSignalListener(signal: Signal, listener: (signal: Signal) => void): void;
+}
@@ -0,0 +1,625 @@
+use boa_engine::{
+    builtins::promise::ResolvingFunctions,
+    job::NativeJob,
+    js_string,
+    object::builtins::{JsArray, JsArrayBuffer, JsPromise, JsTypedArray, JsUint8Array},
+    property::PropertyKey,
+    Context, JsError, JsNativeError, JsObject, JsResult, JsValue,
+};
+use ike_core::{
+    module,
+    permissions::{self, PermissionKind},
+    throw,
+};
+use ike_fs::which;
+use signal::{
+    add_signal_listener_ex, kill_ex, remove_signal_listener_ex, send_signal, signal_from_js,
+};
+use smol::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    process::{Child, ExitStatus, Stdio},
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    path::PathBuf,
+};
+
+mod signal;
+
+/// Size of the chunks stdout and stderr are read in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StdioKind {
+    Pipe,
+    Inherit,
+    Null,
+}
+
+impl StdioKind {
+    fn from_value(value: &JsValue, default: Self, ctx: &mut Context) -> JsResult<Self> {
+        if value.is_undefined() {
+            return Ok(default);
+        }
+
+        match value.to_string(ctx)?.to_std_string_escaped().as_str() {
+            "pipe" => Ok(Self::Pipe),
+            "inherit" => Ok(Self::Inherit),
+            "null" => Ok(Self::Null),
+            other => throw!(
+                typ,
+                "Invalid stdio `{}`, expected `pipe`, `inherit` or `null`",
+                other
+            ),
+        }
+    }
+
+    fn stdio(self) -> Stdio {
+        match self {
+            Self::Pipe => Stdio::piped(),
+            Self::Inherit => Stdio::inherit(),
+            Self::Null => Stdio::null(),
+        }
+    }
+}
+
+/// What `spawn_ex` and `spawn_sync_ex` are called with: `(command, args, options)`.
+struct SpawnOptions {
+    program: PathBuf,
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    stdin: StdioKind,
+    stdout: StdioKind,
+    stderr: StdioKind,
+}
+
+impl SpawnOptions {
+    fn from_args(args: &[JsValue], ctx: &mut Context) -> JsResult<Self> {
+        let command = match args.first() {
+            Some(command) if command.is_string() => command.to_string(ctx)?,
+            _ => throw!(typ, "Expected a command to spawn"),
+        };
+        let command = command.to_std_string_escaped();
+
+        let mut command_args = Vec::new();
+        match args.get(1) {
+            None => {}
+            Some(value) if value.is_undefined() => {}
+            Some(value) => {
+                let array = match value.as_object() {
+                    Some(object) if object.is_array() => JsArray::from_object(object.clone())?,
+                    _ => throw!(typ, "Arguments must be an array of strings"),
+                };
+                for index in 0..array.length(ctx)? {
+                    let arg = array.get(index, ctx)?.to_string(ctx)?;
+                    command_args.push(arg.to_std_string_escaped());
+                }
+            }
+        }
+
+        let options = args.get(2).and_then(JsValue::as_object).cloned();
+        let option = |name: &str, ctx: &mut Context| -> JsResult<JsValue> {
+            match &options {
+                Some(options) => options.get(js_string!(name), ctx),
+                None => Ok(JsValue::undefined()),
+            }
+        };
+
+        let cwd = match option("cwd", ctx)? {
+            value if value.is_undefined() => None,
+            value => Some(PathBuf::from(value.to_string(ctx)?.to_std_string_escaped())),
+        };
+
+        let mut env = Vec::new();
+        if let Some(object) = option("env", ctx)?.as_object() {
+            for key in object.own_property_keys(ctx)? {
+                if let PropertyKey::String(name) = &key {
+                    let value = object.get(key.clone(), ctx)?;
+                    if !value.is_undefined() {
+                        let value = value.to_string(ctx)?.to_std_string_escaped();
+                        env.push((name.to_std_string_escaped(), value));
+                    }
+                }
+            }
+        }
+
+        let stdin = StdioKind::from_value(&option("stdin", ctx)?, StdioKind::Inherit, ctx)?;
+        let stdout = StdioKind::from_value(&option("stdout", ctx)?, StdioKind::Pipe, ctx)?;
+        let stderr = StdioKind::from_value(&option("stderr", ctx)?, StdioKind::Pipe, ctx)?;
+
+        // A PATH passed in `env` is where the command is looked up
+        let path = env
+            .iter()
+            .find(|(key, _)| key == "PATH")
+            .map(|(_, value)| value.clone());
+        let program = match which(&command, path, cwd.clone()) {
+            Some(program) => program,
+            None => throw!(err, "Command `{}` not found", command),
+        };
+
+        // `--allow-run=git` allows `git` as well as the program it resolves to
+        if !permissions::is_granted(PermissionKind::Run, Some(&program.to_string_lossy()), ctx) {
+            permissions::check(PermissionKind::Run, Some(&command), ctx)?;
+        }
+
+        Ok(Self {
+            program,
+            args: command_args,
+            cwd,
+            env,
+            stdin,
+            stdout,
+            stderr,
+        })
+    }
+
+    fn command(&self) -> std::process::Command {
+        let mut command = std::process::Command::new(&self.program);
+        command
+            .args(&self.args)
+            .envs(self.env.iter().cloned())
+            .stdin(self.stdin.stdio())
+            .stdout(self.stdout.stdio())
+            .stderr(self.stderr.stdio());
+
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(cwd);
+        }
+
+        command
+    }
+}
+
+/// A pipe of a child. `Busy` while a read or write is in flight, the pipe is moved into it.
+enum Pipe<T> {
+    Open(T),
+    Busy,
+    Closed,
+}
+
+impl<T> Pipe<T> {
+    fn new(pipe: Option<T>) -> Self {
+        match pipe {
+            Some(pipe) => Self::Open(pipe),
+            None => Self::Closed,
+        }
+    }
+
+    fn take(&mut self) -> Option<T> {
+        match std::mem::replace(self, Self::Busy) {
+            Self::Open(pipe) => Some(pipe),
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+}
+
+type Output = Box<dyn AsyncRead + Unpin>;
+type Input = Box<dyn AsyncWrite + Unpin>;
+
+struct ChildEntry {
+    child: Child,
+    stdin: Pipe<Input>,
+    /// stdout and stderr
+    outputs: [Pipe<Output>; 2],
+    exited: bool,
+}
+
+impl ChildEntry {
+    /// Nothing is left to do with a child once it exited and its pipes are closed.
+    fn is_done(&self) -> bool {
+        self.exited
+            && matches!(self.stdin, Pipe::Closed)
+            && self
+                .outputs
+                .iter()
+                .all(|output| matches!(output, Pipe::Closed))
+    }
+}
+
+thread_local! {
+    static CHILDREN: RefCell<HashMap<u32, ChildEntry>> = RefCell::default();
+    static NEXT_ID: Cell<u32> = const { Cell::new(0) };
+}
+
+fn with_child<R>(id: u32, f: impl FnOnce(&mut ChildEntry) -> R) -> JsResult<R> {
+    CHILDREN.with_borrow_mut(|children| match children.get_mut(&id) {
+        Some(entry) => Ok(f(entry)),
+        None => Err(JsNativeError::error()
+            .with_message(format!("Unknown child process {}", id))
+            .into()),
+    })
+}
+
+/// Drops the child once it's done, see [`ChildEntry::is_done`].
+fn release(id: u32) {
+    CHILDREN.with_borrow_mut(|children| {
+        if children.get(&id).is_some_and(ChildEntry::is_done) {
+            children.remove(&id);
+        }
+    });
+}
+
+fn child_id(args: &[JsValue], ctx: &mut Context) -> JsResult<u32> {
+    match args.first() {
+        Some(id) if id.is_number() => id.to_u32(ctx),
+        _ => throw!(typ, "Expected a child process id"),
+    }
+}
+
+fn status_to_js(status: ExitStatus, ctx: &mut Context) -> JsResult<JsValue> {
+    #[cfg(unix)]
+    let signal = {
+        use std::os::unix::process::ExitStatusExt;
+
+        status
+            .signal()
+            .and_then(|signal| nix::sys::signal::Signal::try_from(signal).ok())
+            .map(|signal| signal.as_str())
+    };
+    #[cfg(not(unix))]
+    let signal: Option<&str> = None;
+
+    let object = JsObject::with_object_proto(ctx.intrinsics());
+    let code = match status.code() {
+        Some(code) => JsValue::from(code),
+        None => JsValue::null(),
+    };
+    let signal = match signal {
+        Some(signal) => JsValue::from(js_string!(signal)),
+        None => JsValue::null(),
+    };
+    object.set(js_string!("code"), code, false, ctx)?;
+    object.set(js_string!("signal"), signal, false, ctx)?;
+    object.set(js_string!("success"), status.success(), false, ctx)?;
+
+    Ok(object.into())
+}
+
+fn bytes_to_js(bytes: Vec<u8>, ctx: &mut Context) -> JsResult<JsValue> {
+    let buffer = JsArrayBuffer::from_byte_block(bytes, ctx)?;
+    Ok(JsUint8Array::from_array_buffer(buffer, ctx)?.into())
+}
+
+fn bytes_from_js(value: &JsValue, ctx: &mut Context) -> JsResult<Vec<u8>> {
+    let array = match value
+        .as_object()
+        .map(|object| JsTypedArray::from_object(object.clone()))
+    {
+        Some(Ok(array)) => array,
+        _ => throw!(typ, "Expected a Uint8Array"),
+    };
+    let offset = array.byte_offset(ctx)?;
+    let length = array.byte_length(ctx)?;
+    let buffer = array.buffer(ctx)?;
+    let buffer = match buffer.as_object() {
+        Some(buffer) => JsArrayBuffer::from_object(buffer.clone())?,
+        None => throw!(typ, "Expected a Uint8Array"),
+    };
+
+    let data = buffer.data();
+    Ok(data
+        .as_deref()
+        .and_then(|data| data.get(offset..offset + length))
+        .map(<[u8]>::to_vec)
+        .unwrap_or_default())
+}
+
+fn io_error(err: std::io::Error) -> JsError {
+    JsNativeError::error().with_message(err.to_string()).into()
+}
+
+/// Settles `resolvers` from a job, once the future that produced `result` finished.
+fn settle(
+    resolvers: ResolvingFunctions,
+    result: JsResult<JsValue>,
+    ctx: &mut Context,
+) -> JsResult<JsValue> {
+    match result {
+        Ok(value) => resolvers
+            .resolve
+            .call(&JsValue::undefined(), &[value], ctx)?,
+        Err(err) => {
+            let err = err.to_opaque(ctx);
+            resolvers.reject.call(&JsValue::undefined(), &[err], ctx)?
+        }
+    };
+
+    Ok(JsValue::undefined())
+}
+
+/// Spawns a child process and returns `{ id, pid, stdin, stdout, stderr }`, the stdio
+/// fields tell which of them are piped.
+pub fn spawn_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let options = SpawnOptions::from_args(args, ctx)?;
+
+    let mut child = match smol::process::Command::from(options.command()).spawn() {
+        Ok(child) => child,
+        Err(err) => throw!(
+            err,
+            "Failed to spawn `{}`: {}",
+            options.program.display(),
+            err
+        ),
+    };
+    let pid = child.id();
+
+    let stdin = Pipe::new(child.stdin.take().map(|stdin| Box::new(stdin) as Input));
+    let stdout = Pipe::new(child.stdout.take().map(|stdout| Box::new(stdout) as Output));
+    let stderr = Pipe::new(child.stderr.take().map(|stderr| Box::new(stderr) as Output));
+
+    let id = NEXT_ID.get();
+    NEXT_ID.set(id + 1);
+    CHILDREN.with_borrow_mut(|children| {
+        children.insert(
+            id,
+            ChildEntry {
+                child,
+                stdin,
+                outputs: [stdout, stderr],
+                exited: false,
+            },
+        )
+    });
+
+    let object = JsObject::with_object_proto(ctx.intrinsics());
+    object.set(js_string!("id"), id, false, ctx)?;
+    object.set(js_string!("pid"), pid, false, ctx)?;
+    object.set(
+        js_string!("stdin"),
+        options.stdin == StdioKind::Pipe,
+        false,
+        ctx,
+    )?;
+    object.set(
+        js_string!("stdout"),
+        options.stdout == StdioKind::Pipe,
+        false,
+        ctx,
+    )?;
+    object.set(
+        js_string!("stderr"),
+        options.stderr == StdioKind::Pipe,
+        false,
+        ctx,
+    )?;
+
+    Ok(object.into())
+}
+
+/// Resolves to `{ code, signal, success }` once the child exited.
+pub fn child_wait_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let id = child_id(args, ctx)?;
+    let status = with_child(id, |entry| entry.child.status())?;
+    let (promise, resolvers) = JsPromise::new_pending(ctx);
+
+    let wait = async move {
+        let status = status.await;
+
+        NativeJob::new(move |ctx| {
+            let _ = with_child(id, |entry| entry.exited = true);
+            release(id);
+
+            let result = status
+                .map_err(io_error)
+                .and_then(|status| status_to_js(status, ctx));
+            settle(resolvers, result, ctx)
+        })
+    };
+    ctx.job_queue().enqueue_future_job(Box::pin(wait), ctx);
+
+    Ok(promise.into())
+}
+
+/// Resolves to the next chunk of stdout (`0`) or stderr (`1`), `null` once it ended.
+pub fn child_read_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let id = child_id(args, ctx)?;
+    let index = match args.get(1).map(|index| index.to_u32(ctx)).transpose()? {
+        Some(index @ (0 | 1)) => index as usize,
+        _ => throw!(typ, "Expected 0 for stdout or 1 for stderr"),
+    };
+    let (promise, resolvers) = JsPromise::new_pending(ctx);
+
+    let output = match with_child(id, |entry| {
+        let closed = matches!(entry.outputs[index], Pipe::Closed);
+        (entry.outputs[index].take(), closed)
+    })? {
+        (Some(output), _) => output,
+        (None, true) => {
+            settle(resolvers, Ok(JsValue::null()), ctx)?;
+            return Ok(promise.into());
+        }
+        (None, false) => throw!(err, "Output of child process {} is already being read", id),
+    };
+
+    let read = async move {
+        let mut output = output;
+        let mut buffer = vec![0; CHUNK_SIZE];
+        let result = output.read(&mut buffer).await;
+
+        NativeJob::new(move |ctx| {
+            let result = match result {
+                Ok(0) | Err(_) => {
+                    let _ = with_child(id, |entry| entry.outputs[index] = Pipe::Closed);
+                    release(id);
+                    result.map(|_| JsValue::null()).map_err(io_error)
+                }
+                Ok(read) => {
+                    let _ = with_child(id, |entry| entry.outputs[index] = Pipe::Open(output));
+                    buffer.truncate(read);
+                    bytes_to_js(buffer, ctx)
+                }
+            };
+
+            settle(resolvers, result, ctx)
+        })
+    };
+    ctx.job_queue().enqueue_future_job(Box::pin(read), ctx);
+
+    Ok(promise.into())
+}
+
+/// Writes a Uint8Array to stdin of the child.
+pub fn child_write_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let id = child_id(args, ctx)?;
+    let bytes = bytes_from_js(args.get(1).unwrap_or(&JsValue::undefined()), ctx)?;
+    let (promise, resolvers) = JsPromise::new_pending(ctx);
+
+    let input = match with_child(id, |entry| entry.stdin.take())? {
+        Some(input) => input,
+        None => throw!(err, "stdin of child process {} is closed", id),
+    };
+
+    let write = async move {
+        let mut input = input;
+        let mut result = input.write_all(&bytes).await;
+        if result.is_ok() {
+            result = input.flush().await;
+        }
+
+        NativeJob::new(move |ctx| {
+            let result = match result {
+                Ok(()) => {
+                    let _ = with_child(id, |entry| entry.stdin = Pipe::Open(input));
+                    Ok(JsValue::undefined())
+                }
+                Err(err) => {
+                    let _ = with_child(id, |entry| entry.stdin = Pipe::Closed);
+                    release(id);
+                    Err(io_error(err))
+                }
+            };
+
+            settle(resolvers, result, ctx)
+        })
+    };
+    ctx.job_queue().enqueue_future_job(Box::pin(write), ctx);
+
+    Ok(promise.into())
+}
+
+/// Closes stdin of the child, so it sees the end of its input.
+pub fn child_close_stdin_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let id = child_id(args, ctx)?;
+    with_child(id, |entry| {
+        if !matches!(entry.stdin, Pipe::Busy) {
+            entry.stdin = Pipe::Closed;
+        }
+    })?;
+    release(id);
+
+    Ok(JsValue::undefined())
+}
+
+/// Stops reading stdout (`0`) or stderr (`1`) of the child.
+pub fn child_close_output_ex(
+    _: &JsValue,
+    args: &[JsValue],
+    ctx: &mut Context,
+) -> JsResult<JsValue> {
+    let id = child_id(args, ctx)?;
+    let index = args.get(1).map(|index| index.to_u32(ctx)).transpose()?;
+    with_child(id, |entry| {
+        if let Some(output) = index.and_then(|index| entry.outputs.get_mut(index as usize)) {
+            if !matches!(output, Pipe::Busy) {
+                *output = Pipe::Closed;
+            }
+        }
+    })?;
+    release(id);
+
+    Ok(JsValue::undefined())
+}
+
+/// Sends a signal to the child, `SIGTERM` by default. Windows has no signals, the child is
+/// always terminated there.
+pub fn child_kill_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let id = child_id(args, ctx)?;
+    let signo = signal_from_js(args.get(1), ctx)?;
+
+    let result = with_child(id, |entry| -> JsResult<()> {
+        // The pid could belong to another process by now
+        if entry.exited {
+            return Ok(());
+        }
+
+        #[cfg(unix)]
+        {
+            send_signal(entry.child.id() as i32, signo)
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = signo;
+            entry.child.kill().map_err(io_error)
+        }
+    });
+
+    match result {
+        // Killing a child that is gone already is not an error
+        Err(_) => Ok(JsValue::undefined()),
+        Ok(result) => result.map(|_| JsValue::undefined()),
+    }
+}
+
+/// Runs a child to completion and returns `{ pid, code, signal, success, stdout, stderr }`.
+/// `stdin` can't be piped as there is nothing to write to it while blocking.
+pub fn spawn_sync_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    let mut options = SpawnOptions::from_args(args, ctx)?;
+    if options.stdin == StdioKind::Pipe {
+        options.stdin = StdioKind::Null;
+    }
+
+    let child = match options.command().spawn() {
+        Ok(child) => child,
+        Err(err) => throw!(
+            err,
+            "Failed to spawn `{}`: {}",
+            options.program.display(),
+            err
+        ),
+    };
+    let pid = child.id();
+    let output = match child.wait_with_output() {
+        Ok(output) => output,
+        Err(err) => throw!(
+            err,
+            "Failed to wait for `{}`: {}",
+            options.program.display(),
+            err
+        ),
+    };
+
+    let result = status_to_js(output.status, ctx)?;
+    let object = result.as_object().expect("status is an object");
+    let stdout = match options.stdout {
+        StdioKind::Pipe => bytes_to_js(output.stdout, ctx)?,
+        _ => JsValue::null(),
+    };
+    let stderr = match options.stderr {
+        StdioKind::Pipe => bytes_to_js(output.stderr, ctx)?,
+        _ => JsValue::null(),
+    };
+    object.set(js_string!("pid"), pid, false, ctx)?;
+    object.set(js_string!("stdout"), stdout, false, ctx)?;
+    object.set(js_string!("stderr"), stderr, false, ctx)?;
+
+    Ok(result)
+}
+
+module!(ProcessModule, "process", js = ["process.js"], exposed = {
+    "spawn_ex" => spawn_ex,
+    "spawn_sync_ex" => spawn_sync_ex,
+    "child_wait_ex" => child_wait_ex,
+    "child_read_ex" => child_read_ex,
+    "child_write_ex" => child_write_ex,
+    "child_close_stdin_ex" => child_close_stdin_ex,
+    "child_close_output_ex" => child_close_output_ex,
+    "child_kill_ex" => child_kill_ex,
+    "add_signal_listener_ex" => add_signal_listener_ex,
+    "remove_signal_listener_ex" => remove_signal_listener_ex,
+    "kill_ex" => kill_ex,
+},);
@@ -0,0 +1,122 @@
+const STDOUT = 0;
+const STDERR = 1;
+
+const readable = (id, index) => {
+  return new ReadableStream({
+    async pull(controller) {
+      const chunk = await child_read_ex(id, index);
+
+      if (chunk === null) {
+        controller.close();
+      } else {
+        controller.enqueue(chunk);
+      }
+    },
+    cancel() {
+      child_close_output_ex(id, index);
+    },
+  });
+};
+
+const writable = (id) => {
+  const encoder = new TextEncoder();
+
+  return new WritableStream({
+    async write(chunk) {
+      const bytes = typeof chunk === 'string' ? encoder.encode(chunk) : chunk;
+      await child_write_ex(id, bytes);
+    },
+    close() {
+      child_close_stdin_ex(id);
+    },
+    abort() {
+      child_close_stdin_ex(id);
+    },
+  });
+};
+
+class ChildProcess {
+  #id;
+
+  constructor(child) {
+    this.#id = child.id;
+    this.pid = child.pid;
+    this.status = child_wait_ex(child.id);
+    this.stdin = child.stdin ? writable(child.id) : null;
+    this.stdout = child.stdout ? readable(child.id, STDOUT) : null;
+    this.stderr = child.stderr ? readable(child.id, STDERR) : null;
+  }
+
+  kill(signal = 'SIGTERM') {
+    child_kill_ex(this.#id, signal);
+  }
+}
+
+const spawn = (command, args = [], options = {}) => {
+  return new ChildProcess(spawn_ex(command, args, options));
+};
+
+const spawnSync = (command, args = [], options = {}) => {
+  return spawn_sync_ex(command, args, options);
+};
+
+const collect = async (stream) => {
+  const chunks = [];
+  const reader = stream.getReader();
+
+  while (true) {
+    const { done, value } = await reader.read();
+    if (done) {
+      break;
+    }
+    chunks.push(value);
+  }
+
+  const bytes = new Uint8Array(
+    chunks.reduce((length, chunk) => length + chunk.length, 0),
+  );
+  let offset = 0;
+  for (const chunk of chunks) {
+    bytes.set(chunk, offset);
+    offset += chunk.length;
+  }
+
+  return new TextDecoder().decode(bytes);
+};
+
+const exec = async (command, args = [], options = {}) => {
+  const child = spawn(command, args, {
+    ...options,
+    stdout: 'pipe',
+    stderr: 'pipe',
+  });
+  const [stdout, stderr, status] = await Promise.all([
+    collect(child.stdout),
+    collect(child.stderr),
+    child.status,
+  ]);
+
+  return { ...status, stdout, stderr };
+};
+
+const addSignalListener = (signal, listener) => {
+  add_signal_listener_ex(signal, listener);
+};
+
+const removeSignalListener = (signal, listener) => {
+  remove_signal_listener_ex(signal, listener);
+};
+
+const kill = (pid, signal = 'SIGTERM') => {
+  kill_ex(pid, signal);
+};
+
+export {
+  addSignalListener,
+  ChildProcess,
+  exec,
+  kill,
+  removeSignalListener,
+  spawn,
+  spawnSync,
+};
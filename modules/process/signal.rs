@@ -0,0 +1,525 @@
+//! Signal listeners. The native handler is process wide, so signals that arrive are
+//! fanned out to every thread with listeners, like workers, which records them in its
+//! pending signals and wakes a future in its job queue. The listeners then run as a job.
+//! That future stays pending while listeners are registered, which keeps the runtime alive.
+
+use boa_engine::{
+    job::NativeJob, js_string, object::builtins::JsFunction, Context, JsNativeError, JsObject,
+    JsResult, JsValue,
+};
+use futures_util::future::{AbortHandle, Abortable};
+use ike_core::{
+    permissions::{self, PermissionKind},
+    throw,
+};
+use smol::channel::{bounded, Receiver, Sender};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    thread::{self, ThreadId},
+};
+
+/// A thread that listens for signals.
+struct Subscriber {
+    thread: ThreadId,
+    /// Signals the thread has listeners for, one bit per signal number.
+    signals: u64,
+    pending: Arc<AtomicU64>,
+    wake: Sender<()>,
+}
+
+/// Threads with listeners. The native handler of a signal is installed while one of them
+/// listens for it.
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+
+struct Listeners {
+    thread: ThreadId,
+    /// Listeners by signal number, in the order they were added.
+    handlers: BTreeMap<i32, Vec<JsFunction>>,
+    /// Stops waiting for signals once the last listener is removed.
+    abort: Option<AbortHandle>,
+    /// Signals fanned out to this thread since the listeners last ran.
+    pending: Arc<AtomicU64>,
+    wake: (Sender<()>, Receiver<()>),
+}
+
+impl Default for Listeners {
+    fn default() -> Self {
+        Self {
+            thread: thread::current().id(),
+            handlers: BTreeMap::new(),
+            abort: None,
+            pending: Arc::default(),
+            // A full channel already wakes the thread, so one message is enough
+            wake: bounded(1),
+        }
+    }
+}
+
+impl Drop for Listeners {
+    // Listeners of a worker that stopped don't keep the handlers installed
+    fn drop(&mut self) {
+        for signo in self.handlers.keys() {
+            unsubscribe(self.thread, *signo);
+        }
+    }
+}
+
+thread_local! {
+    static LISTENERS: RefCell<Listeners> = RefCell::default();
+}
+
+fn subscribers() -> std::sync::MutexGuard<'static, Vec<Subscriber>> {
+    SUBSCRIBERS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Hands signals that arrived to the threads that listen for them.
+fn fan_out(signals: u64) {
+    for subscriber in subscribers().iter() {
+        let pending = subscriber.signals & signals;
+        if pending != 0 {
+            subscriber.pending.fetch_or(pending, Ordering::SeqCst);
+            let _ = subscriber.wake.try_send(());
+        }
+    }
+}
+
+/// Starts listening for `signo` on the current thread, the native handler is installed
+/// for the first thread that listens for it.
+fn subscribe(signo: i32) -> io::Result<()> {
+    let (thread, pending, wake) = LISTENERS.with_borrow(|listeners| {
+        (
+            listeners.thread,
+            listeners.pending.clone(),
+            listeners.wake.0.clone(),
+        )
+    });
+    let mut subscribers = subscribers();
+
+    if !subscribers
+        .iter()
+        .any(|other| other.signals & (1 << signo) != 0)
+    {
+        sys::install(signo)?;
+    }
+
+    match subscribers.iter_mut().find(|other| other.thread == thread) {
+        Some(subscriber) => subscriber.signals |= 1 << signo,
+        None => subscribers.push(Subscriber {
+            thread,
+            signals: 1 << signo,
+            pending,
+            wake,
+        }),
+    }
+
+    Ok(())
+}
+
+/// Stops listening for `signo` on `thread`, the signal gets its default behavior back
+/// once no thread listens for it.
+fn unsubscribe(thread: ThreadId, signo: i32) {
+    let mut subscribers = subscribers();
+
+    if let Some(index) = subscribers.iter().position(|other| other.thread == thread) {
+        subscribers[index].signals &= !(1 << signo);
+        if subscribers[index].signals == 0 {
+            subscribers.swap_remove(index);
+        }
+    }
+
+    if !subscribers
+        .iter()
+        .any(|other| other.signals & (1 << signo) != 0)
+    {
+        sys::uninstall(signo);
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use nix::{
+        errno::Errno,
+        libc,
+        sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal},
+    };
+    use std::{
+        collections::BTreeMap,
+        io::{self, Read},
+        os::{fd::IntoRawFd, unix::net::UnixStream},
+        str::FromStr,
+        sync::{
+            atomic::{AtomicI32, AtomicU64, Ordering},
+            Mutex, PoisonError,
+        },
+    };
+
+    /// Signals that arrived since they were last fanned out, one bit per signal number.
+    static PENDING: AtomicU64 = AtomicU64::new(0);
+    /// Write end of the pipe that wakes the thread that fans signals out.
+    static WAKE_FD: AtomicI32 = AtomicI32::new(-1);
+    /// Actions the handlers replaced, they are restored once no thread listens anymore.
+    static PREVIOUS: Mutex<BTreeMap<i32, SigAction>> = Mutex::new(BTreeMap::new());
+
+    extern "C" fn handle(signo: libc::c_int) {
+        let errno = Errno::last_raw();
+
+        PENDING.fetch_or(1 << signo, Ordering::SeqCst);
+        let fd = WAKE_FD.load(Ordering::SeqCst);
+        if fd >= 0 {
+            // A full pipe already wakes the thread, the byte can be dropped
+            unsafe { libc::write(fd, [0u8].as_ptr().cast(), 1) };
+        }
+
+        Errno::set_raw(errno);
+    }
+
+    /// The handler can only do async signal safe things, so a thread of its own hands
+    /// the signals to the listening threads. It runs for the rest of the process.
+    fn start_fan_out() -> io::Result<()> {
+        let (mut reader, writer) = UnixStream::pair()?;
+        writer.set_nonblocking(true)?;
+
+        std::thread::Builder::new()
+            .name("ike-signals".to_string())
+            .spawn(move || {
+                let mut buffer = [0u8; 64];
+                loop {
+                    match reader.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(_) => super::fan_out(PENDING.swap(0, Ordering::SeqCst)),
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                        Err(_) => break,
+                    }
+                }
+            })?;
+        WAKE_FD.store(writer.into_raw_fd(), Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    pub fn parse(name: &str) -> Option<i32> {
+        Signal::from_str(name).ok().map(|signal| signal as i32)
+    }
+
+    pub fn name(signo: i32) -> &'static str {
+        Signal::try_from(signo).map_or("UNKNOWN", Signal::as_str)
+    }
+
+    pub fn can_listen(signo: i32) -> bool {
+        !matches!(
+            Signal::try_from(signo),
+            Ok(Signal::SIGKILL | Signal::SIGSTOP)
+        )
+    }
+
+    /// Called with the subscribers locked, so only one thread installs at a time.
+    pub fn install(signo: i32) -> io::Result<()> {
+        if WAKE_FD.load(Ordering::SeqCst) < 0 {
+            start_fan_out()?;
+        }
+
+        let signal = Signal::try_from(signo)?;
+        let action = SigAction::new(
+            SigHandler::Handler(handle),
+            SaFlags::SA_RESTART,
+            SigSet::empty(),
+        );
+        let previous = unsafe { sigaction(signal, &action) }?;
+        PREVIOUS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(signo, previous);
+
+        Ok(())
+    }
+
+    pub fn uninstall(signo: i32) {
+        let previous = PREVIOUS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&signo);
+
+        if let (Some(previous), Ok(signal)) = (previous, Signal::try_from(signo)) {
+            let _ = unsafe { sigaction(signal, &previous) };
+        }
+    }
+
+    pub fn kill(pid: i32, signo: i32) -> io::Result<()> {
+        let signal = Signal::try_from(signo)?;
+        nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid), signal)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::{
+        io,
+        sync::atomic::{AtomicU64, Ordering},
+    };
+    use winapi::{
+        shared::minwindef::{BOOL, DWORD, FALSE, TRUE},
+        um::{
+            consoleapi::SetConsoleCtrlHandler,
+            handleapi::CloseHandle,
+            processthreadsapi::{OpenProcess, TerminateProcess},
+            wincon::{CTRL_BREAK_EVENT, CTRL_C_EVENT},
+            winnt::PROCESS_TERMINATE,
+        },
+    };
+
+    const SIGINT: i32 = 2;
+    const SIGBREAK: i32 = 21;
+
+    /// Signals that have a listener, the others are left to the default handler.
+    static INSTALLED: AtomicU64 = AtomicU64::new(0);
+
+    // Runs on a thread of its own, so it can hand the signal out itself
+    unsafe extern "system" fn handle(event: DWORD) -> BOOL {
+        let signo = match event {
+            CTRL_C_EVENT => SIGINT,
+            CTRL_BREAK_EVENT => SIGBREAK,
+            _ => return FALSE,
+        };
+        if INSTALLED.load(Ordering::SeqCst) & (1 << signo) == 0 {
+            return FALSE;
+        }
+
+        super::fan_out(1 << signo);
+        TRUE
+    }
+
+    pub fn parse(name: &str) -> Option<i32> {
+        match name {
+            "SIGINT" => Some(SIGINT),
+            "SIGBREAK" => Some(SIGBREAK),
+            "SIGTERM" => Some(15),
+            "SIGKILL" => Some(9),
+            _ => None,
+        }
+    }
+
+    pub fn name(signo: i32) -> &'static str {
+        match signo {
+            SIGINT => "SIGINT",
+            SIGBREAK => "SIGBREAK",
+            15 => "SIGTERM",
+            9 => "SIGKILL",
+            _ => "UNKNOWN",
+        }
+    }
+
+    /// Only Ctrl-C and Ctrl-Break reach a console process on Windows.
+    pub fn can_listen(signo: i32) -> bool {
+        matches!(signo, SIGINT | SIGBREAK)
+    }
+
+    pub fn install(signo: i32) -> io::Result<()> {
+        if INSTALLED.fetch_or(1 << signo, Ordering::SeqCst) == 0
+            && unsafe { SetConsoleCtrlHandler(Some(handle), TRUE) } == 0
+        {
+            INSTALLED.fetch_and(!(1 << signo), Ordering::SeqCst);
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    pub fn uninstall(signo: i32) {
+        if INSTALLED.fetch_and(!(1 << signo), Ordering::SeqCst) == 1 << signo {
+            unsafe { SetConsoleCtrlHandler(Some(handle), FALSE) };
+        }
+    }
+
+    /// Windows has no signals, every signal terminates the process.
+    pub fn kill(pid: i32, _: i32) -> io::Result<()> {
+        unsafe {
+            let process = OpenProcess(PROCESS_TERMINATE, FALSE, pid as DWORD);
+            if process.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+
+            let terminated = TerminateProcess(process, 1);
+            CloseHandle(process);
+            if terminated == 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Signal number of a name like `SIGINT`, `SIGTERM` by default.
+pub(crate) fn signal_from_js(value: Option<&JsValue>, ctx: &mut Context) -> JsResult<i32> {
+    let name = match value {
+        Some(value) if !value.is_undefined() => value.to_string(ctx)?.to_std_string_escaped(),
+        _ => return Ok(sys::parse("SIGTERM").expect("SIGTERM exists everywhere")),
+    };
+
+    match sys::parse(&name) {
+        Some(signo) => Ok(signo),
+        None => throw!(typ, "Unknown signal `{}`", name),
+    }
+}
+
+pub(crate) fn send_signal(pid: i32, signo: i32) -> JsResult<()> {
+    sys::kill(pid, signo).map_err(|err| JsNativeError::error().with_message(err.to_string()).into())
+}
+
+fn listener_args(args: &[JsValue], ctx: &mut Context) -> JsResult<(i32, JsFunction)> {
+    let signo = match args.first() {
+        Some(signal) if signal.is_string() => signal_from_js(Some(signal), ctx)?,
+        _ => throw!(typ, "Expected a signal name like `SIGINT`"),
+    };
+    if !sys::can_listen(signo) {
+        throw!(typ, "Can't listen for `{}`", sys::name(signo));
+    }
+
+    match args
+        .get(1)
+        .and_then(JsValue::as_object)
+        .and_then(|handler| JsFunction::from_object(handler.clone()))
+    {
+        Some(handler) => Ok((signo, handler)),
+        None => throw!(typ, "Expected a function as signal listener"),
+    }
+}
+
+/// Waits for the next signal and runs its listeners in a job.
+fn arm(ctx: &mut Context) {
+    let (handle, registration) = AbortHandle::new_pair();
+    let wake = LISTENERS.with_borrow_mut(|listeners| {
+        listeners.abort = Some(handle);
+        listeners.wake.1.clone()
+    });
+
+    let wait = async move {
+        let result = Abortable::new(wake.recv(), registration).await;
+
+        NativeJob::new(move |ctx| match result {
+            Ok(_) => dispatch(ctx),
+            Err(_) => Ok(JsValue::undefined()),
+        })
+    };
+    ctx.job_queue().enqueue_future_job(Box::pin(wait), ctx);
+}
+
+fn dispatch(ctx: &mut Context) -> JsResult<JsValue> {
+    let calls: Vec<(i32, Vec<JsFunction>)> = LISTENERS.with_borrow(|listeners| {
+        let pending = listeners.pending.swap(0, Ordering::SeqCst);
+        listeners
+            .handlers
+            .iter()
+            .filter(|(signo, _)| pending & (1 << **signo) != 0)
+            .map(|(signo, handlers)| (*signo, handlers.clone()))
+            .collect()
+    });
+
+    // Listeners can remove themselves, which stops waiting again
+    if LISTENERS.with_borrow(|listeners| !listeners.handlers.is_empty()) {
+        arm(ctx);
+    }
+
+    for (signo, handlers) in calls {
+        let name = JsValue::from(js_string!(sys::name(signo)));
+        for handler in handlers {
+            handler.call(&JsValue::undefined(), &[name.clone()], ctx)?;
+        }
+    }
+
+    Ok(JsValue::undefined())
+}
+
+/// Adds a listener for a signal, adding the same function twice does nothing.
+pub fn add_signal_listener_ex(
+    _: &JsValue,
+    args: &[JsValue],
+    ctx: &mut Context,
+) -> JsResult<JsValue> {
+    let (signo, handler) = listener_args(args, ctx)?;
+
+    let first = LISTENERS.with_borrow_mut(|listeners| {
+        let handlers = listeners.handlers.entry(signo).or_default();
+        if handlers
+            .iter()
+            .any(|existing| JsObject::equals(existing, &handler))
+        {
+            return None;
+        }
+
+        handlers.push(handler);
+        Some(handlers.len() == 1)
+    });
+
+    if first == Some(true) {
+        if let Err(err) = subscribe(signo) {
+            LISTENERS.with_borrow_mut(|listeners| listeners.handlers.remove(&signo));
+            throw!(err, "Failed to listen for `{}`: {}", sys::name(signo), err);
+        }
+    }
+    if LISTENERS.with_borrow(|listeners| listeners.abort.is_none()) {
+        arm(ctx);
+    }
+
+    Ok(JsValue::undefined())
+}
+
+/// Removes a listener. Without listeners a signal gets its default behavior back and
+/// the process no longer waits for signals.
+pub fn remove_signal_listener_ex(
+    _: &JsValue,
+    args: &[JsValue],
+    ctx: &mut Context,
+) -> JsResult<JsValue> {
+    let (signo, handler) = listener_args(args, ctx)?;
+
+    let (thread, last_for_signal, abort) = LISTENERS.with_borrow_mut(|listeners| {
+        let thread = listeners.thread;
+        let Some(handlers) = listeners.handlers.get_mut(&signo) else {
+            return (thread, false, None);
+        };
+        handlers.retain(|existing| !JsObject::equals(existing, &handler));
+
+        let last = handlers.is_empty();
+        if last {
+            listeners.handlers.remove(&signo);
+        }
+        match listeners.handlers.is_empty() {
+            true => (thread, last, listeners.abort.take()),
+            false => (thread, last, None),
+        }
+    });
+
+    if last_for_signal {
+        unsubscribe(thread, signo);
+    }
+    if let Some(abort) = abort {
+        abort.abort();
+    }
+
+    Ok(JsValue::undefined())
+}
+
+/// Sends a signal to a process, `SIGTERM` by default. Any process can be signaled, so
+/// a sandbox has to allow running every command.
+pub fn kill_ex(_: &JsValue, args: &[JsValue], ctx: &mut Context) -> JsResult<JsValue> {
+    permissions::check(PermissionKind::Run, None, ctx)?;
+
+    let pid = match args.first() {
+        Some(pid) if pid.is_number() => pid.to_i32(ctx)?,
+        _ => throw!(typ, "Expected a process id"),
+    };
+    let signo = signal_from_js(args.get(1), ctx)?;
+
+    send_signal(pid, signo)?;
+
+    Ok(JsValue::undefined())
+}
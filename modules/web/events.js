@@ -0,0 +1,313 @@
+import { getArgument, toString } from "@std/_internal_";
+
+const _type = Symbol("type");
+const _cancelable = Symbol("cancelable");
+const _canceled = Symbol("canceled");
+const _stopped = Symbol("stopped");
+const _target = Symbol("target");
+const _timeStamp = Symbol("timeStamp");
+const _detail = Symbol("detail");
+const _promise = Symbol("promise");
+const _reason = Symbol("reason");
+const _data = Symbol("data");
+const _message = Symbol("message");
+const _filename = Symbol("filename");
+const _lineno = Symbol("lineno");
+const _colno = Symbol("colno");
+const _error = Symbol("error");
+
+// Listeners are keyed by their target so `globalThis` can be one without
+// having `EventTarget` in its prototype chain
+const listeners = new WeakMap();
+
+const listenersOf = (target, type) => {
+  let types = listeners.get(target);
+  if (types === undefined) {
+    types = new Map();
+    listeners.set(target, types);
+  }
+
+  let list = types.get(type);
+  if (list === undefined) {
+    list = [];
+    types.set(type, list);
+  }
+
+  return list;
+};
+
+const normalizeOptions = (options) => {
+  if (typeof options === "boolean") {
+    return { capture: options, once: false };
+  }
+
+  return { capture: Boolean(options?.capture), once: Boolean(options?.once) };
+};
+
+class Event {
+  [_type];
+  [_cancelable];
+  [_canceled] = false;
+  [_stopped] = false;
+  [_target] = null;
+  [_timeStamp] = Date.now();
+
+  constructor(type, init = {}) {
+    this[_type] = toString(getArgument(type, "type", "Event"));
+    this[_cancelable] = Boolean(init?.cancelable);
+  }
+
+  get type() {
+    return this[_type];
+  }
+
+  get target() {
+    return this[_target];
+  }
+
+  get currentTarget() {
+    return this[_target];
+  }
+
+  get cancelable() {
+    return this[_cancelable];
+  }
+
+  get defaultPrevented() {
+    return this[_canceled];
+  }
+
+  get timeStamp() {
+    return this[_timeStamp];
+  }
+
+  preventDefault() {
+    if (this[_cancelable]) {
+      this[_canceled] = true;
+    }
+  }
+
+  stopImmediatePropagation() {
+    this[_stopped] = true;
+  }
+
+  stopPropagation() {}
+
+  get [Symbol.toStringTag]() {
+    return "Event";
+  }
+}
+
+class CustomEvent extends Event {
+  [_detail];
+
+  constructor(type, init = {}) {
+    super(type, init);
+    this[_detail] = init?.detail ?? null;
+  }
+
+  get detail() {
+    return this[_detail];
+  }
+
+  get [Symbol.toStringTag]() {
+    return "CustomEvent";
+  }
+}
+
+class PromiseRejectionEvent extends Event {
+  [_promise];
+  [_reason];
+
+  constructor(type, init) {
+    super(type, init);
+    this[_promise] = getArgument(
+      init?.promise,
+      "promise",
+      "PromiseRejectionEvent",
+    );
+    this[_reason] = init.reason;
+  }
+
+  get promise() {
+    return this[_promise];
+  }
+
+  get reason() {
+    return this[_reason];
+  }
+
+  get [Symbol.toStringTag]() {
+    return "PromiseRejectionEvent";
+  }
+}
+
+class MessageEvent extends Event {
+  [_data];
+
+  constructor(type, init = {}) {
+    super(type, init);
+    this[_data] = init?.data ?? null;
+  }
+
+  get data() {
+    return this[_data];
+  }
+
+  get origin() {
+    return "";
+  }
+
+  get lastEventId() {
+    return "";
+  }
+
+  get source() {
+    return null;
+  }
+
+  get ports() {
+    return [];
+  }
+
+  get [Symbol.toStringTag]() {
+    return "MessageEvent";
+  }
+}
+
+class ErrorEvent extends Event {
+  [_message];
+  [_filename];
+  [_lineno];
+  [_colno];
+  [_error];
+
+  constructor(type, init = {}) {
+    super(type, init);
+    this[_message] = toString(init?.message ?? "");
+    this[_filename] = toString(init?.filename ?? "");
+    this[_lineno] = Number(init?.lineno ?? 0);
+    this[_colno] = Number(init?.colno ?? 0);
+    this[_error] = init?.error;
+  }
+
+  get message() {
+    return this[_message];
+  }
+
+  get filename() {
+    return this[_filename];
+  }
+
+  get lineno() {
+    return this[_lineno];
+  }
+
+  get colno() {
+    return this[_colno];
+  }
+
+  get error() {
+    return this[_error];
+  }
+
+  get [Symbol.toStringTag]() {
+    return "ErrorEvent";
+  }
+}
+
+class EventTarget {
+  addEventListener(type, callback, options = undefined) {
+    const target = this ?? globalThis;
+    type = toString(getArgument(type, "type", "EventTarget.addEventListener"));
+
+    if (callback === null || callback === undefined) {
+      return;
+    }
+
+    const { capture, once } = normalizeOptions(options);
+    const list = listenersOf(target, type);
+    if (list.some((l) => l.callback === callback && l.capture === capture)) {
+      return;
+    }
+
+    list.push({ callback, capture, once, removed: false });
+  }
+
+  removeEventListener(type, callback, options = undefined) {
+    const target = this ?? globalThis;
+    type = toString(
+      getArgument(type, "type", "EventTarget.removeEventListener"),
+    );
+
+    const { capture } = normalizeOptions(options);
+    const list = listenersOf(target, type);
+    const index = list.findIndex(
+      (l) => l.callback === callback && l.capture === capture,
+    );
+
+    if (index !== -1) {
+      list[index].removed = true;
+      list.splice(index, 1);
+    }
+  }
+
+  dispatchEvent(event) {
+    const target = this ?? globalThis;
+    if (!(event instanceof Event)) {
+      throw new TypeError(
+        "EventTarget.dispatchEvent: Argument 1 is not an Event",
+      );
+    }
+
+    event[_target] = target;
+    event[_stopped] = false;
+
+    // Listeners added while dispatching only see the next event, and every
+    // listener runs even if one of them throws, the first error is rethrown
+    let error = null;
+    for (const listener of [...listenersOf(target, event.type)]) {
+      if (listener.removed) {
+        continue;
+      }
+      if (listener.once) {
+        target.removeEventListener(event.type, listener.callback, {
+          capture: listener.capture,
+        });
+      }
+
+      try {
+        if (typeof listener.callback === "function") {
+          listener.callback.call(target, event);
+        } else {
+          listener.callback.handleEvent(event);
+        }
+      } catch (e) {
+        error ??= { e };
+      }
+
+      if (event[_stopped]) {
+        break;
+      }
+    }
+
+    if (error !== null) {
+      throw error.e;
+    }
+
+    return !event.defaultPrevented;
+  }
+
+  get [Symbol.toStringTag]() {
+    return "EventTarget";
+  }
+}
+
+export {
+  CustomEvent,
+  ErrorEvent,
+  Event,
+  EventTarget,
+  MessageEvent,
+  PromiseRejectionEvent,
+};
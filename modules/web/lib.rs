@@ -11,7 +11,7 @@ use boa_engine::{
 };
 use ike_core::throw;
 use ike_function::ike_function;
-use timeouts::{clear_timeout_ex, set_timeout_ex};
+use timeouts::{cancel_timer_ex, queue_microtask_ex, set_timer_ref_ex, start_timer_ex};
 use url::quirks;
 use url::Url;
 
@@ -271,10 +271,12 @@ pub fn get_promise_state() {
 ike_core::module!(
     WebModule,
     "web",
-    js = ["streams.js", "timeouts.js", "base64.js", "encoding.js", "headers.js", "main.js", "url.js", "console.js"],
+    js = ["streams.js", "timeouts.js", "base64.js", "encoding.js", "headers.js", "main.js", "url.js", "console.js", "events.js"],
     exposed = {
-        "set_timeout_ex" => set_timeout_ex,
-        "clear_timeout_ex" => clear_timeout_ex,
+        "start_timer_ex" => start_timer_ex,
+        "cancel_timer_ex" => cancel_timer_ex,
+        "set_timer_ref_ex" => set_timer_ref_ex,
+        "queue_microtask_ex" => queue_microtask_ex,
         "atob_ex" => atob,
         "btoa_ex" => btoa,
         "decode_ex" => encoding::decode,
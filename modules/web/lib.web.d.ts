@@ -900,27 +900,124 @@ declare module 'module:web/streams.js' {
 
 declare module 'module:web/timeouts.js' {
   declare global {
+    /**
+     * A timer of `setTimeout` or `setInterval`. It converts to the id of the
+     * timer, so `clearTimeout(Number(timer))` works as well.
+     */
+    export declare class Timeout {
+      /**
+       * Keeps the process alive until the timer fired, which is the default.
+       */
+      ref(): this;
+
+      /**
+       * Lets the process exit even if the timer didn't fire yet.
+       */
+      unref(): this;
+
+      /**
+       * Whether the timer keeps the process alive.
+       */
+      hasRef(): boolean;
+
+      /**
+       * Starts the delay over. A timer that already fired is scheduled again.
+       */
+      refresh(): this;
+
+      /**
+       * Cancels the timer, like `clearTimeout`.
+       */
+      close(): this;
+
+      [Symbol.toPrimitive](): number;
+    }
+
+    /**
+     * A callback of `setImmediate`.
+     */
+    export declare class Immediate extends Timeout {}
+
     /**
      * The `setTimeout()` function sets a timer which executes a function or specified piece of code once the timer expires.
      *
+     * The callback always runs in a later task, even with a delay of 0. Timers
+     * nested deeper than 5 levels wait at least 4ms.
+     *
      * @param callback - The function to execute.
      * @param ms - The number of milliseconds to wait before executing the code.
      * @param args - Additional arguments to pass to the function.
      */
-    export declare function setTimeout(
-      callback: (...args: any[]) => void,
-      ms: number,
-      ...args: any[]
-    ): number;
+    export declare function setTimeout<A extends any[]>(
+      callback: (...args: A) => void,
+      ms?: number,
+      ...args: A
+    ): Timeout;
 
     /**
      * The `clearTimeout()` function cancels a timeout previously established by calling `setTimeout()`.
      *
-     * @param id - The identifier of the timeout you want to cancel.
+     * @param id - The timer or its id.
+     */
+    export declare function clearTimeout(
+      id: Timeout | number | undefined,
+    ): void;
+
+    /**
+     * Calls the callback every `ms` milliseconds until the interval is cleared.
+     *
+     * @param callback - The function to execute.
+     * @param ms - The number of milliseconds between the calls.
+     * @param args - Additional arguments to pass to the function.
+     */
+    export declare function setInterval<A extends any[]>(
+      callback: (...args: A) => void,
+      ms?: number,
+      ...args: A
+    ): Timeout;
+
+    /**
+     * Cancels an interval of `setInterval()`.
+     *
+     * @param id - The timer or its id.
+     */
+    export declare function clearInterval(
+      id: Timeout | number | undefined,
+    ): void;
+
+    /**
+     * Calls the callback in a later task without any delay.
+     *
+     * @param callback - The function to execute.
+     * @param args - Additional arguments to pass to the function.
+     */
+    export declare function setImmediate<A extends any[]>(
+      callback: (...args: A) => void,
+      ...args: A
+    ): Immediate;
+
+    /**
+     * Cancels a callback of `setImmediate()`.
+     */
+    export declare function clearImmediate(
+      id: Immediate | number | undefined,
+    ): void;
+
+    /**
+     * Runs the callback once the current job and the microtasks queued before
+     * it are done. Errors are reported like uncaught ones.
      */
-    export declare function clearTimeout(id: number): void;
+    export declare function queueMicrotask(callback: () => void): void;
   }
-  export { setTimeout, clearTimeout };
+  export {
+    setTimeout,
+    clearTimeout,
+    setInterval,
+    clearInterval,
+    setImmediate,
+    clearImmediate,
+    queueMicrotask,
+  };
 }
 
 declare module 'module:web/base64.js' {
@@ -1326,3 +1423,269 @@ declare module 'module:web/url.js' {
 
   export { URL, URLSearchParams };
 }
+
+declare module 'module:web/events.js' {
+  global {
+    /**
+     * Options of an {@link Event}.
+     */
+    export interface EventInit {
+      /**
+       * Whether {@link Event.preventDefault} cancels the event.
+       *
+       * @default false
+       */
+      cancelable?: boolean;
+    }
+
+    /**
+     * An event dispatched on an {@link EventTarget}.
+     */
+    class Event {
+      constructor(type: string, init?: EventInit);
+
+      /**
+       * The name of the event, like `exit`.
+       */
+      readonly type: string;
+
+      /**
+       * The target the event was dispatched on, `null` before it is dispatched.
+       */
+      readonly target: EventTarget | null;
+
+      /**
+       * The target whose listener is running, the same as {@link Event.target}.
+       */
+      readonly currentTarget: EventTarget | null;
+
+      /**
+       * Whether the event can be canceled.
+       */
+      readonly cancelable: boolean;
+
+      /**
+       * Whether {@link Event.preventDefault} was called on a cancelable event.
+       */
+      readonly defaultPrevented: boolean;
+
+      /**
+       * Milliseconds since the epoch when the event was created.
+       */
+      readonly timeStamp: number;
+
+      /**
+       * Cancels the event if it is cancelable.
+       */
+      preventDefault(): void;
+
+      /**
+       * Stops the remaining listeners from running.
+       */
+      stopImmediatePropagation(): void;
+
+      /**
+       * Does nothing, events don't bubble.
+       */
+      stopPropagation(): void;
+    }
+
+    /**
+     * Options of a {@link CustomEvent}.
+     */
+    export interface CustomEventInit<T = any> extends EventInit {
+      detail?: T;
+    }
+
+    /**
+     * An event carrying data of any kind.
+     */
+    class CustomEvent<T = any> extends Event {
+      constructor(type: string, init?: CustomEventInit<T>);
+
+      /**
+       * The data passed when the event was created, `null` by default.
+       */
+      readonly detail: T;
+    }
+
+    /**
+     * Options of a {@link PromiseRejectionEvent}.
+     */
+    export interface PromiseRejectionEventInit extends EventInit {
+      promise: Promise<any>;
+      reason?: any;
+    }
+
+    /**
+     * Dispatched on `globalThis` as `unhandledrejection` when a promise is
+     * rejected and no handler was added once the microtasks ran. Calling
+     * {@link Event.preventDefault} stops the rejection from being reported,
+     * otherwise `--unhandled-rejections` decides what happens, by default the
+     * process exits with 1.
+     */
+    class PromiseRejectionEvent extends Event {
+      constructor(type: string, init: PromiseRejectionEventInit);
+
+      /**
+       * The promise that was rejected.
+       */
+      readonly promise: Promise<any>;
+
+      /**
+       * The value the promise was rejected with.
+       */
+      readonly reason: any;
+    }
+
+    /**
+     * Options of a {@link MessageEvent}.
+     */
+    export interface MessageEventInit<T = any> extends EventInit {
+      data?: T;
+    }
+
+    /**
+     * A message sent with `postMessage`, dispatched as `message` on a
+     * {@link Worker} or on the global scope of a worker.
+     */
+    class MessageEvent<T = any> extends Event {
+      constructor(type: string, init?: MessageEventInit<T>);
+
+      /**
+       * A structured clone of the value that was sent.
+       */
+      readonly data: T;
+
+      readonly origin: string;
+
+      readonly lastEventId: string;
+
+      readonly source: null;
+
+      readonly ports: readonly never[];
+    }
+
+    /**
+     * Options of an {@link ErrorEvent}.
+     */
+    export interface ErrorEventInit extends EventInit {
+      message?: string;
+      filename?: string;
+      lineno?: number;
+      colno?: number;
+      error?: any;
+    }
+
+    /**
+     * An uncaught error of a worker, dispatched as `error` on its global scope
+     * and, unless a listener prevents it there, on its {@link Worker}.
+     */
+    class ErrorEvent extends Event {
+      constructor(type: string, init?: ErrorEventInit);
+
+      readonly message: string;
+
+      /**
+       * The file of the worker the error happened in.
+       */
+      readonly filename: string;
+
+      readonly lineno: number;
+
+      readonly colno: number;
+
+      /**
+       * The error that was thrown, only available in the worker itself.
+       */
+      readonly error: any;
+    }
+
+    export type EventListener = (event: Event) => void;
+
+    export interface EventListenerObject {
+      handleEvent(event: Event): void;
+    }
+
+    export type EventListenerOrEventListenerObject =
+      | EventListener
+      | EventListenerObject;
+
+    export interface AddEventListenerOptions {
+      capture?: boolean;
+      /**
+       * Removes the listener after it ran once.
+       */
+      once?: boolean;
+    }
+
+    /**
+     * An object events can be dispatched on and listened to.
+     */
+    class EventTarget {
+      /**
+       * Adds a listener for events of `type`, adding the same one again does nothing.
+       */
+      addEventListener(
+        type: string,
+        listener: EventListenerOrEventListenerObject | null,
+        options?: boolean | AddEventListenerOptions,
+      ): void;
+
+      /**
+       * Removes a listener added with {@link EventTarget.addEventListener}.
+       */
+      removeEventListener(
+        type: string,
+        listener: EventListenerOrEventListenerObject | null,
+        options?: boolean | { capture?: boolean },
+      ): void;
+
+      /**
+       * Runs the listeners of the event, even if one of them throws. The first
+       * error is rethrown afterwards.
+       *
+       * @returns `false` if the event is cancelable and was canceled.
+       */
+      dispatchEvent(event: Event): boolean;
+    }
+
+    /**
+     * Adds a listener for events dispatched on `globalThis`.
+     *
+     * The runtime dispatches `beforeExit` once the event loop drains, listeners
+     * can schedule more work to keep the process alive. `exit` is dispatched
+     * right before the process exits with `Ike.exitCode`, only synchronous
+     * work runs in its listeners. `unhandledrejection` is a
+     * {@link PromiseRejectionEvent}.
+     */
+    function addEventListener(
+      type: string,
+      listener: EventListenerOrEventListenerObject | null,
+      options?: boolean | AddEventListenerOptions,
+    ): void;
+
+    /**
+     * Removes a listener added with {@link addEventListener}.
+     */
+    function removeEventListener(
+      type: string,
+      listener: EventListenerOrEventListenerObject | null,
+      options?: boolean | { capture?: boolean },
+    ): void;
+
+    /**
+     * Dispatches an event on `globalThis`.
+     */
+    function dispatchEvent(event: Event): boolean;
+  }
+
+  export {
+    CustomEvent,
+    ErrorEvent,
+    Event,
+    EventTarget,
+    MessageEvent,
+    PromiseRejectionEvent,
+  };
+}
@@ -1,12 +1,213 @@
-export const setTimeout = (cb, _delay, ...args) => {
-  if (typeof cb !== 'function') {
-    throw new Error('setTimeout requires a function as first argument');
+// Timers of the HTML spec, with the `ref()`, `unref()` and `refresh()` of Node
+
+const MAX_DELAY = 2 ** 31 - 1;
+const MAX_NESTING = 5;
+const MIN_NESTED_DELAY = 4;
+
+const _id = Symbol('id');
+const _timer = Symbol('timer');
+const _callback = Symbol('callback');
+const _args = Symbol('args');
+const _delay = Symbol('delay');
+const _repeat = Symbol('repeat');
+const _nesting = Symbol('nesting');
+const _ref = Symbol('ref');
+const _schedule = Symbol('schedule');
+const _fire = Symbol('fire');
+
+const active = new Map();
+let nextId = 1;
+// Nesting level of the timer whose callback is running, 0 outside of timers
+let currentNesting = 0;
+
+const normalizeDelay = (delay) => {
+  delay = Number(delay);
+
+  // Like browsers, delays that don't fit into 32 bits are 1ms
+  if (!Number.isFinite(delay) || delay < 0 || delay > MAX_DELAY) {
+    return delay > MAX_DELAY ? 1 : 0;
   }
 
-  const delay = _delay ? parseInt(_delay, 10) : 0;
-  return set_timeout_ex(cb, delay, ...args);
+  return Math.trunc(delay);
 };
 
-export const clearTimeout = (id) => {
-  return clear_timeout_ex(parseInt(id, 10) || 0);
+const assertCallback = (callback, name) => {
+  if (typeof callback !== 'function') {
+    throw new TypeError(`${name} requires a function as first argument`);
+  }
 };
+
+class Timeout {
+  [_id] = nextId++;
+  [_timer] = null;
+  [_callback];
+  [_args];
+  [_delay];
+  [_repeat];
+  [_nesting];
+  [_ref] = true;
+
+  constructor(callback, delay, args, repeat) {
+    this[_callback] = callback;
+    this[_args] = args;
+    this[_delay] = delay;
+    this[_repeat] = repeat;
+    this[_nesting] = currentNesting + 1;
+
+    active.set(this[_id], this);
+    this[_schedule]();
+  }
+
+  [_schedule]() {
+    let delay = this[_delay];
+    if (this[_nesting] > MAX_NESTING && delay < MIN_NESTED_DELAY) {
+      delay = MIN_NESTED_DELAY;
+    }
+
+    this[_timer] = start_timer_ex(() => this[_fire](), delay, this[_ref]);
+  }
+
+  [_fire]() {
+    this[_timer] = null;
+    if (!this[_repeat]) {
+      active.delete(this[_id]);
+    }
+
+    const previous = currentNesting;
+    currentNesting = this[_nesting];
+    try {
+      this[_callback](...this[_args]);
+    } finally {
+      currentNesting = previous;
+
+      // The callback can clear its own interval
+      if (this[_repeat] && active.has(this[_id])) {
+        this[_nesting]++;
+        this[_schedule]();
+      }
+    }
+  }
+
+  /**
+   * Keeps the process alive until the timer fired, which is the default.
+   */
+  ref() {
+    this[_ref] = true;
+    if (this[_timer] !== null) {
+      set_timer_ref_ex(this[_timer], true);
+    }
+
+    return this;
+  }
+
+  /**
+   * Lets the process exit even if the timer didn't fire yet.
+   */
+  unref() {
+    this[_ref] = false;
+    if (this[_timer] !== null) {
+      set_timer_ref_ex(this[_timer], false);
+    }
+
+    return this;
+  }
+
+  hasRef() {
+    return this[_ref];
+  }
+
+  /**
+   * Starts the delay over, a timer that already fired is scheduled again.
+   */
+  refresh() {
+    if (this[_timer] !== null) {
+      cancel_timer_ex(this[_timer]);
+    }
+
+    active.set(this[_id], this);
+    this[_schedule]();
+
+    return this;
+  }
+
+  close() {
+    clearTimer(this);
+
+    return this;
+  }
+
+  [Symbol.toPrimitive]() {
+    return this[_id];
+  }
+
+  get [Symbol.toStringTag]() {
+    return 'Timeout';
+  }
+}
+
+const clearTimer = (timer) => {
+  const id =
+    typeof timer === 'object' && timer !== null
+      ? timer[Symbol.toPrimitive]?.()
+      : Number(timer);
+  const timeout = active.get(id);
+
+  if (timeout === undefined) {
+    return;
+  }
+
+  active.delete(id);
+  if (timeout[_timer] !== null) {
+    cancel_timer_ex(timeout[_timer]);
+    timeout[_timer] = null;
+  }
+};
+
+export const setTimeout = (callback, delay = 0, ...args) => {
+  assertCallback(callback, 'setTimeout');
+
+  return new Timeout(callback, normalizeDelay(delay), args, false);
+};
+
+export const setInterval = (callback, delay = 0, ...args) => {
+  assertCallback(callback, 'setInterval');
+
+  return new Timeout(callback, normalizeDelay(delay), args, true);
+};
+
+export const clearTimeout = (timer) => clearTimer(timer);
+
+export const clearInterval = (timer) => clearTimer(timer);
+
+/**
+ * Runs the callback once the current job and the microtasks queued before it
+ * are done. Errors are reported like uncaught ones.
+ */
+export const queueMicrotask = (callback) => {
+  assertCallback(callback, 'queueMicrotask');
+
+  queue_microtask_ex(callback);
+};
+
+class Immediate extends Timeout {
+  constructor(callback, args) {
+    super(callback, 0, args, false);
+  }
+
+  // Immediates aren't nested timers, they are never clamped
+  [_schedule]() {
+    this[_timer] = start_timer_ex(() => this[_fire](), 0, this[_ref]);
+  }
+
+  get [Symbol.toStringTag]() {
+    return 'Immediate';
+  }
+}
+
+export const setImmediate = (callback, ...args) => {
+  assertCallback(callback, 'setImmediate');
+
+  return new Immediate(callback, args);
+};
+
+export const clearImmediate = (immediate) => clearTimer(immediate);
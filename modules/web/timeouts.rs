@@ -2,82 +2,90 @@
 
 use boa_engine::{job::NativeJob, object::builtins::JsFunction, JsResult, JsValue};
 use futures_util::future::{AbortHandle, Abortable};
+use ike_core::event_loop::LoopRef;
 use ike_function::ike_function;
 use smol::Timer;
-use std::sync::LazyLock;
-use std::{collections::HashMap, sync::atomic::AtomicU32};
-use std::{sync::Mutex, time::Duration};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+    time::Duration,
+};
 
-struct Timeouts {}
-
-impl Timeouts {
-    pub fn get(id: u32) -> Option<AbortHandle> {
-        let timeouts = unsafe { TIMEOUTS.lock().unwrap() };
-        timeouts.get(&id).cloned()
-    }
+/// A pending timer. The ids, nesting levels and intervals of the timers of `setTimeout`
+/// live in timeouts.js, a timer here only calls its callback once after the delay.
+struct PendingTimer {
+    abort: AbortHandle,
+    loop_ref: LoopRef,
+}
 
-    pub fn remove(id: u32) {
-        let mut timeouts = unsafe { TIMEOUTS.lock().unwrap() };
-        timeouts.remove(&id);
-    }
+thread_local! {
+    static TIMERS: RefCell<HashMap<u32, Rc<PendingTimer>>> = RefCell::default();
+    static NEXT_ID: Cell<u32> = const { Cell::new(1) };
 }
 
-static mut TIMEOUTS: LazyLock<Mutex<HashMap<u32, AbortHandle>>> = LazyLock::new(Default::default);
-static TIMER_ID: AtomicU32 = AtomicU32::new(0);
+fn get_timer(id: i32) -> Option<Rc<PendingTimer>> {
+    TIMERS.with(|timers| timers.borrow().get(&(id as u32)).cloned())
+}
 
+/// Calls `callback` in its own job once `delay` milliseconds passed, even for a delay of 0
+/// it never runs synchronously. Returns the id of the timer.
 #[ike_function]
-pub fn set_timeout_ex(#[function] callback: JsFunction, #[i32] delay: Option<i32>) {
-    let delay = delay.unwrap_or(0);
-    // All args after 2 are passed to the callback
-    let params = if args.len() >= 3 {
-        args[2..].to_vec()
-    } else {
-        Vec::new()
-    };
-    if delay == 0 {
-        // Execute immediately
-        callback.call(&JsValue::undefined(), &params, ctx)?;
-
-        return Ok(JsValue::undefined());
-    }
-
-    let timeout_id = TIMER_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed) as usize;
-    let (abort_handle, abort_registration) = AbortHandle::new_pair();
-    {
-        let mut timeouts = unsafe { TIMEOUTS.lock().unwrap() };
-        timeouts.insert(timeout_id as u32, abort_handle);
-    }
+pub fn start_timer_ex(#[function] callback: JsFunction, #[i32] delay: i32, #[bool] is_ref: bool) {
+    let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+    let (abort, registration) = AbortHandle::new_pair();
+    let timer = Rc::new(PendingTimer {
+        abort,
+        loop_ref: LoopRef::new(),
+    });
+    timer.loop_ref.set_ref(is_ref);
+    TIMERS.with(|timers| timers.borrow_mut().insert(id, Rc::clone(&timer)));
 
+    let delay = Duration::from_millis(delay.max(0) as u64);
     let wait = async move {
-        let result = Abortable::new(
-            Timer::after(Duration::from_millis(delay as u64)),
-            abort_registration,
-        )
-        .await;
+        let result = Abortable::new(Timer::after(delay), registration).await;
+
+        // The future is done, it must not count as unref'd anymore
+        timer.loop_ref.set_ref(true);
+        TIMERS.with(|timers| timers.borrow_mut().remove(&id));
 
         NativeJob::new(move |context| -> JsResult<JsValue> {
             if result.is_ok() {
-                callback.call(&JsValue::undefined(), &params, context)?;
+                callback.call(&JsValue::undefined(), &[], context)?;
             }
-            Timeouts::remove(timeout_id as u32);
             Ok(JsValue::undefined())
         })
     };
 
     ctx.job_queue().enqueue_future_job(Box::pin(wait), ctx);
 
-    Ok(JsValue::from(timeout_id as i32))
+    Ok(JsValue::from(id))
 }
 
 #[ike_function]
-pub fn clear_timeout_ex(#[i32] timeout_id_value: Option<i32>) {
-    if let Some(timeout_id) = timeout_id_value {
-        let timeout_id = timeout_id as u32;
-
-        if let Some(handle) = Timeouts::get(timeout_id) {
-            handle.abort()
-            // the original timeout will remove the id from the timeouts list
-        }
+pub fn cancel_timer_ex(#[i32] id: i32) {
+    if let Some(timer) = get_timer(id) {
+        // The timer removes itself once the abort is noticed
+        timer.abort.abort();
     }
+
+    Ok(JsValue::undefined())
+}
+
+/// Whether a pending timer keeps the event loop alive.
+#[ike_function]
+pub fn set_timer_ref_ex(#[i32] id: i32, #[bool] is_ref: bool) {
+    if let Some(timer) = get_timer(id) {
+        timer.loop_ref.set_ref(is_ref);
+    }
+
+    Ok(JsValue::undefined())
+}
+
+#[ike_function]
+pub fn queue_microtask_ex(#[function] callback: JsFunction) {
+    let job = NativeJob::new(move |context| callback.call(&JsValue::undefined(), &[], context));
+    ctx.job_queue().enqueue_promise_job(job, ctx);
+
     Ok(JsValue::undefined())
 }
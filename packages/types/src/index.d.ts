@@ -1,5 +1,6 @@
 import '../../../modules/web/lib.web.d.ts';
 import '../../../modules/fs/lib.fs.d.ts';
+import '../../../modules/process/lib.process.d.ts';
 import './modules/buffer.d.ts';
 import './modules/test.d.ts';
 import './modules/inspect.d.ts';
@@ -7,7 +8,10 @@ import './modules/assert.d.ts';
 import './modules/_internal_.d.ts';
 import './modules/format.d.ts';
 import './modules/path.d.ts';
+import './modules/dotenv.d.ts';
+import './modules/shell.d.ts';
 import './console.d.ts';
+import './worker.d.ts';
 
 /**
  * The Meta interface contains information about the current file and its paths.
@@ -59,7 +63,8 @@ interface Ike {
   meta: Meta;
 
   /**
-   * Exit the process with optional exit code.
+   * Exit the process with optional exit code, `Ike.exitCode` is used without
+   * one. The `exit` event is dispatched and stdout and stderr are flushed first.
    *
    * @param code Exit code.
    * @returns void
@@ -76,7 +81,8 @@ interface Ike {
   setExitCode(code: number): void;
 
   /**
-   * Global exit code
+   * Code the process exits with once the event loop drains. It is set to 1
+   * when an error isn't caught.
    *
    * @default 0
    * @returns number
@@ -137,6 +143,15 @@ interface Ike {
    */
   os: Os;
 
+  /**
+   * Features from `[features]` in ike.toml that are active, sorted by name. Turned on with
+   * `--features a,b`, the ones in `features.default` are active unless
+   * `--no-default-features` is passed.
+   *
+   * @example ['json', 'yaml']
+   */
+  readonly features: readonly string[];
+
   /**
    * Returns the version of the Ike runtime.
    *
@@ -144,6 +159,13 @@ interface Ike {
    */
   version: string;
 
+  /**
+   * Returns the path of the ike executable running the script.
+   *
+   * @example '/usr/local/bin/ike'
+   */
+  execPath: string;
+
   /**
    * Takes a string and parses it as TOML.
    *
@@ -188,14 +210,34 @@ interface Ike {
   path: typeof import('@std/path');
 
   /**
-   * Environment variables.
+   * Environment variables of the process. Reads and writes go to the real environment, so
+   * changes are inherited by subprocesses. Values are converted to strings, names that
+   * are empty or contain `=` throw a `TypeError`.
    *
    * @example
    * ```ts
    * console.log(Ike.env.Path);
+   * Ike.env.NODE_ENV = 'production';
+   * delete Ike.env.DEBUG;
+   * ```
+   */
+  env: Record<string, string | undefined>;
+
+  /**
+   * What the sandbox allows the script to access. Outside of a sandbox, which is
+   * the default, every permission is granted. In one, from `--sandbox`, an
+   * `--allow-*` flag or `[permissions]` of ike.toml, access that wasn't granted
+   * throws a `PermissionDenied` error.
+   *
+   * @example
+   * ```ts
+   * const { state } = await Ike.permissions.query({ name: 'read', path: './data' });
+   * if (state !== 'granted') {
+   *   await Ike.permissions.request({ name: 'read', path: './data' });
+   * }
    * ```
    */
-  env: Record<string, string>;
+  permissions: Permissions;
 
   /**
    * Transpile TypeScript code to JavaScript.
@@ -205,6 +247,35 @@ interface Ike {
    * @returns string
    */
   transpile(loader: Loader, sourceText: string): string;
+
+  spawn: typeof import('module:process/process.js').spawn;
+  spawnSync: typeof import('module:process/process.js').spawnSync;
+  exec: typeof import('module:process/process.js').exec;
+  kill: typeof import('module:process/process.js').kill;
+  addSignalListener: typeof import('module:process/process.js').addSignalListener;
+  removeSignalListener: typeof import('module:process/process.js').removeSignalListener;
+}
+
+type PermissionDescriptor =
+  | { name: 'read' | 'write'; path?: string }
+  | { name: 'env'; variable?: string }
+  | { name: 'run'; command?: string }
+  /** Reserved, nothing checks network access yet. */
+  | { name: 'net'; host?: string };
+
+interface PermissionStatus {
+  /** `prompt` if `request` can ask the user on the terminal. */
+  readonly state: 'granted' | 'prompt' | 'denied';
+}
+
+interface Permissions {
+  /** State of a permission, without a target for all of them. */
+  query(descriptor: PermissionDescriptor): Promise<PermissionStatus>;
+  /**
+   * Asks the user for a permission that is in the `prompt` state. Granted
+   * permissions stay granted for the rest of the run.
+   */
+  request(descriptor: PermissionDescriptor): Promise<PermissionStatus>;
 }
 
 type Loader = 'js' | 'mjs' | 'ts' | 'mts' | 'cjs' | 'cts' | 'jsx' | 'tsx';
@@ -212,6 +283,28 @@ type Loader = 'js' | 'mjs' | 'ts' | 'mts' | 'cjs' | 'cts' | 'jsx' | 'tsx';
 declare global {
   export const Ike: Ike;
 
+  /**
+   * Information about the current module, every module gets its own `import.meta`.
+   */
+  interface ImportMeta extends Meta {
+    /**
+     * `file://` URL of the current module.
+     * @example 'file:///user/project/src/index.ts'
+     */
+    url: string;
+    /**
+     * `true` if the current module is the entry point of the program.
+     */
+    main: boolean;
+    /**
+     * Resolves a specifier relative to the current module using the same
+     * resolution as `import`. Builtin modules are returned as they are.
+     *
+     * @example import.meta.resolve('./utils.ts') // 'file:///user/project/src/utils.ts'
+     */
+    resolve(specifier: string): string;
+  }
+
   /**
    * Function allows to call internal rust functions.
    *
@@ -0,0 +1,48 @@
+declare module '@std/dotenv' {
+  export interface LoadOptions {
+    /**
+     * Path of the file to load.
+     *
+     * @default '.env'
+     */
+    path?: string;
+    /**
+     * Replace variables that are already set in `Ike.env`.
+     *
+     * @default false
+     */
+    override?: boolean;
+  }
+
+  /**
+   * Parses the contents of a .env file. Quoted values can span several lines, double
+   * quoted ones understand escapes like `\n`. `${VAR}` and `${VAR:-default}` expand to
+   * variables defined before them or to the ones of the process.
+   *
+   * @param source The contents of the file.
+   * @returns The variables of the file.
+   * @throws {SyntaxError} If the source isn't valid.
+   * @example
+   * ```ts
+   * import { parse } from '@std/dotenv';
+   *
+   * parse('NAME=ike\nGREETING="hello ${NAME}"'); // { NAME: 'ike', GREETING: 'hello ike' }
+   * ```
+   */
+  export const parse: (source: string) => Record<string, string>;
+
+  /**
+   * Reads a .env file and adds its variables to `Ike.env`. Variables that are already
+   * set are kept unless `override` is set.
+   *
+   * @param options Where to read from and whether to replace existing variables.
+   * @returns The variables of the file.
+   * @example
+   * ```ts
+   * import { load } from '@std/dotenv';
+   *
+   * load({ path: '.env.local' });
+   * ```
+   */
+  export const load: (options?: LoadOptions) => Record<string, string>;
+}
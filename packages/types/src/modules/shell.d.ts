@@ -0,0 +1,133 @@
+/**
+ * Shell scripting with a `$` tagged template. Interpolated values are never parsed by
+ * the shell, so they are safe to use as arguments.
+ *
+ * Supported are pipes `|`, `&&`, `||`, `;`, redirections `>`, `>>`, `<`, `2>`, `&>`,
+ * `2>&1`, quotes, globs, `$VAR` and `NAME=value` before a command. `cat`, `cd`, `echo`,
+ * `ls`, `mkdir`, `pwd` and `rm` are built in and behave the same on every platform.
+ *
+ * @example
+ * ```ts
+ * import { $ } from '@std/shell';
+ *
+ * const branch = await $`git branch --show-current`.text();
+ * await $`git log --oneline ${branch} | head -n 5 > log.txt`;
+ *
+ * const { exitCode } = await $`test -f ${file}`.nothrow();
+ * ```
+ */
+declare module '@std/shell' {
+  export interface ShellOptions {
+    /**
+     * Directory commands run in.
+     *
+     * @default Ike.cwd()
+     */
+    cwd?: string;
+    /**
+     * Variables added to the environment of the commands.
+     */
+    env?: Record<string, string>;
+    /**
+     * Throw a `ShellError` when the script exits with a non-zero code.
+     *
+     * @default true
+     */
+    throws?: boolean;
+  }
+
+  /**
+   * Output of a script. `stdout` and `stderr` hold what wasn't redirected to a file.
+   */
+  export class ShellOutput {
+    readonly exitCode: number;
+    readonly stdout: Uint8Array;
+    readonly stderr: Uint8Array;
+
+    /**
+     * stdout decoded as UTF-8.
+     */
+    text(): string;
+    /**
+     * stdout parsed as JSON.
+     */
+    json<T = any>(): T;
+    /**
+     * Lines of stdout, without a trailing empty line.
+     */
+    lines(): string[];
+  }
+
+  /**
+   * Thrown when a script exits with a non-zero code, the message contains its stderr.
+   */
+  export class ShellError extends Error {
+    readonly output: ShellOutput;
+    readonly exitCode: number;
+    readonly stdout: Uint8Array;
+    readonly stderr: Uint8Array;
+  }
+
+  /**
+   * A script that runs once the current task is done. Options can be changed until then.
+   */
+  export class ShellPromise implements PromiseLike<ShellOutput> {
+    cwd(dir: string): this;
+    env(vars: Record<string, string>): this;
+    /**
+     * Resolve instead of throwing on non-zero exit codes.
+     */
+    nothrow(): this;
+    throws(throws: boolean): this;
+
+    then<T = ShellOutput, E = never>(
+      onfulfilled?: ((output: ShellOutput) => T | PromiseLike<T>) | null,
+      onrejected?: ((reason: any) => E | PromiseLike<E>) | null,
+    ): Promise<T | E>;
+    catch<E = never>(
+      onrejected?: ((reason: any) => E | PromiseLike<E>) | null,
+    ): Promise<ShellOutput | E>;
+    finally(onfinally?: (() => void) | null): Promise<ShellOutput>;
+
+    text(): Promise<string>;
+    json<T = any>(): Promise<T>;
+    lines(): Promise<string[]>;
+  }
+
+  export interface Shell {
+    /**
+     * Runs a script. Strings are a single argument, arrays one argument per element and
+     * `{ raw: '...' }` is parsed as part of the script.
+     *
+     * @throws {SyntaxError} If the script can't be parsed.
+     */
+    (strings: TemplateStringsArray, ...values: unknown[]): ShellPromise;
+    /**
+     * A shell whose scripts run in `dir`.
+     */
+    cwd(dir: string): Shell;
+    /**
+     * A shell whose scripts get `vars` in addition to the environment.
+     */
+    env(vars: Record<string, string>): Shell;
+    /**
+     * A shell whose scripts don't throw on non-zero exit codes.
+     */
+    nothrow(): Shell;
+    throws(throws: boolean): Shell;
+    escape: typeof escape;
+  }
+
+  /**
+   * Quotes a string so the shell reads it as a single word.
+   *
+   * @example
+   * ```ts
+   * escape("it's"); // 'it'\''s'
+   * ```
+   */
+  export const escape: (value: string) => string;
+
+  export const $: Shell;
+  export default $;
+}
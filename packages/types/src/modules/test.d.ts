@@ -14,20 +14,20 @@ declare module '@std/test' {
    * @param fn The function that contains the test.
    */
   const it: {
-    (test: string, fn: () => void): void;
+    (test: string, fn: () => void | Promise<void>): void;
     /**
      * Skips a test, and marks it as todo.
      * @param test The name of the test.
      * @param fn The function that contains the test.
      */
-    todo: (test: string, fn: () => void) => void;
+    todo: (test: string, fn: () => void | Promise<void>) => void;
     /**
      * Skips a test.
      *
      * @param test The name of the test.
      * @param fn The function that contains the test.
      */
-    skip: (test: string, fn: () => void) => void;
+    skip: (test: string, fn: () => void | Promise<void>) => void;
     /**
      * Runs a test only if the condition is true.
      *
@@ -35,7 +35,7 @@ declare module '@std/test' {
      * @param test The name of the test.
      * @param fn The function that contains the test.
      */
-    if: (condition: boolean, test: string, fn: () => void) => void;
+    if: (condition: boolean, test: string, fn: () => void | Promise<void>) => void;
   };
 
   /**
@@ -0,0 +1,72 @@
+declare global {
+  export interface WorkerOptions {
+    /**
+     * Only module workers are supported, `classic` throws.
+     *
+     * @default 'module'
+     */
+    type?: 'module';
+    /**
+     * Name of the worker, available as `name` in its global scope.
+     */
+    name?: string;
+  }
+
+  export interface StructuredSerializeOptions {
+    /**
+     * Array buffers moved to the receiver instead of being copied, they are
+     * detached for the sender.
+     */
+    transfer?: ArrayBuffer[];
+  }
+
+  /**
+   * A module running on its own thread with its own global scope. Messages are
+   * structured clones, so dates, maps, sets, errors, typed arrays and cycles
+   * survive the trip while functions and symbols throw a `DataCloneError`.
+   *
+   * In the worker, `self.onmessage` receives the messages and `postMessage`
+   * answers. The worker keeps running until it calls `close()` or the
+   * `Worker` is terminated, and keeps the process alive until then.
+   *
+   * Uncaught errors of the worker are dispatched as {@link ErrorEvent} on its
+   * global scope, then on the `Worker`. If no listener prevents them there
+   * they are uncaught in the thread that created the worker.
+   *
+   * @example
+   * ```ts
+   * const worker = new Worker(new URL('./worker.ts', import.meta.url));
+   * worker.onmessage = (event) => {
+   *   console.log(event.data);
+   *   worker.terminate();
+   * };
+   * worker.postMessage({ numbers: [1, 2, 3] });
+   * ```
+   */
+  class Worker extends EventTarget {
+    /**
+     * @param specifier - Path or `file:` URL of the module, relative paths are
+     * resolved from `Ike.cwd()`.
+     */
+    constructor(specifier: string | URL, options?: WorkerOptions);
+
+    onmessage: ((event: MessageEvent) => void) | null;
+    onmessageerror: ((event: MessageEvent) => void) | null;
+    onerror: ((event: ErrorEvent) => void) | null;
+
+    /**
+     * Sends a structured clone of `message` to the worker.
+     */
+    postMessage(
+      message: any,
+      transfer?: ArrayBuffer[] | StructuredSerializeOptions,
+    ): void;
+
+    /**
+     * Stops the worker once it is done with what it is running right now.
+     */
+    terminate(): void;
+  }
+}
+
+export {};
@@ -0,0 +1,18 @@
+import { describe, expect, it } from '@std/test';
+import math, { add, answer, double, filename } from './commonjs/math.js';
+
+describe('package.json type', () => {
+  it('expect commonjs modules to expose module.exports as default export', () => {
+    expect(math.add(1, 2)).toBe(3);
+  });
+
+  it('expect own properties of module.exports to be named exports', () => {
+    expect(add(2, 3)).toBe(5);
+    expect(double(4)).toBe(8);
+  });
+
+  it('expect require to load commonjs and json files', () => {
+    expect(answer).toBe(42);
+    expect(filename.endsWith('math.js')).toBe(true);
+  });
+});
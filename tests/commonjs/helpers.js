@@ -0,0 +1,3 @@
+module.exports = {
+  double: (n) => n * 2,
+};
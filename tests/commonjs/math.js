@@ -0,0 +1,6 @@
+const { double } = require('./helpers.js');
+
+exports.add = (a, b) => a + b;
+exports.double = double;
+exports.answer = require('./data.json').answer;
+exports.filename = __filename;
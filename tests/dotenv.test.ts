@@ -0,0 +1,49 @@
+import { load, parse } from '@std/dotenv';
+import { describe, expect, it } from '@std/test';
+
+describe('@std/dotenv', () => {
+  it('expect unquoted values to be trimmed and comments to be ignored', () => {
+    const vars = parse('# comment\nNAME = ike # inline\nexport EMPTY=\n');
+
+    expect(vars.NAME).toBe('ike');
+    expect(vars.EMPTY).toBe('');
+  });
+
+  it('expect double quoted values to understand escapes', () => {
+    const vars = parse('GREETING="hello\\n\\"world\\""');
+
+    expect(vars.GREETING).toBe('hello\n"world"');
+  });
+
+  it('expect single quoted values to be taken as they are', () => {
+    const vars = parse("RAW='${NAME}\\n'");
+
+    expect(vars.RAW).toBe('${NAME}\\n');
+  });
+
+  it('expect quoted values to span several lines', () => {
+    const vars = parse('KEY="first\nsecond"\nNEXT=1');
+
+    expect(vars.KEY).toBe('first\nsecond');
+    expect(vars.NEXT).toBe('1');
+  });
+
+  it('expect variables to be expanded', () => {
+    const vars = parse('NAME=ike\nGREETING=hello ${NAME}\nFALLBACK=${IKE_MISSING_VAR:-none}');
+
+    expect(vars.GREETING).toBe('hello ike');
+    expect(vars.FALLBACK).toBe('none');
+  });
+
+  it('expect invalid sources to throw', () => {
+    expect(() => parse('not a variable')).toThrow();
+    expect(() => parse('KEY="unterminated')).toThrow();
+  });
+
+  it('expect load to add the variables to Ike.env', () => {
+    const vars = load({ path: 'tests/dotenv/.env' });
+
+    expect(vars.IKE_DOTENV_GREETING).toBe('hello ike');
+    expect(Ike.env.IKE_DOTENV_NAME).toBe('ike');
+  });
+});
@@ -28,4 +28,43 @@ describe('Ike', () => {
   it('expect env to be defined', () => {
     expect(Ike.env).toBeDefined();
   });
+
+  it('expect env to read and write the process environment', () => {
+    Ike.env.IKE_TEST_LIVE_ENV = 'live';
+
+    expect(Ike.env.IKE_TEST_LIVE_ENV).toBe('live');
+    expect('IKE_TEST_LIVE_ENV' in Ike.env).toBe(true);
+    expect(Object.keys(Ike.env).includes('IKE_TEST_LIVE_ENV')).toBe(true);
+    expect({ ...Ike.env }.IKE_TEST_LIVE_ENV).toBe('live');
+
+    delete Ike.env.IKE_TEST_LIVE_ENV;
+
+    expect(Ike.env.IKE_TEST_LIVE_ENV).toBeUndefined();
+    expect('IKE_TEST_LIVE_ENV' in Ike.env).toBe(false);
+  });
+
+  it('expect env to convert values to strings', () => {
+    (Ike.env as any).IKE_TEST_NUMBER = 42;
+
+    expect(Ike.env.IKE_TEST_NUMBER).toBe('42');
+    delete Ike.env.IKE_TEST_NUMBER;
+  });
+
+  it('expect env to reject invalid names', () => {
+    expect(() => {
+      Ike.env['INVALID=NAME'] = 'value';
+    }).toThrow();
+    expect(() => {
+      Ike.env[''] = 'value';
+    }).toThrow();
+  });
+
+  it('expect Ike.features to be a frozen array', () => {
+    expect(Array.isArray(Ike.features)).toBe(true);
+    expect(Object.isFrozen(Ike.features)).toBe(true);
+    expect(() => {
+      'use strict';
+      (Ike as any).features = ['other'];
+    }).toThrow();
+  });
 });
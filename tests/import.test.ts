@@ -0,0 +1,49 @@
+import { describe, expect, it } from '@std/test';
+
+const name = 'counter';
+const relative = await import('./import/counter.ts');
+const computed = await import(
+  Ike.path.join(import.meta.dir, 'import', `${name}.ts`)
+);
+const fileUrl = await import(import.meta.resolve('./import/counter.ts'));
+const [first, second] = await Promise.all([
+  import('./import/counter.ts'),
+  import('./import/counter.ts'),
+]);
+const pathA = await import('@std/path');
+const pathB = await import('@std/path');
+
+let missingError: unknown;
+try {
+  await import('./import/missing.ts');
+} catch (e) {
+  missingError = e;
+}
+
+describe('dynamic import', () => {
+  it('expect to import a relative module', () => {
+    expect(relative.value).toBe('counter');
+  });
+
+  it('expect to import a computed path', () => {
+    expect(computed.value).toBe('counter');
+  });
+
+  it('expect to import a file URL', () => {
+    expect(fileUrl.value).toBe('counter');
+  });
+
+  it('expect the same module to be evaluated only once', () => {
+    expect(first).toBe(second);
+    expect(relative).toBe(first);
+    expect(globalThis.IMPORT_COUNTER).toBe(1);
+  });
+
+  it('expect builtin modules to be cached', () => {
+    expect(pathA).toBe(pathB);
+  });
+
+  it('expect to reject for missing modules', () => {
+    expect(missingError).toBeDefined();
+  });
+});
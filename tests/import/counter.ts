@@ -0,0 +1,3 @@
+globalThis.IMPORT_COUNTER = (globalThis.IMPORT_COUNTER ?? 0) + 1;
+
+export const value = 'counter';
@@ -0,0 +1,27 @@
+import { describe, expect, it } from '@std/test';
+import { meta } from './meta/imported.ts';
+
+describe('import.meta', () => {
+  it('expect file information to be defined', () => {
+    expect(import.meta.file).toBe('meta.test.ts');
+    expect(import.meta.path).toBe(import.meta.filename);
+    expect(import.meta.dir).toBe(import.meta.dirname);
+    expect(import.meta.url.startsWith('file://')).toBeTruthy();
+  });
+
+  it('expect only the entry module to be main', () => {
+    expect(import.meta.main).toBe(true);
+    expect(meta.main).toBe(false);
+  });
+
+  it('expect imported modules to have their own import.meta', () => {
+    expect(meta.file).toBe('imported.ts');
+    expect(meta.dir).toBe(Ike.path.join(import.meta.dir, 'meta'));
+  });
+
+  it('expect resolve to use module resolution', () => {
+    expect(import.meta.resolve('./meta/imported.ts')).toBe(meta.url);
+    expect(import.meta.resolve('@std/path')).toBe('@std/path');
+    expect(() => import.meta.resolve('./does-not-exist.ts')).toThrow();
+  });
+});
@@ -0,0 +1,23 @@
+import { describe, expect, it } from '@std/test';
+import { VERSION, greet } from './packages/app/index.ts';
+
+let missingError: unknown;
+try {
+  await import('./packages/app/missing.ts');
+} catch (e) {
+  missingError = e;
+}
+
+describe('ike.toml dependencies', () => {
+  it('expect path dependencies to resolve to package.main', () => {
+    expect(greet('Ike')).toBe('Hello, Ike!');
+  });
+
+  it('expect subpaths to resolve through exports', () => {
+    expect(VERSION).toBe('0.1.0');
+  });
+
+  it('expect missing exports to throw', () => {
+    expect(missingError).toBeDefined();
+  });
+});
@@ -0,0 +1,2 @@
+export { greet } from 'lib';
+export { VERSION } from 'lib/version';
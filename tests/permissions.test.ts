@@ -0,0 +1,51 @@
+import { describe, expect, it } from '@std/test';
+
+describe('Ike.permissions', () => {
+  it('expect everything to be granted outside of a sandbox', async () => {
+    const read = await Ike.permissions.query({ name: 'read', path: '/' });
+    const env = await Ike.permissions.query({ name: 'env', variable: 'HOME' });
+    const run = await Ike.permissions.query({ name: 'run' });
+
+    expect(read.state).toBe('granted');
+    expect(env.state).toBe('granted');
+    expect(run.state).toBe('granted');
+  });
+
+  it('expect request to resolve without asking when granted', async () => {
+    const status = await Ike.permissions.request({ name: 'net' });
+
+    expect(status.state).toBe('granted');
+  });
+
+  it('expect an unknown permission name to reject', async () => {
+    let error: unknown;
+    try {
+      // @ts-expect-error
+      await Ike.permissions.query({ name: 'camera' });
+    } catch (e) {
+      error = e;
+    }
+
+    expect(error instanceof TypeError).toBe(true);
+  });
+});
+
+describe('--sandbox', () => {
+  const script = Ike.path.join(import.meta.dir, 'permissions', 'sandboxed.ts');
+
+  it('expect fs, env, spawn and shell builtins to be denied', async () => {
+    const { stdout, success } = await Ike.exec(Ike.execPath, [
+      'run',
+      '--sandbox',
+      script,
+    ]);
+    const results = JSON.parse(stdout);
+
+    expect(success).toBe(true);
+    expect(results.read).toBe('PermissionDenied');
+    expect(results.write).toBe('PermissionDenied');
+    expect(results.env).toBe('PermissionDenied');
+    expect(results.spawn).toBe('PermissionDenied');
+    expect(results.shell).toMatch(/^ls: Requires read access/);
+  });
+});
@@ -0,0 +1,26 @@
+import { $ } from '@std/shell';
+
+// Run with --sandbox by permissions.test.ts, prints what each operation threw
+const attempt = async (run: () => unknown): Promise<string> => {
+  try {
+    await run();
+    return 'granted';
+  } catch (e) {
+    return e instanceof Error ? e.name : String(e);
+  }
+};
+
+const file = Ike.path.join(import.meta.dir, 'sandboxed.ts');
+
+// Builtins report errors on stderr like commands do
+const shell = await $`ls ${import.meta.dir}`.nothrow();
+
+console.log(
+  JSON.stringify({
+    read: await attempt(() => Ike.readFileSync(file)),
+    write: await attempt(() => Ike.createFileSync(`${file}.tmp`)),
+    env: await attempt(() => Ike.env.HOME),
+    spawn: await attempt(() => Ike.spawnSync(Ike.execPath, ['--version'])),
+    shell: new TextDecoder().decode(shell.stderr),
+  }),
+);
@@ -0,0 +1,109 @@
+import { $, ShellError, escape } from '@std/shell';
+import { describe, expect, it } from '@std/test';
+
+const dir = Ike.path.join(import.meta.dir, 'shell-tmp');
+const sh = $.cwd(import.meta.dir);
+const unix = !Ike.isWindows();
+
+describe('@std/shell', () => {
+  it('expect builtins to run on every platform', async () => {
+    expect(await sh`echo hello world`.text()).toBe('hello world\n');
+    expect(await sh`echo -n hello`.text()).toBe('hello');
+  });
+
+  it('expect interpolated values to be single arguments', async () => {
+    const value = 'a  b; rm -rf / && echo $HOME *';
+
+    expect(await sh`echo ${value}`.text()).toBe(`${value}\n`);
+    expect(await sh`echo ${['one', 'two three']}`.lines()).toBe([
+      'one two three',
+    ]);
+  });
+
+  it('expect quotes and variables to be expanded', async () => {
+    const text = await sh`NAME=ike; echo "hi $NAME" 'no $NAME' \$NAME`
+      .env({ NAME: 'env' })
+      .text();
+
+    expect(text).toBe('hi ike no $NAME $NAME\n');
+  });
+
+  it('expect comments to drop interpolated values', async () => {
+    const text = await sh`echo one # ${'two'} ${['three']}
+echo four`.text();
+
+    expect(text).toBe('one\nfour\n');
+  });
+
+  it('expect builtins to work with files and redirections', async () => {
+    await sh`rm -rf ${dir} && mkdir -p ${dir}/nested`;
+    await sh`echo first > ${dir}/file.txt; echo second >> ${dir}/file.txt`;
+
+    expect(await sh`cat ${dir}/file.txt`.lines()).toBe(['first', 'second']);
+    expect(await sh`cd ${dir} && ls`.lines()).toBe(['file.txt', 'nested']);
+    expect(await sh`cat < ${dir}/file.txt`.text()).toBe('first\nsecond\n');
+
+    await sh`rm -r ${dir}`;
+    expect(Ike.existsSync(dir)).toBe(false);
+  });
+
+  it('expect globs to match files', async () => {
+    const files = await $.cwd(Ike.path.join(import.meta.dir, 'dotenv'))`ls .*`
+      .lines();
+
+    expect(files.includes('.env')).toBe(true);
+    expect(await sh`echo no-match-*.none`.text()).toBe('no-match-*.none\n');
+  });
+
+  it.if(unix, 'expect commands to be piped', async () => {
+    const lines = await sh`printf 'b\na\nc\n' | sort | head -n 2`.lines();
+
+    expect(lines).toBe(['a', 'b']);
+  });
+
+  it.if(unix, 'expect stderr to be redirected to stdout', async () => {
+    const output = await sh`sh -c 'echo out; echo err >&2' 2>&1`;
+
+    expect(output.text()).toBe('out\nerr\n');
+    expect(output.stderr.length).toBe(0);
+  });
+
+  it.if(unix, 'expect output to be parsed as JSON', async () => {
+    const json = await sh`printf '{"name":"ike"}'`.json();
+
+    expect(json.name).toBe('ike');
+  });
+
+  it('expect non-zero exit codes to throw', async () => {
+    let error: unknown;
+    try {
+      await sh`cd does-not-exist`;
+    } catch (e) {
+      error = e;
+    }
+
+    expect(error instanceof ShellError).toBe(true);
+    expect((error as ShellError).exitCode).toBe(1);
+  });
+
+  it('expect nothrow to resolve with the exit code', async () => {
+    const { exitCode } = await sh`ike-command-that-does-not-exist`.nothrow();
+    expect(exitCode).toBe(127);
+
+    const output = await sh.nothrow()`cd does-not-exist || echo fallback`;
+    expect(output.exitCode).toBe(0);
+    expect(output.text()).toBe('fallback\n');
+  });
+
+  it('expect escape to quote strings', () => {
+    expect(escape('plain')).toBe('plain');
+    expect(escape("it's here")).toBe("'it'\\''s here'");
+    expect(escape('')).toBe("''");
+  });
+
+  it('expect invalid scripts to throw', () => {
+    expect(() => sh`echo "open`).toThrow();
+    expect(() => sh`echo a |`).toThrow();
+    expect(() => sh`sleep 1 &`).toThrow();
+  });
+});
@@ -0,0 +1,81 @@
+import { describe, expect, it } from '@std/test';
+
+const unix = !Ike.isWindows();
+
+describe('Ike.addSignalListener', () => {
+  it.if(unix, 'expect listeners to be called with the signal', async () => {
+    const received = new Promise<string>((resolve) => {
+      const listener = (signal: string) => {
+        Ike.removeSignalListener('SIGUSR1', listener);
+        resolve(signal);
+      };
+      Ike.addSignalListener('SIGUSR1', listener);
+    });
+
+    Ike.kill(Ike.pid, 'SIGUSR1');
+
+    expect(await received).toBe('SIGUSR1');
+  });
+
+  it.if(unix, 'expect every listener of a signal to be called', async () => {
+    const calls: string[] = [];
+    const received = new Promise<void>((resolve) => {
+      const first = () => calls.push('first');
+      const second = () => {
+        calls.push('second');
+        Ike.removeSignalListener('SIGUSR2', first);
+        Ike.removeSignalListener('SIGUSR2', second);
+        resolve();
+      };
+      Ike.addSignalListener('SIGUSR2', first);
+      Ike.addSignalListener('SIGUSR2', first);
+      Ike.addSignalListener('SIGUSR2', second);
+    });
+
+    Ike.kill(Ike.pid, 'SIGUSR2');
+    await received;
+
+    expect(calls).toBe(['first', 'second']);
+  });
+
+  it.if(unix, 'expect signals to reach listeners in workers too', async () => {
+    const worker = new Worker(new URL('./signal/worker.ts', import.meta.url));
+    const messages: string[] = [];
+    const received = new Promise<void>((resolve) => {
+      worker.onmessage = (event) => {
+        messages.push(event.data);
+        if (event.data === 'listening') {
+          Ike.kill(Ike.pid, 'SIGUSR1');
+        } else {
+          resolve();
+        }
+      };
+    });
+    const inMain = new Promise<string>((resolve) => {
+      const listener = (signal: string) => {
+        Ike.removeSignalListener('SIGUSR1', listener);
+        resolve(signal);
+      };
+      Ike.addSignalListener('SIGUSR1', listener);
+    });
+
+    await received;
+    worker.terminate();
+
+    expect(messages).toBe(['listening', 'SIGUSR1']);
+    expect(await inMain).toBe('SIGUSR1');
+  });
+
+  it('expect unknown signals to throw', () => {
+    expect(() => Ike.addSignalListener('SIGNOPE' as any, () => {})).toThrow();
+    expect(() => Ike.addSignalListener('SIGKILL', () => {})).toThrow();
+    expect(() => Ike.kill(Ike.pid, 'SIGNOPE' as any)).toThrow();
+  });
+
+  it.if(unix, 'expect kill to signal other processes', async () => {
+    const child = Ike.spawn('sleep', ['10']);
+    Ike.kill(child.pid, 'SIGKILL');
+
+    expect((await child.status).signal).toBe('SIGKILL');
+  });
+});
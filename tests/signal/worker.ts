@@ -0,0 +1,9 @@
+declare function postMessage(message: unknown): void;
+
+const listener = (signal: string) => {
+  Ike.removeSignalListener('SIGUSR1', listener);
+  postMessage(signal);
+};
+Ike.addSignalListener('SIGUSR1', listener);
+
+postMessage('listening');
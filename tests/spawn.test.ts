@@ -0,0 +1,97 @@
+import { describe, expect, it } from '@std/test';
+
+const unix = !Ike.isWindows();
+const fixtures = Ike.path.join(import.meta.dir, 'spawn');
+
+describe('Ike.spawn', () => {
+  it.if(unix, 'expect exec to collect the output', async () => {
+    const { code, success, stdout, stderr } = await Ike.exec('echo', ['hello']);
+
+    expect(code).toBe(0);
+    expect(success).toBe(true);
+    expect(stdout).toBe('hello\n');
+    expect(stderr).toBe('');
+  });
+
+  it.if(unix, 'expect exec to report a failing exit code', async () => {
+    const { code, success } = await Ike.exec('sh', ['-c', 'exit 3']);
+
+    expect(code).toBe(3);
+    expect(success).toBe(false);
+  });
+
+  it.if(unix, 'expect spawnSync to return the output as bytes', () => {
+    const result = Ike.spawnSync('sh', ['-c', 'printf out; printf err >&2']);
+
+    expect(result.success).toBe(true);
+    expect(new TextDecoder().decode(result.stdout!)).toBe('out');
+    expect(new TextDecoder().decode(result.stderr!)).toBe('err');
+  });
+
+  it.if(unix, 'expect options to set cwd and env', async () => {
+    const { stdout } = await Ike.exec('sh', ['-c', 'echo "$IKE_SPAWN_TEST $(pwd)"'], {
+      cwd: '/',
+      env: { IKE_SPAWN_TEST: 'value' },
+    });
+
+    expect(stdout).toBe('value /\n');
+  });
+
+  it.if(unix, 'expect stdin to be piped into the child', async () => {
+    const child = Ike.spawn('cat', [], { stdin: 'pipe' });
+
+    const writer = child.stdin!.getWriter();
+    await writer.write('piped');
+    await writer.close();
+
+    const reader = child.stdout!.getReader();
+    const { value } = await reader.read();
+
+    expect(new TextDecoder().decode(value)).toBe('piped');
+    expect((await child.status).success).toBe(true);
+  });
+
+  it.if(unix, 'expect kill to terminate the child', async () => {
+    const child = Ike.spawn('sleep', ['10']);
+    child.kill();
+
+    const status = await child.status;
+    expect(status.success).toBe(false);
+    expect(status.signal).toBe('SIGTERM');
+  });
+
+  it('expect unknown commands to throw', () => {
+    expect(() => Ike.spawn('ike-command-that-does-not-exist')).toThrow();
+  });
+
+  it.if(unix, 'expect bare commands to ignore the cwd option', async () => {
+    const { stdout } = await Ike.exec('echo', ['safe'], { cwd: fixtures });
+
+    expect(stdout).toBe('safe\n');
+  });
+
+  it.if(unix, 'expect the working directory to be ignored', async () => {
+    const { stdout } = await Ike.exec(Ike.execPath, ['run', 'bare.ts'], {
+      cwd: fixtures,
+    });
+
+    expect(stdout).toBe('safe\n');
+  });
+
+  it.if(unix, 'expect relative paths to be resolved against cwd', async () => {
+    const { stdout } = await Ike.exec('./echo', [], { cwd: fixtures });
+
+    expect(stdout).toBe('hijacked\n');
+  });
+
+  it('expect a missing PATH to not find commands', () => {
+    const path = Ike.env.PATH;
+    delete Ike.env.PATH;
+
+    try {
+      expect(() => Ike.spawn('echo')).toThrow();
+    } finally {
+      Ike.env.PATH = path;
+    }
+  });
+});
@@ -0,0 +1,4 @@
+// Run from this directory by spawn.test.ts, `echo` must come from PATH
+const { stdout } = await Ike.exec('echo', ['safe']);
+
+console.log(stdout.trim());
@@ -0,0 +1,18 @@
+import { describe, expect, it } from '@std/test';
+
+describe('@std/test', () => {
+  it('expect async tests to be awaited', async () => {
+    const value = await Promise.resolve(42);
+
+    expect(value).toBe(42);
+  });
+
+  it('expect values set after an await to be seen', async () => {
+    let done = false;
+    await Promise.resolve().then(() => {
+      done = true;
+    });
+
+    expect(done).toBe(true);
+  });
+});
@@ -0,0 +1,130 @@
+import { describe, expect, it } from '@std/test';
+
+describe('EventTarget', () => {
+  it('expect listeners to run in order with the event', () => {
+    const target = new EventTarget();
+    const calls: string[] = [];
+
+    target.addEventListener('ping', (event) => {
+      calls.push(`first ${event.type}`);
+      expect(event.target).toBe(target);
+    });
+    target.addEventListener('ping', {
+      handleEvent: (event) => calls.push(`second ${event.type}`),
+    });
+
+    expect(target.dispatchEvent(new Event('ping'))).toBe(true);
+    expect(calls).toBe(['first ping', 'second ping']);
+  });
+
+  it('expect the same listener to be added once', () => {
+    const target = new EventTarget();
+    let count = 0;
+    const listener = () => count++;
+
+    target.addEventListener('ping', listener);
+    target.addEventListener('ping', listener);
+    target.dispatchEvent(new Event('ping'));
+
+    expect(count).toBe(1);
+  });
+
+  it('expect removed and once listeners to stop running', () => {
+    const target = new EventTarget();
+    let removed = 0;
+    let once = 0;
+    const listener = () => removed++;
+
+    target.addEventListener('ping', listener);
+    target.addEventListener('ping', () => once++, { once: true });
+    target.dispatchEvent(new Event('ping'));
+    target.removeEventListener('ping', listener);
+    target.dispatchEvent(new Event('ping'));
+
+    expect(removed).toBe(1);
+    expect(once).toBe(1);
+  });
+
+  it('expect preventDefault to cancel only cancelable events', () => {
+    const target = new EventTarget();
+    target.addEventListener('ping', (event) => event.preventDefault());
+
+    expect(target.dispatchEvent(new Event('ping'))).toBe(true);
+    expect(target.dispatchEvent(new Event('ping', { cancelable: true }))).toBe(
+      false,
+    );
+  });
+
+  it('expect every listener to run before an error is rethrown', () => {
+    const target = new EventTarget();
+    let ran = false;
+
+    target.addEventListener('ping', () => {
+      throw new Error('boom');
+    });
+    target.addEventListener('ping', () => {
+      ran = true;
+    });
+
+    expect(() => target.dispatchEvent(new Event('ping'))).toThrow();
+    expect(ran).toBe(true);
+  });
+
+  it('expect CustomEvent to carry its detail', () => {
+    const event = new CustomEvent('ping', { detail: { id: 1 } });
+
+    expect(event.detail).toBe({ id: 1 });
+    expect(new CustomEvent('ping').detail).toBe(null);
+  });
+});
+
+describe('globalThis events', () => {
+  it('expect events to be dispatched on globalThis', () => {
+    let target: EventTarget | null = null;
+    const listener = (event: Event) => {
+      target = event.target;
+    };
+
+    addEventListener('ping', listener);
+    dispatchEvent(new Event('ping'));
+    removeEventListener('ping', listener);
+
+    expect(target === globalThis).toBe(true);
+  });
+});
+
+describe('unhandledrejection', () => {
+  const tick = () => new Promise((resolve) => setTimeout(resolve, 10));
+
+  it('expect a rejection without a handler to dispatch it', async () => {
+    const reason = new Error('nothing handles this');
+    const events: PromiseRejectionEvent[] = [];
+    const listener = (event: Event) => {
+      events.push(event as PromiseRejectionEvent);
+      event.preventDefault();
+    };
+
+    addEventListener('unhandledrejection', listener);
+    const promise = Promise.reject(reason);
+    await tick();
+    removeEventListener('unhandledrejection', listener);
+
+    expect(events.length).toBe(1);
+    expect(events[0].promise === promise).toBe(true);
+    expect(events[0].reason === reason).toBe(true);
+    expect(events[0].cancelable).toBe(true);
+  });
+
+  it('expect a rejection handled in time to not dispatch it', async () => {
+    let count = 0;
+    const listener = () => count++;
+
+    addEventListener('unhandledrejection', listener);
+    const promise = Promise.reject(new Error('handled'));
+    promise.catch(() => {});
+    await tick();
+    removeEventListener('unhandledrejection', listener);
+
+    expect(count).toBe(0);
+  });
+});
@@ -0,0 +1,109 @@
+import { describe, expect, it } from '@std/test';
+
+const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+describe('setTimeout', () => {
+  it('expect a delay of 0 to run after the current job', async () => {
+    const calls: string[] = [];
+
+    setTimeout(() => calls.push('timeout'), 0);
+    calls.push('sync');
+    expect(calls).toBe(['sync']);
+
+    await sleep(5);
+    expect(calls).toBe(['sync', 'timeout']);
+  });
+
+  it('expect the arguments to be passed to the callback', async () => {
+    let received: unknown[] = [];
+
+    setTimeout((a: number, b: string) => (received = [a, b]), 0, 1, 'two');
+    await sleep(5);
+
+    expect(received).toBe([1, 'two']);
+  });
+
+  it('expect clearTimeout to cancel the timer and accept its id', async () => {
+    let count = 0;
+
+    clearTimeout(setTimeout(() => count++, 0));
+    clearTimeout(Number(setTimeout(() => count++, 0)));
+    await sleep(5);
+
+    expect(count).toBe(0);
+  });
+
+  it('expect timer objects to be ref and unref', () => {
+    const timer = setTimeout(() => {}, 0);
+
+    expect(timer.hasRef()).toBe(true);
+    expect(timer.unref().hasRef()).toBe(false);
+    expect(timer.ref().hasRef()).toBe(true);
+    clearTimeout(timer);
+  });
+
+  it('expect refresh to schedule a fired timer again', async () => {
+    let count = 0;
+    const timer = setTimeout(() => count++, 0);
+
+    await sleep(5);
+    timer.refresh();
+    await sleep(5);
+
+    expect(count).toBe(2);
+  });
+
+  it('expect a callback that is not a function to throw', () => {
+    // @ts-expect-error
+    expect(() => setTimeout('code', 0)).toThrow();
+  });
+});
+
+describe('setInterval', () => {
+  it('expect the callback to run until the interval is cleared', async () => {
+    let count = 0;
+    const interval = setInterval(() => {
+      count++;
+      if (count === 3) {
+        clearInterval(interval);
+      }
+    }, 1);
+
+    await sleep(30);
+    expect(count).toBe(3);
+  });
+});
+
+describe('setImmediate', () => {
+  it('expect the callback to run after the current job', async () => {
+    const calls: string[] = [];
+
+    setImmediate(() => calls.push('immediate'));
+    calls.push('sync');
+    await sleep(5);
+
+    expect(calls).toBe(['sync', 'immediate']);
+  });
+
+  it('expect clearImmediate to cancel the callback', async () => {
+    let count = 0;
+
+    clearImmediate(setImmediate(() => count++));
+    await sleep(5);
+
+    expect(count).toBe(0);
+  });
+});
+
+describe('queueMicrotask', () => {
+  it('expect microtasks to run before timers', async () => {
+    const calls: string[] = [];
+
+    setTimeout(() => calls.push('timeout'), 0);
+    queueMicrotask(() => calls.push('microtask'));
+    calls.push('sync');
+    await sleep(5);
+
+    expect(calls).toBe(['sync', 'microtask', 'timeout']);
+  });
+});
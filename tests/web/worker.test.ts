@@ -0,0 +1,120 @@
+import { describe, expect, it } from '@std/test';
+
+const echo = new URL('./worker/echo.ts', import.meta.url);
+
+const nextMessage = (worker: Worker) =>
+  new Promise<MessageEvent>((resolve) => {
+    worker.addEventListener('message', resolve, { once: true });
+  });
+
+const roundTrip = async (value: unknown, transfer?: ArrayBuffer[]) => {
+  const worker = new Worker(echo);
+  const received = nextMessage(worker);
+
+  worker.postMessage(value, transfer);
+  const { data } = await received;
+  worker.terminate();
+
+  return data.data;
+};
+
+describe('Worker', () => {
+  it('expect messages to reach the worker and come back', async () => {
+    const worker = new Worker(echo, { name: 'echo' });
+    const received = nextMessage(worker);
+
+    worker.postMessage('ping');
+    const event = await received;
+    worker.terminate();
+
+    expect(event instanceof MessageEvent).toBe(true);
+    expect(event.data).toBe({ name: 'echo', data: 'ping' });
+  });
+
+  it('expect onmessage to receive messages', async () => {
+    const worker = new Worker(echo);
+    const received = new Promise((resolve) => {
+      worker.onmessage = (event) => resolve(event.data.data);
+    });
+
+    worker.postMessage(1);
+    expect(await received).toBe(1);
+    worker.terminate();
+  });
+
+  it('expect values to be structured clones', async () => {
+    const cyclic: Record<string, unknown> = { list: [1, 'two', null] };
+    cyclic.self = cyclic;
+
+    const data = await roundTrip({
+      cyclic,
+      date: new Date(0),
+      map: new Map([['key', 1n]]),
+      set: new Set([Number.NaN, -0]),
+      error: new TypeError('cloned'),
+      bytes: new Uint8Array([1, 2, 3]),
+    });
+
+    expect(data.cyclic.self).toBe(data.cyclic);
+    expect(data.cyclic.list).toBe([1, 'two', null]);
+    expect(data.date.getTime()).toBe(0);
+    expect(data.map.get('key')).toBe(1n);
+    expect([...data.set].map(String)).toBe(['NaN', '0']);
+    expect(Object.is([...data.set][1], -0)).toBe(true);
+    expect(data.error instanceof TypeError).toBe(true);
+    expect(data.error.message).toBe('cloned');
+    expect(data.bytes instanceof Uint8Array).toBe(true);
+    expect([...data.bytes]).toBe([1, 2, 3]);
+  });
+
+  it('expect functions and symbols to throw a DataCloneError', () => {
+    const worker = new Worker(echo);
+
+    expect(() => worker.postMessage(() => {})).toThrow();
+    expect(() => worker.postMessage({ symbol: Symbol('no') })).toThrow();
+    worker.terminate();
+  });
+
+  it('expect transferred buffers to be moved', async () => {
+    const buffer = new Uint8Array([4, 5, 6]).buffer;
+
+    const data = await roundTrip(buffer, [buffer]);
+
+    expect([...new Uint8Array(data)]).toBe([4, 5, 6]);
+    expect(buffer.byteLength).toBe(0);
+  });
+
+  it('expect close to stop the worker', async () => {
+    const worker = new Worker(echo);
+    worker.postMessage('close');
+    worker.postMessage('ignored');
+
+    let received = false;
+    worker.onmessage = () => (received = true);
+    await new Promise((resolve) => setTimeout(resolve, 50));
+
+    expect(received).toBe(false);
+  });
+
+  it('expect uncaught errors to be dispatched on the worker', async () => {
+    const worker = new Worker(new URL('./worker/throws.ts', import.meta.url));
+
+    const event = await new Promise<ErrorEvent>((resolve) => {
+      worker.onerror = (event) => {
+        event.preventDefault();
+        resolve(event);
+      };
+    });
+
+    worker.terminate();
+
+    expect(event instanceof ErrorEvent).toBe(true);
+    expect(event.message.includes('thrown in a worker')).toBe(true);
+    expect(event.filename.endsWith('throws.ts')).toBe(true);
+  });
+
+  it('expect classic workers to throw', () => {
+    // @ts-expect-error
+    expect(() => new Worker(echo, { type: 'classic' })).toThrow();
+  });
+});
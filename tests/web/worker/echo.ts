@@ -0,0 +1,13 @@
+declare const self: { onmessage: ((event: MessageEvent) => void) | null };
+declare const name: string;
+declare function postMessage(message: unknown): void;
+declare function close(): void;
+
+self.onmessage = (event) => {
+  if (event.data === 'close') {
+    close();
+    return;
+  }
+
+  postMessage({ name, data: event.data });
+};
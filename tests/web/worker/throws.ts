@@ -0,0 +1 @@
+throw new Error('thrown in a worker');
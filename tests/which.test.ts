@@ -1,3 +1,19 @@
-import { describe, beforeAll, afterAll, it } from '@std/test';
+import { describe, expect, it } from '@std/test';
 
-describe('Ike.which', () => {});
+const unix = !Ike.isWindows();
+const fixtures = Ike.path.join(import.meta.dir, 'spawn');
+
+describe('Ike.which', () => {
+  it.if(unix, 'expect bare names to be looked up in PATH only', () => {
+    expect(Ike.which('echo', { cwd: fixtures })).notToBe(
+      Ike.path.join(fixtures, 'echo'),
+    );
+    expect(Ike.which('echo', { cwd: fixtures, path: '' })).toBeNull();
+  });
+
+  it.if(unix, 'expect relative paths to be resolved against cwd', () => {
+    expect(Ike.which('./echo', { cwd: fixtures })).toBe(
+      Ike.path.join(fixtures, 'echo'),
+    );
+  });
+});
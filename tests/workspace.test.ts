@@ -0,0 +1,8 @@
+import { describe, expect, it } from '@std/test';
+import { sum } from './workspace/packages/calc/index.ts';
+
+describe('workspaces', () => {
+  it('expect workspace members to resolve to their sources', () => {
+    expect(sum(1, 2, 3)).toBe(6);
+  });
+});
@@ -0,0 +1,5 @@
+import { add } from '@workspace/math';
+
+export function sum(...numbers: number[]): number {
+  return numbers.reduce(add, 0);
+}